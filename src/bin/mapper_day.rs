@@ -1,9 +1,26 @@
+use std::io;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[macro_use]
 extern crate common;
-use common::scanner::long_range_scanner;
-use common::stylesheet::Stylesheet;
-use common::stylesheet::StyleProperties;
-use common::stylesheet::StyleTransformation;
-use common::stylesheet::StyleColor;
+#[cfg(feature = "ansi_art")]
+use common::ansi_art;
+use common::chart::{self, Bar};
+use common::config::{ConfigError, ConfigFiles, Profile, PROFILE_ENV};
+use common::dashboard::Dashboard;
+use common::heatmap;
+use common::input::{Key, RawMode};
+use common::level_printer::{self, LevelPrinter};
+#[cfg(feature = "qr")]
+use common::qr;
+use common::scanner::{self, long_range_scanner, ScannerSettings};
+use common::shared_stylesheet::SharedStylesheet;
+use common::stylesheet::{ColorMode, Stylesheet};
+use common::term;
+use common::terminal::{self, TerminalSize};
+use common::text;
 
 
 const MAX_PLEASE: u64 = 3;
@@ -14,7 +31,7 @@ const MAX_PLEASE: u64 = 3;
 // and doing nearly nothing with it
 // makes a binary more than 600K bigger.
 extern crate clap;
-use clap::{Arg, ArgMatches, App};
+use clap::{Arg, ArgMatches, App, SubCommand};
 
 
 struct AppInfo<'a> {
@@ -30,6 +47,129 @@ const APP_INFO: AppInfo<'static> = AppInfo {
 };
 
 
+/// Load the profile and the configuration files into sheet, and return
+/// the scanner settings. The precedence, from the lowest, is: the
+/// profile theme, the configuration files, the profile stylesheet and
+/// scanner settings, the files passed on the command line.
+fn load_config(sheet: &mut Stylesheet, options: &ArgMatches) -> Result<ScannerSettings, ConfigError> {
+    let profile = match Profile::selected_name(options.value_of("profile")) {
+        Some(name) => Profile::load(&name)?,
+        None => Profile::default(),
+    };
+    if let Some(theme) = profile.theme {
+        // the sheet is not frozen yet, so this can't fail
+        sheet.load_theme(theme).unwrap();
+    }
+
+    let mut config_files = ConfigFiles::discover();
+    config_files.stylesheets.extend(profile.stylesheet.clone());
+    let settings = config_files.load(sheet)?.merge(&profile.scanner);
+
+    let mut cli_files = ConfigFiles::default();
+    cli_files.stylesheets.extend(options.value_of("styles").map(Into::into));
+    cli_files.scanner_settings.extend(options.value_of("scanner-config").map(Into::into));
+    let settings = settings.merge(&cli_files.load(sheet)?);
+
+    profile.output.apply(sheet);
+    Ok(settings)
+}
+
+
+/// Show the dashboard on the whole screen, scanning every interval,
+/// until the user presses q, Esc or Ctrl+C. Only the events of the levels
+/// that printer shows are added. The dashboard is drawn with styles,
+/// that can change while it runs. Return the dashboard, to print its
+/// statistics on the normal screen.
+fn run_dashboard(
+        sheet: &Stylesheet,
+        styles: &SharedStylesheet,
+        settings: &ScannerSettings,
+        printer: &LevelPrinter,
+        interval: Duration,
+    ) -> io::Result<Dashboard> {
+    if !sheet.is_interactive() {
+        return Err(io::Error::other("The dashboard needs a terminal"));
+    }
+    // the guards are dropped in reverse order: first the cursor is shown,
+    // then the normal screen and the terminal mode are restored
+    let mut raw = RawMode::enable()?;
+    let _screen = term::alternate_screen(sheet)?;
+    let _hidden = term::hide_cursor(sheet)?;
+
+    let mut dashboard = Dashboard::new(format!("{} - long range scanner", APP_INFO.name));
+    let mut next_scan = Instant::now();
+    loop {
+        if Instant::now() >= next_scan {
+            let event = long_range_scanner::scan();
+            if settings.shows(&event) && printer.shows(event.level) {
+                dashboard.push(event);
+                // errors of notifications can't be shown on the dashboard
+                let _ = settings.alert(sheet, &event);
+            }
+            next_scan += interval;
+        }
+        let size = terminal::terminal_size().unwrap_or(TerminalSize { columns: 80, rows: 24 });
+        sheet.try_print_raw(&dashboard.render(&styles.read(), size))?;
+        sheet.flush()?;
+        // redraw at least every 0.2 seconds, to update the elapsed time
+        let timeout = next_scan.saturating_duration_since(Instant::now()).min(Duration::from_millis(200));
+        match raw.poll_key(timeout)? {
+            Some(Key::Char('q')) | Some(Key::Escape) | Some(Key::Ctrl('c')) => return Ok(dashboard),
+            _ => (),
+        }
+    }
+}
+
+
+/// Print the number of events per level as a bar chart, and when they
+/// happened as a heatmap.
+fn print_summary(sheet: &Stylesheet, dashboard: &Dashboard) -> io::Result<()> {
+    sheet.try_println("dashboard_title", format!("Events: {}", dashboard.total()))?;
+    let bars: Vec<Bar> = dashboard.counts().into_iter()
+        .map(|(level, count)| Bar { label: level, value: count as f64, style_name: level })
+        .collect();
+    chart::print_bar_chart(sheet, &bars)?;
+    let width = sheet.output_width();
+    let label_width = bars.iter().map(|bar| text::visible_width(bar.label)).max().unwrap_or(0);
+    let columns = width.saturating_sub(label_width + 1) / heatmap::CELL_WIDTH;
+    sheet.try_println("dashboard_title", "Activity:")?;
+    sheet.try_print_heatmap(&dashboard.activity_heatmap(columns))
+}
+
+
+/// Print an image file with colored characters.
+#[cfg(feature = "ansi_art")]
+fn show_logo(sheet: &Stylesheet, path: &str) {
+    if let Err(err) = ansi_art::print_image_file(sheet, path) {
+        sheet.eprintln("complain", err.to_string());
+        process::exit(1);
+    }
+}
+
+/// Without images, explain how to enable them.
+#[cfg(not(feature = "ansi_art"))]
+fn show_logo(sheet: &Stylesheet, _path: &str) {
+    sheet.eprintln("complain", "Images are not supported: build with the ansi_art feature");
+    process::exit(1);
+}
+
+/// Print a link as a QR code, followed by the link itself.
+#[cfg(feature = "qr")]
+fn show_qr(sheet: &Stylesheet, link: &str) {
+    if let Err(err) = qr::print_qr(sheet, link) {
+        sheet.eprintln("complain", err.to_string());
+        process::exit(1);
+    }
+    sheet.println_link("info", link, link);
+}
+
+/// Without QR codes, only print the link.
+#[cfg(not(feature = "qr"))]
+fn show_qr(sheet: &Stylesheet, link: &str) {
+    sheet.println_link("info", link, link);
+}
+
+
 fn main() {
     let options: ArgMatches = App::new(APP_INFO.name)
         .about(APP_INFO.description)
@@ -42,24 +182,184 @@ fn main() {
             .help("No practical effect, but it's good to be kind. Specify multiple times to implore properly.")
             .takes_value(false)
         )
+        .arg(
+            Arg::with_name("color")
+            .long("color")
+            .help("When to use colors.")
+            .takes_value(true)
+            .possible_values(&ColorMode::NAMES)
+            .default_value("auto")
+        )
+        .arg(
+            Arg::with_name("profile")
+            .long("profile")
+            .help(&format!("A profile to use, like \"bridge\". Default: the value of {}.", PROFILE_ENV))
+            .takes_value(true)
+            .value_name("NAME")
+        )
+        .arg(
+            Arg::with_name("styles")
+            .long("styles")
+            .help("A stylesheet file (TOML, JSON or YAML) that overrides the configured styles.")
+            .takes_value(true)
+            .value_name("FILE")
+        )
+        .arg(
+            Arg::with_name("min-level")
+            .long("min-level")
+            .help("Don't print the events of less important levels.")
+            .takes_value(true)
+            .possible_values(&level_printer::DEFAULT_LEVELS)
+            .default_value("debug")
+        )
+        .arg(
+            Arg::with_name("scanner-config")
+            .long("scanner-config")
+            .help("A TOML file that overrides the configured scanner settings.")
+            .takes_value(true)
+            .value_name("FILE")
+        )
+        .subcommand(
+            SubCommand::with_name("dashboard")
+            .about("Show a live dashboard of the scanned events. Press q to quit. The --styles file is reloaded when it changes.")
+            .arg(
+                Arg::with_name("interval")
+                .long("interval")
+                .help("Milliseconds between two scans.")
+                .takes_value(true)
+                .value_name("MS")
+                .default_value("500")
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("logo")
+            .about("Show a PNG image, like a logo, with colored characters.")
+            .arg(
+                Arg::with_name("file")
+                .help("The image file.")
+                .required(true)
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("qr")
+            .about("Show a link as a QR code, to open it from a phone.")
+            .arg(
+                Arg::with_name("link")
+                .help("The link to show.")
+                .required(true)
+            )
+        )
         .get_matches();
 
-    let mut sheet: Stylesheet = Stylesheet::new();
-    sheet.add_style("danger", StyleProperties {
-        transformation: [StyleTransformation::Bold, StyleTransformation::Blink].to_vec(), color: Some(StyleColor::Red), background: None
-    });
-    sheet.add_style("info", StyleProperties {
-        transformation: [].to_vec(), color: Some(StyleColor::Green), background: None
-    });
-    sheet.add_style("complain", StyleProperties {
-        transformation: [].to_vec(), color: Some(StyleColor::Yellow), background: None
-    });
+    let mut sheet = stylesheet! {
+        danger: bold + blink, red;
+        info: green;
+        complain: yellow;
+        dashboard_title: bold, cyan;
+        status_line: reverse;
+    };
+    scanner::add_threat_styles(&mut sheet);
+    #[cfg(feature = "qr")]
+    qr::add_qr_styles(&mut sheet);
+
+    let settings = match load_config(&mut sheet, &options) {
+        Ok(settings) => settings,
+        Err(err) => {
+            sheet.eprintln("complain", err.to_string());
+            process::exit(1);
+        },
+    };
+
+    // possible_values() guarantees that parsing succeeds;
+    // an explicit --color flag wins over the configuration
+    let color_mode = match settings.color {
+        Some(color_mode) if options.occurrences_of("color") == 0 => color_mode,
+        _ => options.value_of("color").unwrap().parse().unwrap(),
+    };
+    sheet.set_color_mode(color_mode);
+    if let Err(err) = sheet.apply_env_overrides() {
+        sheet.eprintln("complain", err.to_string());
+    }
+    let sheet = sheet.into_frozen();
 
     let please_count: u64 = options.occurrences_of("please");
     if please_count > MAX_PLEASE {
         let message: String = format!("{}{}{}", "You said please ", &please_count, " times... please stop!");
-        sheet.println("complain", message);
+        sheet.eprintln("complain", message);
+    }
+
+    let mut printer = LevelPrinter::default();
+    // possible_values() guarantees that the level is known
+    printer.set_min_level(options.value_of("min-level").unwrap()).unwrap();
+
+    if let Some(dashboard_options) = options.subcommand_matches("dashboard") {
+        let interval = match dashboard_options.value_of("interval").unwrap().parse::<u64>() {
+            Ok(interval) if interval > 0 => Duration::from_millis(interval),
+            _ => {
+                sheet.eprintln("complain", "The interval must be a positive number of milliseconds");
+                process::exit(1);
+            },
+        };
+        // the --styles file can be edited while the dashboard runs
+        let styles = SharedStylesheet::new(sheet.clone_unfrozen());
+        let reload_error = Arc::new(Mutex::new(None));
+        let _watcher = options.value_of("styles").map(|path| {
+            let reload_error = Arc::clone(&reload_error);
+            // errors can't be shown on the dashboard: the last one is shown after it
+            styles.watch_file_over(path, sheet.clone_unfrozen(), Duration::from_secs(1), move |err| {
+                *reload_error.lock().unwrap() = Some(err.to_string());
+            })
+        });
+        let dashboard = match run_dashboard(&sheet, &styles, &settings, &printer, interval) {
+            Ok(dashboard) => dashboard,
+            Err(err) => {
+                sheet.eprintln("complain", err.to_string());
+                process::exit(1);
+            },
+        };
+        if let Some(err) = reload_error.lock().unwrap().take() {
+            sheet.eprintln("complain", format!("Can't reload the styles: {}", err));
+        }
+        let printed = print_summary(&styles.read(), &dashboard);
+        match printed {
+            // the reader went away, for example "mapper_day dashboard | head -1"
+            Err(ref err) if err.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+            Err(err) => {
+                sheet.eprintln("complain", err.to_string());
+                process::exit(1);
+            },
+            Ok(()) => return,
+        }
     }
 
-    long_range_scanner::scan().print(&sheet);
+    if let Some(logo_options) = options.subcommand_matches("logo") {
+        show_logo(&sheet, logo_options.value_of("file").unwrap());
+        return;
+    }
+    if let Some(qr_options) = options.subcommand_matches("qr") {
+        show_qr(&sheet, qr_options.value_of("link").unwrap());
+        return;
+    }
+
+    let event = long_range_scanner::scan();
+    if !settings.shows(&event) {
+        return;
+    }
+    match event.print_with(&sheet, &mut printer) {
+        // alert only about the events the user wants to see
+        Ok(false) => return,
+        Ok(true) => (),
+        Err(err) => {
+            // the reader went away, for example "mapper_day | head -0":
+            // there is nobody to complain to
+            if err.kind() == io::ErrorKind::BrokenPipe {
+                process::exit(0);
+            }
+            sheet.eprintln("complain", err.to_string());
+            process::exit(1);
+        },
+    }
+    if let Err(err) = settings.alert(&sheet, &event) {
+        sheet.eprintln("complain", format!("Can't alert: {}", err));
+    }
 }
@@ -1,3 +1,106 @@
+use std::io;
+use std::process;
+
+#[macro_use]
+extern crate common;
+use common::config::ConfigFiles;
+use common::highlight::HighlightRules;
+use common::stylesheet::ColorMode;
+
+extern crate clap;
+use clap::{Arg, App};
+
+
+const APP_NAME: &str = "reducer_day";
+
+/// The rules used if no --highlight option is given: (pattern, style name).
+const DEFAULT_RULES: [(&str, &str); 3] = [
+    (r"(?i)\b(error|danger|alert)\b", "danger"),
+    (r"(?i)\bwarn(ing)?\b", "warning"),
+    (r"\b\d+(\.\d+)? ?ms\b", "info"),
+];
+
+
+/// Return the highlight rules written as PATTERN=STYLE, or the default
+/// rules if there are none.
+fn highlight_rules<'a, I>(specs: I) -> Result<HighlightRules, String> where I: Iterator<Item = &'a str> {
+    let mut rules = HighlightRules::new();
+    for spec in specs {
+        // patterns can contain "=", style names can't
+        let (pattern, style_name) = spec.rsplit_once('=')
+            .ok_or_else(|| format!("Invalid rule \"{}\": it must be PATTERN=STYLE", spec))?;
+        rules.add_rule(pattern, style_name).map_err(|err| err.to_string())?;
+    }
+    if rules.is_empty() {
+        for (pattern, style_name) in DEFAULT_RULES.iter() {
+            // the default patterns are valid
+            rules.add_rule(pattern, style_name).unwrap();
+        }
+    }
+    Ok(rules)
+}
+
+
 fn main() {
-    println!("Hello, world!");
+    let options = App::new(APP_NAME)
+        .about("Read lines from the standard input and print them highlighted, as a filter in pipelines.")
+        .arg(
+            Arg::with_name("color")
+            .long("color")
+            .help("When to use colors.")
+            .takes_value(true)
+            .possible_values(&ColorMode::NAMES)
+            .default_value("auto")
+        )
+        .arg(
+            Arg::with_name("styles")
+            .long("styles")
+            .help("A stylesheet file (TOML, JSON or YAML) that overrides the configured styles.")
+            .takes_value(true)
+            .value_name("FILE")
+        )
+        .arg(
+            Arg::with_name("highlight")
+            .long("highlight")
+            .help("A rule: the texts that match the regular expression PATTERN get the style STYLE. \
+                   Can be repeated. Default: errors, warnings and durations.")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("PATTERN=STYLE")
+        )
+        .get_matches();
+
+    let mut sheet = stylesheet! {
+        danger: bold, red;
+        warning: yellow;
+        info: green;
+        complain: yellow;
+    };
+
+    let mut files = ConfigFiles::discover();
+    files.stylesheets.extend(options.value_of("styles").map(Into::into));
+    if let Err(err) = files.load(&mut sheet) {
+        sheet.eprintln("complain", err.to_string());
+        process::exit(1);
+    }
+    // possible_values() guarantees that parsing succeeds
+    sheet.set_color_mode(options.value_of("color").unwrap().parse().unwrap());
+    if let Err(err) = sheet.apply_env_overrides() {
+        sheet.eprintln("complain", err.to_string());
+    }
+
+    match highlight_rules(options.values_of("highlight").into_iter().flatten()) {
+        Ok(rules) => sheet.set_highlight_rules(rules),
+        Err(err) => {
+            sheet.eprintln("complain", err);
+            process::exit(1);
+        },
+    }
+
+    let stdin = io::stdin();
+    if let Err(err) = sheet.highlight_lines(stdin.lock()) {
+        sheet.eprintln("complain", err.to_string());
+        process::exit(1);
+    }
 }
@@ -0,0 +1,355 @@
+use stylesheet::{StyleColor, StyleProperties, StyleTransformation};
+
+
+/// A part of a text: characters to show, or an escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Escape(&'a str),
+}
+
+/// An iterator over the segments of a text. Returned by segments().
+#[derive(Debug, Clone)]
+pub struct Segments<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        let (segment, rest) = if self.rest.starts_with(is_escape_start) {
+            let (escape, rest) = self.rest.split_at(escape_len(self.rest));
+            (Segment::Escape(escape), rest)
+        } else {
+            let end = self.rest.find(is_escape_start).unwrap_or(self.rest.len());
+            let (text, rest) = self.rest.split_at(end);
+            (Segment::Text(text), rest)
+        };
+        self.rest = rest;
+        Some(segment)
+    }
+}
+
+/// Return true if c starts an escape sequence: ESC, or the 8-bit
+/// version of CSI.
+fn is_escape_start(c: char) -> bool {
+    c == '\x1b' || c == '\u{9b}'
+}
+
+/// Return the length in bytes of the escape sequence at the beginning
+/// of text. An incomplete sequence at the end of text takes the rest
+/// of the text. Sequences interrupted by an invalid character end
+/// before that character.
+fn escape_len(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    let kind = match chars.next() {
+        Some((_, '\u{9b}')) => '[',
+        _ => match chars.next() {
+            Some((_, c)) => c,
+            None => return text.len(),
+        },
+    };
+    match kind {
+        // CSI: parameters and intermediate bytes, then a final byte
+        '[' => {
+            for (i, c) in chars {
+                if ('\x40'..='\x7e').contains(&c) {
+                    return i + 1;
+                }
+                if !('\x20'..='\x3f').contains(&c) {
+                    return i;
+                }
+            }
+            text.len()
+        },
+        // OSC, DCS, SOS, PM, APC: a string terminated by BEL or ST
+        ']' | 'P' | 'X' | '^' | '_' => {
+            for (i, c) in chars {
+                if c == '\x07' {
+                    return i + 1;
+                }
+                if c == '\x1b' {
+                    return if text[i + 1..].starts_with('\\') { i + 2 } else { i };
+                }
+            }
+            text.len()
+        },
+        // intermediate bytes, then a final byte, like ESC ( B
+        '\x20'..='\x2f' => {
+            for (i, c) in chars {
+                if !('\x20'..='\x2f').contains(&c) {
+                    return i + c.len_utf8();
+                }
+            }
+            text.len()
+        },
+        c => 1 + c.len_utf8(),
+    }
+}
+
+/// Split a text into the characters to show and the escape sequences,
+/// like the ones that change colors or move the cursor. Supported
+/// sequences are CSI sequences, strings like OSC hyperlinks and
+/// window titles, and the short sequences that start with ESC.
+///
+/// # Arguments
+///
+/// * `text` - The text to split.
+///
+/// # Example
+///
+/// ```
+/// use common::ansi::{segments, Segment};
+/// let parts: Vec<Segment> = segments("\x1b[31mred\x1b[0m").collect();
+/// assert_eq!(parts[1], Segment::Text("red"));
+/// ```
+pub fn segments<'a>(text: &'a str) -> Segments<'a> {
+    Segments { rest: text }
+}
+
+/// Return text without escape sequences, for example to measure,
+/// log or restyle the output of another program. See segments().
+///
+/// # Arguments
+///
+/// * `text` - The text to clean.
+///
+/// # Example
+///
+/// ```
+/// use common::ansi;
+/// assert_eq!(ansi::strip("\x1b[1;31mShields down\x1b[0m"), "Shields down");
+/// ```
+pub fn strip(text: &str) -> String {
+    segments(text)
+        .filter_map(|segment| match segment {
+            Segment::Text(text) => Some(text),
+            Segment::Escape(_) => None,
+        })
+        .collect()
+}
+
+/// Return the parameters of an SGR escape sequence, the ones that
+/// change colors and text attributes, like "1;31" for "\x1b[1;31m".
+/// Return None for other sequences.
+///
+/// # Arguments
+///
+/// * `escape` - An escape sequence, as returned by segments().
+///
+/// # Example
+///
+/// ```
+/// use common::ansi;
+/// assert_eq!(ansi::sgr_parameters("\x1b[1;31m"), Some("1;31"));
+/// assert_eq!(ansi::sgr_parameters("\x1b[2K"), None);
+/// ```
+pub fn sgr_parameters(escape: &str) -> Option<&str> {
+    let parameters = escape.strip_prefix("\x1b[").or_else(|| escape.strip_prefix('\u{9b}'))?;
+    let parameters = parameters.strip_suffix('m')?;
+    if parameters.chars().all(|c| c.is_ascii_digit() || c == ';' || c == ':') {
+        Some(parameters)
+    } else {
+        None
+    }
+}
+
+/// Update properties as a terminal would do when it receives an SGR
+/// sequence with these parameters. This allows to know the style of
+/// the texts printed by another program.
+/// Colors from the first 16 palette entries are returned as basic
+/// colors, so "38;5;1" is the same as "31". Unknown parameters are
+/// ignored.
+///
+/// # Arguments
+///
+/// * `parameters` - The SGR parameters, as returned by sgr_parameters().
+/// * `properties` - The current style, which is updated.
+///
+/// # Example
+///
+/// ```
+/// use common::ansi;
+/// use common::stylesheet::*;
+/// let mut properties = StyleProperties::default();
+/// ansi::apply_sgr("1;38;2;255;136;0", &mut properties);
+/// assert_eq!(properties.color, Some(StyleColor::Rgb(255, 136, 0)));
+/// ```
+pub fn apply_sgr(parameters: &str, properties: &mut StyleProperties) {
+    let mut parameters = parameters.split(';');
+    while let Some(parameter) = parameters.next() {
+        let mut parts = parameter.split(':');
+        // an empty parameter means 0
+        let code = match parts.next() {
+            Some("") | None => 0,
+            Some(code) => match code.parse::<u16>() {
+                Ok(code) => code,
+                Err(_) => continue,
+            },
+        };
+        let parts: Vec<&str> = parts.collect();
+        match code {
+            0 => *properties = StyleProperties::default(),
+            1 => add_transformation(properties, StyleTransformation::Bold),
+            2 => add_transformation(properties, StyleTransformation::Dim),
+            3 => add_transformation(properties, StyleTransformation::Italic),
+            4 => {
+                remove_underlines(properties);
+                match parts.first() {
+                    Some(&"0") => (),
+                    Some(&"2") => add_transformation(properties, StyleTransformation::DoubleUnderlined),
+                    Some(&"3") => add_transformation(properties, StyleTransformation::CurlyUnderlined),
+                    _ => add_transformation(properties, StyleTransformation::Underlined),
+                }
+            },
+            5 | 6 => add_transformation(properties, StyleTransformation::Blink),
+            7 => add_transformation(properties, StyleTransformation::Reverse),
+            8 => add_transformation(properties, StyleTransformation::Hidden),
+            9 => add_transformation(properties, StyleTransformation::Strikethrough),
+            21 => {
+                remove_underlines(properties);
+                add_transformation(properties, StyleTransformation::DoubleUnderlined);
+            },
+            22 => remove_transformations(properties, &[StyleTransformation::Bold, StyleTransformation::Dim]),
+            23 => remove_transformations(properties, &[StyleTransformation::Italic]),
+            24 => remove_underlines(properties),
+            25 => remove_transformations(properties, &[StyleTransformation::Blink]),
+            27 => remove_transformations(properties, &[StyleTransformation::Reverse]),
+            28 => remove_transformations(properties, &[StyleTransformation::Hidden]),
+            29 => remove_transformations(properties, &[StyleTransformation::Strikethrough]),
+            30..=37 => properties.color = StyleColor::basic((code - 30) as u8),
+            90..=97 => properties.color = StyleColor::basic((code - 90 + 8) as u8),
+            40..=47 => properties.background = StyleColor::basic((code - 40) as u8),
+            100..=107 => properties.background = StyleColor::basic((code - 100 + 8) as u8),
+            39 => properties.color = None,
+            49 => properties.background = None,
+            59 => properties.underline_color = None,
+            38 | 48 | 58 => {
+                // the color is in the next parameters, like "38;5;1",
+                // or in the subparameters, like "38:5:1" or "38:2::255:0:0"
+                let color = if parts.is_empty() {
+                    extended_color(&mut parameters)
+                } else if parts.len() > 4 && parts[0] == "2" {
+                    // skip the color space id of "2:ID:R:G:B"
+                    extended_color(&mut parts[..1].iter().chain(&parts[2..]).cloned())
+                } else {
+                    extended_color(&mut parts.into_iter())
+                };
+                if color.is_some() {
+                    match code {
+                        38 => properties.color = color,
+                        48 => properties.background = color,
+                        _ => properties.underline_color = color,
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Read the color of an extended color parameter (38, 48 or 58):
+/// "5;N" for the 256-color palette, or "2;R;G;B".
+fn extended_color<'a, I>(parameters: &mut I) -> Option<StyleColor> where I: Iterator<Item = &'a str> {
+    let mut next = || parameters.next().and_then(|value| value.parse::<u8>().ok());
+    match next()? {
+        5 => {
+            let index = next()?;
+            Some(StyleColor::basic(index).unwrap_or(StyleColor::Fixed(index)))
+        },
+        2 => Some(StyleColor::Rgb(next()?, next()?, next()?)),
+        _ => None,
+    }
+}
+
+/// Add a transformation, if properties don't have it already.
+fn add_transformation(properties: &mut StyleProperties, transformation: StyleTransformation) {
+    if !properties.transformation.contains(&transformation) {
+        properties.transformation.push(transformation);
+    }
+}
+
+/// Remove the specified transformations from properties.
+fn remove_transformations(properties: &mut StyleProperties, transformations: &[StyleTransformation]) {
+    properties.transformation.retain(|transformation| !transformations.contains(transformation));
+}
+
+/// Remove all kinds of underlines from properties.
+fn remove_underlines(properties: &mut StyleProperties) {
+    remove_transformations(properties, &[
+        StyleTransformation::Underlined,
+        StyleTransformation::DoubleUnderlined,
+        StyleTransformation::CurlyUnderlined,
+    ]);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_sgr() {
+        assert_eq!(strip("plain"), "plain");
+        assert_eq!(strip(""), "");
+        assert_eq!(strip("\x1b[1;38;2;255;136;0mé\x1b[0m à \x1b[mb"), "é à b");
+        assert_eq!(strip("\x1b[4:3;58;5;1mtypo\x1b[0m"), "typo");
+        assert_eq!(strip("\u{9b}31mred"), "red");
+    }
+
+    #[test]
+    fn strip_other_sequences() {
+        // hyperlinks, terminated by BEL or ST
+        assert_eq!(strip("\x1b]8;;https://example.com\x07link\x1b]8;;\x07"), "link");
+        assert_eq!(strip("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"), "link");
+        // cursor movements, charsets, keypad mode
+        assert_eq!(strip("a\x1b[2Kb\x1b[?25lc\x1b(Bd\x1b=e\x1b7f"), "abcdef");
+    }
+
+    #[test]
+    fn strip_broken_sequences() {
+        // incomplete sequences at the end are removed
+        assert_eq!(strip("text\x1b[31"), "text");
+        assert_eq!(strip("text\x1b"), "text");
+        assert_eq!(strip("text\x1b]8;;url"), "text");
+        // invalid characters end a sequence and are kept
+        assert_eq!(strip("\x1b[31ñx"), "ñx");
+        assert_eq!(strip("\x1b]0;title\x1b[1mx"), "x");
+    }
+
+    #[test]
+    fn parse_sgr() {
+        assert_eq!(sgr_parameters("\x1b[m"), Some(""));
+        assert_eq!(sgr_parameters("\x1b[?25l"), None);
+        let mut properties = StyleProperties::default();
+        apply_sgr("1;4:3;91;48;5;208;58:2::0:0:255", &mut properties);
+        assert_eq!(properties.transformation, vec![
+            StyleTransformation::Bold,
+            StyleTransformation::CurlyUnderlined,
+        ]);
+        assert_eq!(properties.color, Some(StyleColor::BrightRed));
+        assert_eq!(properties.background, Some(StyleColor::Fixed(208)));
+        assert_eq!(properties.underline_color, Some(StyleColor::Rgb(0, 0, 255)));
+        // attributes must be reset one by one, or all together
+        apply_sgr("22;24;39;59", &mut properties);
+        assert!(properties.transformation.is_empty());
+        assert_eq!(properties.color, None);
+        apply_sgr("", &mut properties);
+        assert_eq!(properties, StyleProperties::default());
+        // malformed colors are ignored
+        apply_sgr("38;5", &mut properties);
+        assert_eq!(properties.color, None);
+    }
+
+    #[test]
+    fn split_segments() {
+        let parts: Vec<Segment> = segments("a\x1b[1mb\x1b[0m").collect();
+        assert_eq!(parts, vec![
+            Segment::Text("a"), Segment::Escape("\x1b[1m"), Segment::Text("b"), Segment::Escape("\x1b[0m"),
+        ]);
+    }
+}
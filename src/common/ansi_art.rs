@@ -0,0 +1,225 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use heatmap::{ASCII_SHADES, SHADES};
+use stylesheet::{Stylesheet, StyleColor};
+
+extern crate image;
+use self::image::imageops::FilterType;
+use self::image::{DynamicImage, GenericImageView};
+
+
+/// Pixels with a lower alpha are transparent.
+const MIN_ALPHA: u8 = 128;
+
+
+/// The error returned when an image can't be read or decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageError {
+    /// The file, or None for images in memory.
+    pub path: Option<PathBuf>,
+    pub message: String,
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path.display(), self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+
+/// Return the pixels of an image scaled to a width. Images narrower
+/// than width are not enlarged. Pixels stay square, because terminal
+/// characters are about twice as tall as wide and contain two pixels.
+fn pixels(image: &DynamicImage, width: usize) -> Vec<Vec<Option<StyleColor>>> {
+    let (image_width, image_height) = image.dimensions();
+    let width = (width as u32).min(image_width).max(1);
+    let height = (u64::from(image_height) * u64::from(width) / u64::from(image_width.max(1))).max(1) as u32;
+    let image = image.resize_exact(width, height, FilterType::Triangle).to_rgba8();
+    image.rows()
+        .map(|row| {
+            row.map(|pixel| {
+                let [r, g, b, a] = pixel.0;
+                if a < MIN_ALPHA { None } else { Some(StyleColor::Rgb(r, g, b)) }
+            })
+            .collect()
+        })
+        .collect()
+}
+
+/// Return a picture made of colored pixels, two pixels per character:
+/// "▀" with the color of the upper pixel and the background of the
+/// lower one. Terminals that don't support truecolor show the most
+/// similar colors they support. If colors are not enabled, every
+/// character is a shade of heatmap::SHADES, or of heatmap::ASCII_SHADES
+/// if Unicode is not enabled, that shows the brightness of its pixels.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to format the picture.
+/// * `pixels` - The rows of pixels, from the top. None pixels are
+///   transparent. Rows should have the same length.
+///
+/// # Example
+///
+/// ```
+/// use common::ansi_art;
+/// use common::stylesheet::Stylesheet;
+/// use common::stylesheet::StyleColor::*;
+/// let sheet = Stylesheet::new();
+/// let flag = vec![
+///     vec![Some(Rgb(0, 0, 255)), Some(Rgb(255, 255, 255)), Some(Rgb(255, 0, 0))],
+///     vec![Some(Rgb(0, 0, 255)), Some(Rgb(255, 255, 255)), Some(Rgb(255, 0, 0))],
+/// ];
+/// println!("{}", ansi_art::format_pixels(&sheet, &flag));
+/// ```
+pub fn format_pixels(sheet: &Stylesheet, pixels: &[Vec<Option<StyleColor>>]) -> String {
+    let colors = sheet.colors_enabled();
+    let shades = if sheet.unicode_enabled() { SHADES } else { ASCII_SHADES };
+    let sgr = |color: &StyleColor, background: bool| color.sgr_codes(background, false, sheet.color_depth()).join(";");
+    let brightness = |color: &Option<StyleColor>| {
+        color.as_ref().and_then(StyleColor::rgb_value).map_or(0.0, |(r, g, b)| {
+            (0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b)) / 255.0
+        })
+    };
+
+    pixels.chunks(2)
+        .map(|rows| {
+            let mut line = String::new();
+            for (x, top) in rows[0].iter().enumerate() {
+                let bottom = rows.get(1).and_then(|row| row.get(x)).cloned().unwrap_or(None);
+                if !colors {
+                    let level = (brightness(top) + brightness(&bottom)) / 2.0;
+                    line.push(shades[(level * (shades.len() - 1) as f64).round() as usize]);
+                    continue;
+                }
+                match (top, &bottom) {
+                    (Some(top), Some(bottom)) => line.push_str(&format!("\x1b[{};{}m▀", sgr(top, false), sgr(bottom, true))),
+                    (Some(top), None) => line.push_str(&format!("\x1b[{}m▀", sgr(top, false))),
+                    (None, Some(bottom)) => line.push_str(&format!("\x1b[{}m▄", sgr(bottom, false))),
+                    (None, None) => {
+                        line.push(' ');
+                        continue;
+                    },
+                }
+                line.push_str("\x1b[0m");
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Return an image, like a logo, drawn with colored block characters.
+/// See format_pixels(). The image is scaled to fit in
+/// columns characters; transparent pixels are left empty. PNG images
+/// are supported. Return an error if data is not a valid image.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to format the image.
+/// * `data` - The contents of an image file.
+/// * `columns` - The maximum width of the image, in characters.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs;
+/// use common::ansi_art;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let data = fs::read("logo.png").unwrap();
+/// println!("{}", ansi_art::format_image(&sheet, &data, 40).unwrap());
+/// ```
+pub fn format_image(sheet: &Stylesheet, data: &[u8], columns: usize) -> Result<String, ImageError> {
+    let image = image::load_from_memory(data)
+        .map_err(|err| ImageError { path: None, message: err.to_string() })?;
+    Ok(format_pixels(sheet, &pixels(&image, columns)))
+}
+
+/// Return an image file drawn with colored block characters.
+/// See format_image().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to format the image.
+/// * `path` - The image file.
+/// * `columns` - The maximum width of the image, in characters.
+pub fn format_image_file<P>(sheet: &Stylesheet, path: P, columns: usize) -> Result<String, ImageError> where P: AsRef<Path> {
+    let path = path.as_ref();
+    let image = image::open(path)
+        .map_err(|err| ImageError { path: Some(path.to_path_buf()), message: err.to_string() })?;
+    Ok(format_pixels(sheet, &pixels(&image, columns)))
+}
+
+/// Print an image file drawn with colored block characters, as wide
+/// as the terminal at most, or 80 columns if the output is not
+/// a terminal. See format_image().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `path` - The image file.
+pub fn print_image_file<P>(sheet: &Stylesheet, path: P) -> io::Result<()> where P: AsRef<Path> {
+    let image = format_image_file(sheet, path, sheet.output_width()).map_err(io::Error::other)?;
+    sheet.try_print_raw(&format!("{}\n", image))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use self::image::{ImageOutputFormat, Rgba, RgbaImage};
+    use stylesheet::ColorMode;
+    use stylesheet::StyleColor::{Black, Rgb};
+    use terminal::{ColorLevel, TerminalCapabilities};
+
+    #[test]
+    fn format_png() {
+        let mut logo = RgbaImage::from_pixel(8, 4, Rgba([255, 255, 255, 255]));
+        logo.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        let mut data = Vec::new();
+        DynamicImage::ImageRgba8(logo).write_to(&mut Cursor::new(&mut data), ImageOutputFormat::Png).unwrap();
+
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        assert_eq!(format_image(&sheet, &data, 80).unwrap(), ":#######\n########");
+        // scaled to 4 columns, 2 rows of pixels
+        assert_eq!(format_image(&sheet, &data, 4).unwrap(), "####");
+        assert!(format_image(&sheet, b"not an image", 80).is_err());
+        assert_eq!(
+            format_image_file(&sheet, "/nonexistent.png", 80).unwrap_err().path,
+            Some(PathBuf::from("/nonexistent.png"))
+        );
+    }
+
+    #[test]
+    fn pixel_art() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::TrueColor, ..TerminalCapabilities::minimal() });
+        let pixels = vec![
+            vec![Some(Rgb(255, 0, 0)), None, None],
+            vec![Some(Rgb(0, 0, 255)), Some(Rgb(255, 255, 255)), None],
+            vec![Some(Black)],
+        ];
+        assert_eq!(
+            format_pixels(&sheet, &pixels),
+            "\x1b[38;2;255;0;0;48;2;0;0;255m▀\x1b[0m\x1b[38;2;255;255;255m▄\x1b[0m \n\x1b[30m▀\x1b[0m"
+        );
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::Ansi256, ..TerminalCapabilities::minimal() });
+        assert!(format_pixels(&sheet, &pixels).starts_with("\x1b[38;5;196;48;5;21m▀"));
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        assert_eq!(format_pixels(&sheet, &pixels), ".: \n ");
+    }
+}
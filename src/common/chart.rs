@@ -0,0 +1,267 @@
+use std::io;
+
+use stylesheet::Stylesheet;
+use text;
+
+
+/// The bars of sparklines, from the lowest.
+pub const SPARK_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// The bars of sparklines if Unicode is not enabled, from the lowest.
+pub const ASCII_SPARK_BARS: [char; 8] = ['_', '.', ',', ':', '-', '=', '+', '#'];
+/// The ends of horizontal bars, from 1/8 to 7/8 of a character.
+const PARTIAL_BARS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+
+/// Return the index of the bar that represents value, scaled from low
+/// to high, or None if value is not a number.
+fn bar_index(value: f64, low: f64, high: f64, bars: usize) -> Option<usize> {
+    if value.is_nan() {
+        return None;
+    }
+    if high <= low {
+        return Some(0);
+    }
+    let scaled = (value - low) / (high - low) * (bars - 1) as f64;
+    Some(scaled.round().clamp(0.0, (bars - 1) as f64) as usize)
+}
+
+/// Return the range used to scale values: from 0, or from the lowest
+/// value if some values are negative, to the highest value.
+fn value_range(values: &[f64]) -> (f64, f64) {
+    values.iter()
+        .filter(|value| !value.is_nan())
+        .fold((0.0, 0.0), |(low, high), &value| (value.min(low), value.max(high)))
+}
+
+/// Return the bars of a sparkline.
+fn spark_bars(values: &[f64], bars: &[char]) -> Vec<Option<char>> {
+    let (low, high) = value_range(values);
+    values.iter()
+        .map(|&value| bar_index(value, low, high, bars.len()).map(|index| bars[index]))
+        .collect()
+}
+
+/// Return a one-line chart of values, one character per value, like
+/// "▁▂▃▅▇". Bars are scaled from 0, or from the lowest value if some
+/// values are negative, to the highest value. NaN values are shown as
+/// spaces.
+///
+/// # Arguments
+///
+/// * `values` - The values, usually the last ones of a series.
+///
+/// # Example
+///
+/// ```
+/// use common::chart;
+/// assert_eq!(chart::sparkline(&[0.0, 1.0, 2.0, 7.0]), "▁▂▃█");
+/// ```
+pub fn sparkline(values: &[f64]) -> String {
+    spark_bars(values, &SPARK_BARS).into_iter().map(|bar| bar.unwrap_or(' ')).collect()
+}
+
+/// Return a sparkline like sparkline(), with ASCII characters if Unicode
+/// is not enabled in sheet. Every bar has the style of the highest
+/// threshold that its value reaches, if any. Bars with the same style
+/// are styled together.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `values` - The values, usually the last ones of a series.
+/// * `thresholds` - (value, style name) pairs, in any order.
+///
+/// # Example
+///
+/// ```
+/// use common::chart;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let events_per_second = [1.0, 3.0, 2.0, 9.0, 4.0];
+/// let line = chart::format_sparkline(&sheet, &events_per_second, &[(0.0, "success"), (5.0, "danger")]);
+/// println!("activity: {}", line);
+/// ```
+pub fn format_sparkline(sheet: &Stylesheet, values: &[f64], thresholds: &[(f64, &str)]) -> String {
+    let bars = if sheet.unicode_enabled() { SPARK_BARS } else { ASCII_SPARK_BARS };
+    let bars = spark_bars(values, &bars);
+    let style_of = |value: f64| thresholds.iter()
+        .filter(|(threshold, _)| value >= *threshold)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, style_name)| *style_name);
+
+    let mut line = String::new();
+    let mut run = String::new();
+    let mut run_style = None;
+    for (&value, bar) in values.iter().zip(bars) {
+        let style_name = style_of(value);
+        if style_name != run_style && !run.is_empty() {
+            line.push_str(&paint(sheet, run_style, &run));
+            run.clear();
+        }
+        run_style = style_name;
+        run.push(bar.unwrap_or(' '));
+    }
+    line.push_str(&paint(sheet, run_style, &run));
+    line
+}
+
+/// Return text with a style, or as it is if there is no style.
+fn paint(sheet: &Stylesheet, style_name: Option<&str>, text: &str) -> String {
+    match style_name {
+        Some(style_name) if !text.is_empty() => sheet.format(style_name, text),
+        _ => text.to_string(),
+    }
+}
+
+/// A row of a bar chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar<'a> {
+    pub label: &'a str,
+    pub value: f64,
+    /// The style of the bar.
+    pub style_name: &'a str,
+}
+
+/// Return a value as shown in bar charts: integers without decimals,
+/// other numbers with one decimal.
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Return a horizontal bar that is length characters long. With Unicode,
+/// fractions of characters are shown, in eighths.
+fn bar(length: f64, unicode: bool) -> String {
+    if !unicode {
+        return "#".repeat(length.round() as usize);
+    }
+    let eighths = (length * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let partial = eighths % 8;
+    if partial > 0 {
+        bar.push(PARTIAL_BARS[partial - 1]);
+    }
+    bar
+}
+
+/// Return a horizontal bar chart, one row per bar: the label, aligned to
+/// the left, the bar, with its style, and the value. Bars are scaled so
+/// that the longest one fills the line. Negative values are shown as
+/// empty bars. Bars are made of "█", or "#" if Unicode is not enabled.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `bars` - The rows of the chart.
+/// * `width` - The width of the rows.
+///
+/// # Example
+///
+/// ```
+/// use common::chart::{self, Bar};
+/// use common::stylesheet::Stylesheet;
+/// let mut sheet = Stylesheet::new();
+/// sheet.set_unicode(false);
+/// let bars = [
+///     Bar { label: "danger", value: 2.0, style_name: "danger" },
+///     Bar { label: "info", value: 4.0, style_name: "info" },
+/// ];
+/// assert_eq!(chart::format_bar_chart(&sheet, &bars, 17), "danger ####     2\ninfo   ######## 4");
+/// ```
+pub fn format_bar_chart(sheet: &Stylesheet, bars: &[Bar], width: usize) -> String {
+    let label_width = bars.iter().map(|bar| text::visible_width(bar.label)).max().unwrap_or(0);
+    let values: Vec<String> = bars.iter().map(|bar| format_value(bar.value)).collect();
+    let value_width = values.iter().map(|value| text::visible_width(value)).max().unwrap_or(0);
+    let bar_width = width.saturating_sub(label_width + value_width + 2).max(1);
+    let max = bars.iter().map(|bar| bar.value).fold(0.0, f64::max);
+
+    bars.iter().zip(values)
+        .map(|(row, value)| {
+            let length = if max > 0.0 { row.value.max(0.0) / max * bar_width as f64 } else { 0.0 };
+            let drawn = bar(length, sheet.unicode_enabled());
+            let padding = bar_width - drawn.chars().count();
+            format!(
+                "{}{} {}{} {:>width$}",
+                row.label, " ".repeat(label_width - text::visible_width(row.label)),
+                paint(sheet, Some(row.style_name), &drawn), " ".repeat(padding),
+                value, width = value_width
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Print a horizontal bar chart as wide as the terminal, or 80 columns
+/// if the output is not a terminal. See format_bar_chart().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `bars` - The rows of the chart.
+///
+/// # Example
+///
+/// ```
+/// use common::chart::{self, Bar};
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// chart::print_bar_chart(&sheet, &[
+///     Bar { label: "Borg", value: 3.0, style_name: "danger" },
+///     Bar { label: "Vulcan", value: 12.0, style_name: "info" },
+/// ]).unwrap();
+/// ```
+pub fn print_bar_chart(sheet: &Stylesheet, bars: &[Bar]) -> io::Result<()> {
+    if bars.is_empty() {
+        return Ok(());
+    }
+    let width = sheet.output_width();
+    sheet.try_print_raw(&format!("{}\n", format_bar_chart(sheet, bars, width)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor, StyleProperties};
+
+    #[test]
+    fn sparklines() {
+        assert_eq!(sparkline(&[]), "");
+        assert_eq!(sparkline(&[0.0, 7.0, 3.5, f64::NAN]), "▁█▅ ");
+        // all zero: nothing to show
+        assert_eq!(sparkline(&[0.0, 0.0]), "▁▁");
+        assert_eq!(sparkline(&[-7.0, 0.0]), "▁█");
+
+        let mut sheet = Stylesheet::new();
+        sheet.set_unicode(false);
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+        assert_eq!(
+            format_sparkline(&sheet, &[0.0, 1.0, 7.0, 6.0, 2.0], &[(5.0, "danger")]),
+            "_.\x1b[31m#+\x1b[0m,"
+        );
+    }
+
+    #[test]
+    fn bar_charts() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_unicode(true);
+        let bars = [
+            Bar { label: "Borg cube", value: 3.0, style_name: "danger" },
+            Bar { label: "Vulcan", value: 16.0, style_name: "info" },
+            Bar { label: "Whormhole", value: 0.5, style_name: "info" },
+            Bar { label: "Nothing", value: -1.0, style_name: "info" },
+        ];
+        // bars are 14 characters long at most, in eighths
+        assert_eq!(format_bar_chart(&sheet, &bars, 28), [
+            "Borg cube ██▋              3",
+            "Vulcan    ██████████████  16",
+            "Whormhole ▌              0.5",
+            "Nothing                   -1",
+        ].join("\n"));
+        assert_eq!(format_bar_chart(&sheet, &[], 80), "");
+    }
+}
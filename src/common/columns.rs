@@ -0,0 +1,78 @@
+use stylesheet::Stylesheet;
+use text;
+
+
+/// The number of spaces between columns.
+pub const GAP: usize = 2;
+
+
+/// Return short texts laid out in columns, like ls does.
+/// See Stylesheet::format_columns().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `items` - A list of (style name, text) pairs.
+/// * `width` - The maximum width of the lines.
+pub fn format_columns(sheet: &Stylesheet, items: &[(&str, &str)], width: usize) -> String {
+    let widths: Vec<usize> = items.iter().map(|(_, text)| text::visible_width(text)).collect();
+    // the most columns that fit, with their widths
+    let (rows, column_widths) = (1..=items.len().max(1)).rev()
+        .map(|columns| {
+            let rows = items.len().div_ceil(columns).max(1);
+            let column_widths: Vec<usize> = widths.chunks(rows)
+                .map(|column| column.iter().cloned().max().unwrap_or(0))
+                .collect();
+            (rows, column_widths)
+        })
+        .find(|(_, column_widths)| {
+            column_widths.len() <= 1 || column_widths.iter().sum::<usize>() + GAP * (column_widths.len() - 1) <= width
+        })
+        .unwrap();
+
+    (0..rows)
+        .map(|row| {
+            let cells: Vec<usize> = (row..items.len()).step_by(rows).collect();
+            cells.iter().enumerate()
+                .map(|(column, &i)| {
+                    let (style_name, text) = items[i];
+                    let padding = if column + 1 == cells.len() {
+                        0
+                    } else {
+                        column_widths[column] - widths[i] + GAP
+                    };
+                    format!("{}{}", sheet.format(style_name, text), " ".repeat(padding))
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::{ColorMode, StyleProperties};
+    use stylesheet::StyleColor::Red;
+
+    #[test]
+    fn layout() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let items = [
+            (Stylesheet::DEFAULT_STYLE, "Sol"), (Stylesheet::DEFAULT_STYLE, "Vulcan"),
+            ("danger", "Wolf 359"), (Stylesheet::DEFAULT_STYLE, "Qo'noS"), (Stylesheet::DEFAULT_STYLE, "Bajor"),
+        ];
+        assert_eq!(format_columns(&sheet, &items, 80), "Sol  Vulcan  \x1b[31mWolf 359\x1b[0m  Qo'noS  Bajor");
+        // the texts go down the columns first
+        assert_eq!(
+            format_columns(&sheet, &items, 23),
+            "Sol     \x1b[31mWolf 359\x1b[0m  Bajor\nVulcan  Qo'noS"
+        );
+        // one column, even if it's too wide
+        assert_eq!(format_columns(&sheet, &items[..3], 5), "Sol\nVulcan\n\x1b[31mWolf 359\x1b[0m");
+        assert_eq!(format_columns(&sheet, &[], 80), "");
+    }
+}
@@ -0,0 +1,447 @@
+use std::env;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use scanner::ScannerSettings;
+use stylesheet::{ConflictPolicy, Stylesheet, StylesheetError};
+use themes::Theme;
+
+extern crate serde;
+use self::serde::Deserialize;
+
+extern crate toml;
+
+
+/// The name of our directory, inside the configuration directories.
+pub const APP_DIR: &str = "cli_exp";
+
+/// Names of the stylesheet files searched in configuration directories,
+/// in order. Only the first one that exists in a directory is used.
+pub const STYLESHEET_FILES: [&str; 4] = ["styles.toml", "styles.json", "styles.yaml", "styles.yml"];
+
+/// Name of the scanner settings file searched in configuration directories.
+pub const SCANNER_SETTINGS_FILE: &str = "scanner.toml";
+
+/// The subdirectory of the configuration directories that contains
+/// the profiles, as NAME.toml files.
+pub const PROFILES_DIR: &str = "profiles";
+
+/// The environment variable that selects a profile, if no profile is
+/// passed on the command line.
+pub const PROFILE_ENV: &str = "CLI_EXP_PROFILE";
+
+
+/// Errors found while loading configuration files.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// A stylesheet file can't be read or is invalid.
+    Stylesheet { path: PathBuf, error: Box<StylesheetError> },
+    /// A scanner settings file can't be read or is invalid.
+    ScannerSettings { path: PathBuf, message: String },
+    /// No profile with this name exists in the configuration directories.
+    UnknownProfile(String),
+    /// A profile file can't be read or is invalid.
+    Profile { path: PathBuf, message: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Stylesheet { path, error } => write!(f, "{}: {}", path.display(), error),
+            ConfigError::ScannerSettings { path, message } => write!(f, "{}: {}", path.display(), message),
+            ConfigError::UnknownProfile(name) => write!(f, "Profile not found: \"{}\"", name),
+            ConfigError::Profile { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+
+/// Return the directory that contains the configuration of the
+/// current user, or None if it can't be determined:
+///
+/// * `$XDG_CONFIG_HOME/cli_exp`, if XDG_CONFIG_HOME is set to an
+///   absolute path, on any system;
+/// * `%APPDATA%\cli_exp` on Windows;
+/// * `~/Library/Application Support/cli_exp` on macOS;
+/// * `~/.config/cli_exp` on other systems.
+///
+/// The directory is not guaranteed to exist.
+pub fn user_config_dir() -> Option<PathBuf> {
+    user_config_dir_from(|name| env::var_os(name))
+}
+
+/// Return the directories that contain the system-wide configuration,
+/// from the most important to the least important:
+///
+/// * `%PROGRAMDATA%\cli_exp` on Windows;
+/// * `/Library/Application Support/cli_exp` on macOS;
+/// * the cli_exp subdirectory of every directory in XDG_CONFIG_DIRS
+///   on other systems, or `/etc/xdg/cli_exp` if it is not set.
+///
+/// The directories are not guaranteed to exist.
+pub fn system_config_dirs() -> Vec<PathBuf> {
+    system_config_dirs_from(|name| env::var_os(name))
+}
+
+/// Same as user_config_dir(), reading environment variables with var.
+fn user_config_dir_from<F>(var: F) -> Option<PathBuf> where F: Fn(&str) -> Option<OsString> {
+    // relative paths must be ignored, as the XDG specification says
+    let absolute = |name: &str| var(name).map(PathBuf::from).filter(|path| path.is_absolute());
+    if let Some(dir) = absolute("XDG_CONFIG_HOME") {
+        return Some(dir.join(APP_DIR));
+    }
+    let dir = if cfg!(windows) {
+        absolute("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        absolute("HOME")?.join("Library").join("Application Support")
+    } else {
+        absolute("HOME")?.join(".config")
+    };
+    Some(dir.join(APP_DIR))
+}
+
+/// Same as system_config_dirs(), reading environment variables with var.
+fn system_config_dirs_from<F>(var: F) -> Vec<PathBuf> where F: Fn(&str) -> Option<OsString> {
+    let dirs = if cfg!(windows) {
+        var("PROGRAMDATA").map(PathBuf::from).into_iter().collect()
+    } else if cfg!(target_os = "macos") {
+        vec![PathBuf::from("/Library/Application Support")]
+    } else {
+        let dirs: Vec<PathBuf> = var("XDG_CONFIG_DIRS")
+            .map(|dirs| env::split_paths(&dirs).filter(|path| path.is_absolute()).collect())
+            .unwrap_or_default();
+        if dirs.is_empty() { vec![PathBuf::from("/etc/xdg")] } else { dirs }
+    };
+    dirs.into_iter().map(|dir| dir.join(APP_DIR)).collect()
+}
+
+
+/// The configuration files to load, in order of precedence: files that
+/// come later override the styles and settings of the previous ones.
+///
+/// discover() finds the files in the system directories, followed by
+/// the user directory. Files passed on the command line should be added
+/// after them, so the precedence is:
+///
+/// 1. styles defined by the program;
+/// 2. system-wide files, see system_config_dirs();
+/// 3. user files, see user_config_dir();
+/// 4. files passed on the command line.
+///
+/// Environment variables read by Stylesheet::apply_env_overrides()
+/// should be applied after loading the files.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::config::ConfigFiles;
+/// use common::stylesheet::Stylesheet;
+/// let mut sheet = Stylesheet::theme_dark();
+/// let mut files = ConfigFiles::discover();
+/// files.stylesheets.push("bridge.toml".into());
+/// let settings = files.load(&mut sheet).unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConfigFiles {
+    /// Stylesheet files, from the lowest precedence to the highest.
+    pub stylesheets: Vec<PathBuf>,
+    /// Scanner settings files, from the lowest precedence to the highest.
+    pub scanner_settings: Vec<PathBuf>,
+}
+
+impl ConfigFiles {
+    /// Return the configuration files that exist in the system and user
+    /// configuration directories. See STYLESHEET_FILES and
+    /// SCANNER_SETTINGS_FILE for the file names.
+    pub fn discover() -> ConfigFiles {
+        let mut dirs = system_config_dirs();
+        dirs.reverse();
+        dirs.extend(user_config_dir());
+        ConfigFiles::discover_in(&dirs)
+    }
+
+    /// Return the configuration files that exist in dirs, from the
+    /// least important directory to the most important.
+    ///
+    /// # Arguments
+    ///
+    /// * `dirs` - The directories to search.
+    pub fn discover_in<P>(dirs: &[P]) -> ConfigFiles where P: AsRef<Path> {
+        let mut files = ConfigFiles::default();
+        for dir in dirs {
+            let dir = dir.as_ref();
+            let stylesheet = STYLESHEET_FILES.iter().map(|name| dir.join(name)).find(|path| path.is_file());
+            files.stylesheets.extend(stylesheet);
+            let settings = dir.join(SCANNER_SETTINGS_FILE);
+            if settings.is_file() {
+                files.scanner_settings.push(settings);
+            }
+        }
+        files
+    }
+
+    /// Load the files in order: styles are merged into sheet, overriding
+    /// existing styles with the same name, and the scanner settings are
+    /// returned. If an error occurs, it refers to the first invalid file;
+    /// sheet may contain the styles of the files loaded before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The stylesheet that receives the styles.
+    pub fn load(&self, sheet: &mut Stylesheet) -> Result<ScannerSettings, ConfigError> {
+        for path in &self.stylesheets {
+            Stylesheet::from_file(path)
+                .and_then(|styles| sheet.merge(&styles, ConflictPolicy::Override))
+                .map_err(|error| ConfigError::Stylesheet { path: path.clone(), error: Box::new(error) })?;
+        }
+        let mut settings = ScannerSettings::default();
+        for path in &self.scanner_settings {
+            let error = |message: String| ConfigError::ScannerSettings { path: path.clone(), message };
+            let source = fs::read_to_string(path).map_err(|err| error(err.to_string()))?;
+            let file_settings: ScannerSettings = toml::from_str(&source).map_err(|err| error(err.to_string()))?;
+            settings = settings.merge(&file_settings);
+        }
+        Ok(settings)
+    }
+}
+
+
+/// Output options of a profile. Unset options are None, and don't
+/// change the Stylesheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSettings {
+    /// See Stylesheet::set_plain().
+    pub plain: Option<bool>,
+    /// See Stylesheet::set_unicode().
+    pub unicode: Option<bool>,
+    /// See Stylesheet::set_red_green_icons().
+    pub red_green_icons: Option<bool>,
+}
+
+impl OutputSettings {
+    /// Apply the options that are set to sheet.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The stylesheet to modify.
+    pub fn apply(&self, sheet: &mut Stylesheet) {
+        if let Some(plain) = self.plain {
+            sheet.set_plain(plain);
+        }
+        if let Some(unicode) = self.unicode {
+            sheet.set_unicode(unicode);
+        }
+        if let Some(red_green_icons) = self.red_green_icons {
+            sheet.set_red_green_icons(red_green_icons);
+        }
+    }
+}
+
+
+/// A named set of settings, selected with a --profile flag or with
+/// the PROFILE_ENV variable, like "bridge" or "engineering".
+/// Profiles are TOML files in the PROFILES_DIR subdirectory of the
+/// configuration directories, for example
+/// `~/.config/cli_exp/profiles/bridge.toml`:
+///
+/// ```toml
+/// theme = "high_contrast"
+/// # relative to the profile file
+/// stylesheet = "bridge.yaml"
+///
+/// [scanner]
+/// min_threat = 50
+///
+/// [output]
+/// red_green_icons = true
+/// ```
+///
+/// All keys are optional. The theme is loaded before the configuration
+/// files; the stylesheet and the scanner settings override the ones of
+/// the configuration files, but not the files passed on the command line.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// The built-in theme to start from.
+    pub theme: Option<Theme>,
+    /// A stylesheet file, loaded like the configuration files.
+    pub stylesheet: Option<PathBuf>,
+    /// Scanner settings.
+    pub scanner: ScannerSettings,
+    /// Output options.
+    pub output: OutputSettings,
+}
+
+impl Profile {
+    /// Return the name of the profile to use: selected, usually the
+    /// value of a --profile flag, or the value of PROFILE_ENV.
+    /// Return None if no profile is selected.
+    ///
+    /// # Arguments
+    ///
+    /// * `selected` - The profile passed on the command line, if any.
+    pub fn selected_name(selected: Option<&str>) -> Option<String> {
+        match selected {
+            Some(name) => Some(name.to_string()),
+            None => env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty()),
+        }
+    }
+
+    /// Return the path of the profile file with the given name. The user
+    /// configuration directory is searched first, then the system ones.
+    /// Return None if it doesn't exist, or if name is not a valid
+    /// profile name: only letters, digits, '_' and '-' are allowed.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The profile name.
+    pub fn find(name: &str) -> Option<PathBuf> {
+        let mut dirs: Vec<PathBuf> = user_config_dir().into_iter().collect();
+        dirs.extend(system_config_dirs());
+        Profile::find_in(name, &dirs)
+    }
+
+    /// Same as find(), but search dirs, from the most important.
+    fn find_in(name: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+        let is_valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+        if !is_valid {
+            return None;
+        }
+        dirs.iter()
+            .map(|dir| dir.join(PROFILES_DIR).join(format!("{}.toml", name)))
+            .find(|path| path.is_file())
+    }
+
+    /// Load the profile with the given name. See find().
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The profile name.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::config::Profile;
+    /// let profile = Profile::load("bridge").unwrap();
+    /// ```
+    pub fn load(name: &str) -> Result<Profile, ConfigError> {
+        match Profile::find(name) {
+            Some(path) => Profile::from_file(path),
+            None => Err(ConfigError::UnknownProfile(name.to_string())),
+        }
+    }
+
+    /// Load a profile file. A relative stylesheet path is made relative
+    /// to the directory of the profile file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the profile file.
+    pub fn from_file<P>(path: P) -> Result<Profile, ConfigError> where P: AsRef<Path> {
+        let path = path.as_ref();
+        let error = |message: String| ConfigError::Profile { path: path.to_path_buf(), message };
+        let source = fs::read_to_string(path).map_err(|err| error(err.to_string()))?;
+        let mut profile: Profile = toml::from_str(&source).map_err(|err| error(err.to_string()))?;
+        if let (Some(stylesheet), Some(dir)) = (&profile.stylesheet, path.parent()) {
+            profile.stylesheet = Some(dir.join(stylesheet));
+        }
+        Ok(profile)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::process;
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor};
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let vars: HashMap<String, OsString> = pairs.iter().map(|(k, v)| (k.to_string(), OsString::from(v))).collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn config_dirs() {
+        let var = vars(&[("XDG_CONFIG_HOME", "/xdg"), ("HOME", "/home/kirk")]);
+        assert_eq!(user_config_dir_from(var), Some(PathBuf::from("/xdg/cli_exp")));
+        // relative paths must be ignored
+        let var = vars(&[("XDG_CONFIG_HOME", "xdg")]);
+        assert_eq!(user_config_dir_from(var), None);
+        if cfg!(unix) && !cfg!(target_os = "macos") {
+            let var = vars(&[("XDG_CONFIG_HOME", ""), ("HOME", "/home/kirk")]);
+            assert_eq!(user_config_dir_from(var), Some(PathBuf::from("/home/kirk/.config/cli_exp")));
+            let var = vars(&[("XDG_CONFIG_DIRS", "/etc/a:relative:/etc/b")]);
+            assert_eq!(system_config_dirs_from(var), vec![PathBuf::from("/etc/a/cli_exp"), PathBuf::from("/etc/b/cli_exp")]);
+            assert_eq!(system_config_dirs_from(vars(&[])), vec![PathBuf::from("/etc/xdg/cli_exp")]);
+        }
+    }
+
+    #[test]
+    fn load_files() {
+        let root = env::temp_dir().join(format!("cli_exp_config_{}", process::id()));
+        let system = root.join("system");
+        let user = root.join("user");
+        fs::create_dir_all(&system).unwrap();
+        fs::create_dir_all(&user).unwrap();
+        fs::write(system.join("styles.toml"), "[danger]\ncolor = \"red\"\n[info]\ncolor = \"green\"\n").unwrap();
+        fs::write(system.join("scanner.toml"), "color = \"never\"\nmin_threat = 10\n").unwrap();
+        fs::write(user.join("styles.yaml"), "danger:\n  color: magenta\n").unwrap();
+        fs::write(user.join("scanner.toml"), "min_threat = 50\n").unwrap();
+
+        let files = ConfigFiles::discover_in(&[&system, &user]);
+        assert_eq!(files.stylesheets, vec![system.join("styles.toml"), user.join("styles.yaml")]);
+        let mut sheet = Stylesheet::new();
+        let settings = files.load(&mut sheet).unwrap();
+        // user files must win
+        assert_eq!(sheet.get_properties("danger").unwrap().color, Some(StyleColor::Magenta));
+        assert_eq!(sheet.get_properties("info").unwrap().color, Some(StyleColor::Green));
+        assert_eq!(settings, ScannerSettings { color: Some(ColorMode::Never), min_threat: Some(50), ..Default::default() });
+
+        fs::write(user.join("scanner.toml"), "min_threat = \"high\"\n").unwrap();
+        match files.load(&mut sheet) {
+            Err(ConfigError::ScannerSettings { path, .. }) => assert_eq!(path, user.join("scanner.toml")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn load_profiles() {
+        let root = env::temp_dir().join(format!("cli_exp_profiles_{}", process::id()));
+        let system = root.join("system");
+        let user = root.join("user");
+        fs::create_dir_all(system.join(PROFILES_DIR)).unwrap();
+        fs::create_dir_all(user.join(PROFILES_DIR)).unwrap();
+        fs::write(system.join(PROFILES_DIR).join("bridge.toml"), "theme = \"dark\"\n").unwrap();
+        fs::write(
+            user.join(PROFILES_DIR).join("bridge.toml"),
+            "theme = \"high_contrast\"\nstylesheet = \"bridge.yaml\"\n[scanner]\nmin_threat = 50\n[output]\nplain = true\n",
+        ).unwrap();
+        fs::write(user.join(PROFILES_DIR).join("broken.toml"), "theme = \"plaid\"\n").unwrap();
+        let dirs = [user.clone(), system.clone()];
+
+        // the user profile must win
+        let path = Profile::find_in("bridge", &dirs).unwrap();
+        let profile = Profile::from_file(&path).unwrap();
+        assert_eq!(profile.theme, Some(Theme::HighContrast));
+        assert_eq!(profile.stylesheet, Some(user.join(PROFILES_DIR).join("bridge.yaml")));
+        assert_eq!(profile.scanner.min_threat, Some(50));
+        let mut sheet = Stylesheet::new();
+        profile.output.apply(&mut sheet);
+        assert!(sheet.is_plain());
+
+        assert!(Profile::from_file(Profile::find_in("broken", &dirs).unwrap()).is_err());
+        // names must not escape the profiles directory
+        assert_eq!(Profile::find_in("../profiles/bridge", &dirs), None);
+        assert_eq!(Profile::find_in("engineering", &dirs), None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use heatmap::Heatmap;
+use progress;
+use scanner::Event;
+use status_line::STATUS_STYLE;
+use stylesheet::Stylesheet;
+use term;
+use terminal::TerminalSize;
+use text;
+
+
+/// The style of the title of the dashboard.
+pub const TITLE_STYLE: &str = "dashboard_title";
+
+/// The number of events kept for the feed. Older events are only counted.
+const MAX_EVENTS: usize = 1000;
+/// The duration of the time slots of the activity heatmap.
+const ACTIVITY_SLOT: Duration = Duration::from_secs(10);
+
+
+/// The state of a full screen view of the scanner: a feed with the
+/// latest events, the number of events per level and a status bar.
+/// The Dashboard doesn't print anything by itself: render() returns
+/// the text that redraws the whole screen.
+///
+/// # Example
+///
+/// ```
+/// use common::dashboard::Dashboard;
+/// use common::scanner::long_range_scanner;
+/// use common::stylesheet::Stylesheet;
+/// use common::terminal::TerminalSize;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut dashboard = Dashboard::new("Long range scanner");
+/// dashboard.push(long_range_scanner::scan());
+/// let screen = dashboard.render(&sheet, TerminalSize { columns: 80, rows: 24 });
+/// ```
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    title: String,
+    /// The latest events, the oldest first.
+    events: VecDeque<Event>,
+    /// The number of events per level.
+    counts: BTreeMap<&'static str, usize>,
+    /// The number of events per level and per ACTIVITY_SLOT.
+    activity: BTreeMap<&'static str, Vec<usize>>,
+    total: usize,
+    started: Instant,
+}
+
+impl Dashboard {
+    /// Return an empty dashboard. The elapsed time starts now.
+    ///
+    /// # Arguments
+    ///
+    /// * `title` - The text on the first line.
+    pub fn new<S>(title: S) -> Dashboard where S: Into<String> {
+        Dashboard {
+            title: title.into(),
+            events: VecDeque::new(),
+            counts: BTreeMap::new(),
+            activity: BTreeMap::new(),
+            total: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Add an event to the feed and to the counters.
+    pub fn push(&mut self, event: Event) {
+        let elapsed = self.started.elapsed();
+        self.push_at(event, elapsed);
+    }
+
+    /// Same as push(), as if elapsed time had passed since the start.
+    fn push_at(&mut self, event: Event, elapsed: Duration) {
+        *self.counts.entry(event.level).or_insert(0) += 1;
+        let slot = (elapsed.as_secs() / ACTIVITY_SLOT.as_secs()) as usize;
+        let slots = self.activity.entry(event.level).or_default();
+        if slots.len() <= slot {
+            slots.resize(slot + 1, 0);
+        }
+        slots[slot] += 1;
+        self.total += 1;
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Return the number of events received.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Return the number of events of a level.
+    pub fn count(&self, level: &str) -> usize {
+        self.counts.get(level).cloned().unwrap_or(0)
+    }
+
+    /// Return the levels that have events and their number of events,
+    /// in alphabetical order.
+    pub fn counts(&self) -> Vec<(&'static str, usize)> {
+        self.counts.iter().map(|(level, count)| (*level, *count)).collect()
+    }
+
+    /// Return a heatmap of the events per level (rows) and per time
+    /// (columns), that shows when events of each level cluster. Every
+    /// column counts the events of 10 seconds, or of a multiple of
+    /// 10 seconds if there would be more than max_columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_columns` - The maximum number of columns.
+    pub fn activity_heatmap(&self, max_columns: usize) -> Heatmap {
+        let slots = self.activity.values().map(Vec::len).max().unwrap_or(0);
+        let merged = slots.div_ceil(max_columns.max(1)).max(1);
+        let columns = slots.div_ceil(merged);
+        let mut heatmap = Heatmap::new();
+        for (level, counts) in &self.activity {
+            let values = (0..columns)
+                .map(|column| counts.iter().skip(column * merged).take(merged).sum::<usize>() as f64)
+                .collect();
+            heatmap.push(*level, values);
+        }
+        heatmap
+    }
+
+    /// Return the text that draws the dashboard on the whole screen,
+    /// from its top left corner, without scrolling it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet containing the styles. The levels of
+    ///   the events are used as style names.
+    /// * `size` - The size of the screen.
+    pub fn render(&self, sheet: &Stylesheet, size: TerminalSize) -> String {
+        self.render_at(sheet, size, self.started.elapsed())
+    }
+
+    /// Same as render(), as if elapsed time had passed since the start.
+    fn render_at(&self, sheet: &Stylesheet, size: TerminalSize, elapsed: Duration) -> String {
+        let lines = self.lines(sheet, size, elapsed);
+        let lines: Vec<String> = lines.iter().map(|line| text::truncate(line, size.columns)).collect();
+        // no newline after the last line, or the screen would scroll
+        format!(
+            "{}{}{}{}",
+            term::cursor_to(0, 0),
+            lines.join(&format!("{}\n", term::CLEAR_LINE_END)),
+            term::CLEAR_LINE_END,
+            term::CLEAR_SCREEN_END
+        )
+    }
+
+    /// Return the lines of the dashboard, not truncated.
+    fn lines(&self, sheet: &Stylesheet, size: TerminalSize, elapsed: Duration) -> Vec<String> {
+        let mut lines = vec![sheet.format(TITLE_STYLE, &self.title)];
+
+        let counters: Vec<String> = self.counts.iter()
+            .map(|(level, count)| format!("{} {}", sheet.format(level, level), count))
+            .collect();
+        lines.push(format!("{}  total {}", counters.join("  "), self.total));
+        let rule = if sheet.unicode_enabled() { "─" } else { "-" };
+        lines.push(rule.repeat(size.columns));
+
+        // the feed takes the rows left, the newest events at the bottom
+        let feed_rows = size.rows.saturating_sub(lines.len() + 1);
+        let skipped = self.events.len().saturating_sub(feed_rows);
+        let mut feed: Vec<String> = self.events.iter().skip(skipped).map(|event| event.format(sheet)).collect();
+        feed.resize(feed_rows, String::new());
+        lines.extend(feed);
+
+        let last = self.events.back().map_or("-", |event| event.message);
+        let status = format!(
+            "events: {} | last: {} | elapsed: {} | q to quit",
+            self.total, last, progress::format_duration(Duration::from_secs(elapsed.as_secs()))
+        );
+        lines.push(sheet.format(STATUS_STYLE, text::truncate(&status, size.columns)));
+        lines.truncate(size.rows.max(1));
+        lines
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor, StyleProperties};
+
+    fn borg() -> Event {
+        Event { level: "danger", message: "Borg cube approaching!", threat: 95 }
+    }
+
+    fn wormhole() -> Event {
+        Event { level: "info", message: "Whormhole detected", threat: 20 }
+    }
+
+    #[test]
+    fn dashboard_lines() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        let mut dashboard = Dashboard::new("Scanner");
+        dashboard.push(borg());
+        dashboard.push(wormhole());
+        dashboard.push(wormhole());
+        assert_eq!(dashboard.count("info"), 2);
+        assert_eq!(dashboard.total(), 3);
+        assert_eq!(dashboard.counts(), vec![("danger", 1), ("info", 2)]);
+        let size = TerminalSize { columns: 70, rows: 6 };
+        // only the newest events fit in the feed
+        assert_eq!(dashboard.lines(&sheet, size, Duration::from_secs(61)), vec![
+            "Scanner".to_string(),
+            "danger 1  info 2  total 3".to_string(),
+            "-".repeat(70),
+            "[ 20] Whormhole detected".to_string(),
+            "[ 20] Whormhole detected".to_string(),
+            "events: 3 | last: Whormhole detected | elapsed: 01:01 | q to quit".to_string(),
+        ]);
+        // empty rows are kept, so the status bar stays at the bottom
+        let size = TerminalSize { columns: 20, rows: 8 };
+        let lines = dashboard.lines(&sheet, size, Duration::from_secs(0));
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[6], "");
+        assert_eq!(lines[7], "events: 3 | last: W…");
+    }
+
+    #[test]
+    fn activity() {
+        let mut dashboard = Dashboard::new("Scanner");
+        dashboard.push_at(borg(), Duration::from_secs(0));
+        dashboard.push_at(wormhole(), Duration::from_secs(5));
+        dashboard.push_at(borg(), Duration::from_secs(25));
+        dashboard.push_at(borg(), Duration::from_secs(29));
+        assert_eq!(dashboard.activity_heatmap(10).rows, vec![
+            ("danger".to_string(), vec![1.0, 0.0, 2.0]),
+            ("info".to_string(), vec![1.0, 0.0, 0.0]),
+        ]);
+        // slots are merged to fit
+        assert_eq!(dashboard.activity_heatmap(2).rows[0], ("danger".to_string(), vec![1.0, 2.0]));
+        assert!(Dashboard::new("Scanner").activity_heatmap(10).rows.is_empty());
+    }
+
+    #[test]
+    fn render_screen() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style(TITLE_STYLE, StyleProperties::builder().color(StyleColor::Cyan).build());
+        let dashboard = Dashboard::new("Scanner");
+        let screen = dashboard.render_at(&sheet, TerminalSize { columns: 10, rows: 4 }, Duration::from_secs(0));
+        assert!(screen.starts_with("\x1b[1;1H\x1b[36mScanner\x1b[0m\x1b[K\n"));
+        assert!(!screen.ends_with('\n'));
+        assert_eq!(screen.matches('\n').count(), 3);
+    }
+}
@@ -0,0 +1,253 @@
+use stylesheet::Stylesheet;
+
+
+/// The style of added lines. Built-in themes show it in green.
+pub const ADDED_STYLE: &str = "success";
+/// The style of removed lines. Built-in themes show it in red.
+pub const REMOVED_STYLE: &str = "danger";
+/// The style of changed lines. Built-in themes show it in yellow.
+pub const CHANGED_STYLE: &str = "warning";
+
+
+/// A line of the difference between two texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    /// A line that is in both texts.
+    Same(&'a str),
+    /// A line that is only in the new text.
+    Added(&'a str),
+    /// A line that is only in the old text.
+    Removed(&'a str),
+    /// A line of the old text replaced by a line of the new text.
+    Changed(&'a str, &'a str),
+}
+
+/// Return the differences between two texts, line by line, in the
+/// order of the texts. Lines are compared exactly, including spaces.
+/// When some lines are removed and others are added in the same place,
+/// they are paired as changed lines, in order; the lines left are
+/// removed or added. If the texts are very different, all the lines
+/// between their common beginning and end are one block of changes.
+///
+/// # Arguments
+///
+/// * `old` - The original text.
+/// * `new` - The modified text.
+///
+/// # Example
+///
+/// ```
+/// use common::diff::{self, DiffLine};
+/// assert_eq!(diff::diff_lines("a\nb\nc", "a\nB\nc\nd"), vec![
+///     DiffLine::Same("a"), DiffLine::Changed("b", "B"), DiffLine::Same("c"), DiffLine::Added("d"),
+/// ]);
+/// ```
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // the lines at the beginning and at the end are often the same,
+    // and they don't need to be compared by edit_script()
+    let prefix = old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+    let edits = edit_script(old_middle, new_middle).unwrap_or_else(|| {
+        // too many differences: the middle is one block
+        old_middle.iter().map(|&line| Edit::Removed(line))
+            .chain(new_middle.iter().map(|&line| Edit::Added(line)))
+            .collect()
+    });
+
+    let mut lines: Vec<DiffLine> = old[..prefix].iter().map(|&line| DiffLine::Same(line)).collect();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    for edit in edits {
+        match edit {
+            Edit::Same(line) => {
+                pair_changes(&mut lines, &mut removed, &mut added);
+                lines.push(DiffLine::Same(line));
+            },
+            Edit::Removed(line) => removed.push(line),
+            Edit::Added(line) => added.push(line),
+        }
+    }
+    pair_changes(&mut lines, &mut removed, &mut added);
+    lines.extend(old[old.len() - suffix..].iter().map(|&line| DiffLine::Same(line)));
+    lines
+}
+
+/// A step of the edit script that turns a text into another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The maximum number of removed and added lines that edit_script()
+/// looks for. The memory it uses grows with the square of this number.
+const MAX_EDITS: usize = 1000;
+
+/// Return the shortest edit script that turns old into new, found with
+/// the Myers algorithm, in O((old + new) * edits) time. Return None if
+/// more than MAX_EDITS lines must be removed or added.
+fn edit_script<'a>(old: &[&'a str], new: &[&'a str]) -> Option<Vec<Edit<'a>>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = (old.len() + new.len()).min(MAX_EDITS) as isize;
+    // v[offset + k] is the furthest x reached on the diagonal k = x - y;
+    // trace[d] keeps the diagonals -d..=d after d edits
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    let mut edits = None;
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]) {
+                v[(offset + k + 1) as usize]
+            } else {
+                v[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+            if x >= n && y >= m {
+                edits = Some(d);
+                break;
+            }
+        }
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+        if edits.is_some() {
+            break;
+        }
+    }
+    let edits = edits?;
+
+    // follow the trace back from the end
+    let mut script = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..=edits).rev() {
+        let previous = &trace[d as usize - 1];
+        let furthest = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && furthest(k - 1) < furthest(k + 1)) { k + 1 } else { k - 1 };
+        let previous_x = furthest(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            script.push(Edit::Same(old[x as usize - 1]));
+            x -= 1;
+            y -= 1;
+        }
+        if x == previous_x {
+            script.push(Edit::Added(new[previous_y as usize]));
+        } else {
+            script.push(Edit::Removed(old[previous_x as usize]));
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    while x > 0 && y > 0 {
+        script.push(Edit::Same(old[x as usize - 1]));
+        x -= 1;
+        y -= 1;
+    }
+    script.reverse();
+    Some(script)
+}
+
+/// Move the removed and added lines of a block to lines, pairing them
+/// as changed lines.
+fn pair_changes<'a>(lines: &mut Vec<DiffLine<'a>>, removed: &mut Vec<&'a str>, added: &mut Vec<&'a str>) {
+    let paired = removed.len().min(added.len());
+    lines.extend(removed.iter().zip(added.iter()).map(|(old, new)| DiffLine::Changed(old, new)));
+    lines.extend(removed.drain(..).skip(paired).map(DiffLine::Removed));
+    lines.extend(added.drain(..).skip(paired).map(DiffLine::Added));
+}
+
+
+/// Return the differences between two texts, one line per line.
+/// See Stylesheet::format_diff().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `old` - The original text.
+/// * `new` - The modified text.
+pub fn format_diff(sheet: &Stylesheet, old: &str, new: &str) -> String {
+    let arrow = if sheet.unicode_enabled() { "→" } else { "->" };
+    diff_lines(old, new).iter()
+        .map(|line| match *line {
+            DiffLine::Same(text) => format!("  {}", text),
+            DiffLine::Added(text) => sheet.format(ADDED_STYLE, format!("+ {}", text)),
+            DiffLine::Removed(text) => sheet.format(REMOVED_STYLE, format!("- {}", text)),
+            DiffLine::Changed(old, new) => sheet.format(CHANGED_STYLE, format!("~ {} {} {}", old, arrow, new)),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+
+    #[test]
+    fn diff() {
+        assert_eq!(diff_lines("", ""), vec![]);
+        assert_eq!(diff_lines("a\nb", "a\nb"), vec![DiffLine::Same("a"), DiffLine::Same("b")]);
+        assert_eq!(diff_lines("a\nb\nc", "c\nd"), vec![
+            DiffLine::Removed("a"), DiffLine::Removed("b"), DiffLine::Same("c"), DiffLine::Added("d"),
+        ]);
+        // extra lines of a block are removed or added after the changes
+        assert_eq!(diff_lines("x\na\nb\ny", "x\nA\ny"), vec![
+            DiffLine::Same("x"), DiffLine::Changed("a", "A"), DiffLine::Removed("b"), DiffLine::Same("y"),
+        ]);
+        assert_eq!(diff_lines("a", "A\nB\na"), vec![
+            DiffLine::Added("A"), DiffLine::Added("B"), DiffLine::Same("a"),
+        ]);
+        assert_eq!(diff_lines("a\nb", "b\na"), vec![DiffLine::Removed("a"), DiffLine::Same("b"), DiffLine::Added("a")]);
+    }
+
+    #[test]
+    fn diff_large_texts() {
+        let old: Vec<String> = (0..10_000).map(|n| format!("sector {}", n)).collect();
+        // one line in 100 is changed
+        let new: Vec<String> = (0..10_000)
+            .map(|n| if n % 100 == 50 { format!("sector {} (lost)", n) } else { format!("sector {}", n) })
+            .collect();
+        let (old, new) = (old.join("\n"), new.join("\n"));
+        let lines = diff_lines(&old, &new);
+        assert_eq!(lines.len(), 10_000);
+        assert_eq!(lines.iter().filter(|line| matches!(line, DiffLine::Changed(_, _))).count(), 100);
+        assert_eq!(lines[50], DiffLine::Changed("sector 50", "sector 50 (lost)"));
+
+        // texts without common lines are one block of changes
+        let other: Vec<String> = (0..12_000).map(|n| format!("star {}", n)).collect();
+        let other = other.join("\n");
+        let lines = diff_lines(&old, &other);
+        assert_eq!(lines.len(), 12_000);
+        assert_eq!(lines[0], DiffLine::Changed("sector 0", "star 0"));
+        assert_eq!(lines[11_999], DiffLine::Added("star 11999"));
+    }
+
+    #[test]
+    fn render() {
+        let mut sheet = Stylesheet::theme_dark();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_unicode(true);
+        let old = "Sol: clear\nWolf 359: clear\nVulcan: clear";
+        let new = "Sol: clear\nWolf 359: Borg cube\nQo'noS: clear";
+        assert_eq!(format_diff(&sheet, old, new), format!(
+            "  Sol: clear\n{}\n{}",
+            sheet.format(CHANGED_STYLE, "~ Wolf 359: clear → Wolf 359: Borg cube"),
+            sheet.format(CHANGED_STYLE, "~ Vulcan: clear → Qo'noS: clear")
+        ));
+        assert_eq!(format_diff(&sheet, "a", "a\nb"), format!("  a\n{}", sheet.format(ADDED_STYLE, "+ b")));
+        assert_eq!(format_diff(&sheet, "a\nb", "b"), format!("{}\n  b", sheet.format(REMOVED_STYLE, "- a")));
+        assert_eq!(format_diff(&sheet, "", ""), "");
+    }
+}
@@ -0,0 +1,216 @@
+use stylesheet::{Stylesheet, StyleColor};
+use text;
+
+
+/// The colors of the default ramp of heatmaps: from dark gray for the
+/// lowest values, through yellow, to red for the highest values.
+pub const DEFAULT_RAMP: [StyleColor; 3] = [
+    StyleColor::Rgb(48, 48, 48),
+    StyleColor::Rgb(255, 200, 0),
+    StyleColor::Rgb(255, 0, 0),
+];
+
+/// The characters that show the values of cells when colors are not
+/// enabled, from the lowest.
+pub const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+/// The characters that show the values of cells when neither colors nor
+/// Unicode are enabled, from the lowest.
+pub const ASCII_SHADES: [char; 5] = [' ', '.', ':', '*', '#'];
+
+/// The number of characters of a cell: two make it roughly square.
+pub const CELL_WIDTH: usize = 2;
+
+
+/// A grid of values printed by Stylesheet::print_heatmap(), like events
+/// per sector (rows) and per minute (columns). Every cell has the
+/// background color of its value in a ramp. The ramp goes from the
+/// lowest to the highest value, unless a range is set.
+///
+/// # Example
+///
+/// ```
+/// use common::heatmap::Heatmap;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let heatmap = Heatmap::new()
+///     .row("Sol", vec![0.0, 1.0, 0.0, 0.0])
+///     .row("Wolf 359", vec![2.0, 9.0, 7.0, 1.0]);
+/// sheet.print_heatmap(&heatmap);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heatmap {
+    /// (label, values) pairs, from the top.
+    pub rows: Vec<(String, Vec<f64>)>,
+    /// The colors of the ramp, from the lowest value.
+    pub ramp: Vec<StyleColor>,
+    /// The values at the ends of the ramp, or None to use the lowest
+    /// and the highest values.
+    pub range: Option<(f64, f64)>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap::new()
+    }
+}
+
+impl Heatmap {
+    /// Return a heatmap without rows, with the default ramp.
+    pub fn new() -> Heatmap {
+        Heatmap { rows: Vec::new(), ramp: DEFAULT_RAMP.to_vec(), range: None }
+    }
+
+    /// Return the heatmap with another ramp.
+    ///
+    /// # Arguments
+    ///
+    /// * `ramp` - The colors, evenly spaced, from the lowest value.
+    ///   See StyleColor::ramp().
+    pub fn with_ramp(mut self, ramp: Vec<StyleColor>) -> Heatmap {
+        self.ramp = ramp;
+        self
+    }
+
+    /// Return the heatmap with fixed values at the ends of the ramp,
+    /// so that several heatmaps can be compared. Values out of the
+    /// range have the colors of its ends.
+    pub fn with_range(mut self, low: f64, high: f64) -> Heatmap {
+        self.range = Some((low, high));
+        self
+    }
+
+    /// Return the heatmap with a row added at the bottom.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The text on the left of the row.
+    /// * `values` - The values of the cells. NaN cells are left empty.
+    pub fn row<S>(mut self, label: S, values: Vec<f64>) -> Heatmap where S: Into<String> {
+        self.push(label, values);
+        self
+    }
+
+    /// Add a row at the bottom. See row().
+    pub fn push<S>(&mut self, label: S, values: Vec<f64>) where S: Into<String> {
+        self.rows.push((label.into(), values));
+    }
+
+    /// Return the values at the ends of the ramp: the range, if it is
+    /// set, or the lowest and the highest values. This scans all the
+    /// cells, so call it once per heatmap, and then position_in().
+    pub fn value_range(&self) -> (f64, f64) {
+        self.range.unwrap_or_else(|| {
+            self.rows.iter()
+                .flat_map(|(_, values)| values.iter().cloned())
+                .filter(|value| !value.is_nan())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| (low.min(value), high.max(value)))
+        })
+    }
+
+    /// Return the position of value in the ramp, from 0.0 to 1.0,
+    /// or None if value is not a number. See position_in().
+    pub fn position(&self, value: f64) -> Option<f64> {
+        position_in(value, self.value_range())
+    }
+}
+
+
+/// Return the position of value in a range returned by
+/// Heatmap::value_range(), from 0.0 to 1.0, or None if value is not
+/// a number.
+///
+/// # Arguments
+///
+/// * `value` - The value of a cell.
+/// * `range` - The values at the ends of the ramp.
+pub fn position_in(value: f64, (low, high): (f64, f64)) -> Option<f64> {
+    if value.is_nan() {
+        return None;
+    }
+    if high <= low {
+        return Some(0.0);
+    }
+    Some(((value - low) / (high - low)).clamp(0.0, 1.0))
+}
+
+
+/// Return a heatmap, one line per row. See Stylesheet::format_heatmap().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to format the heatmap.
+/// * `heatmap` - The Heatmap to format.
+pub fn format_heatmap(sheet: &Stylesheet, heatmap: &Heatmap) -> String {
+    let label_width = heatmap.rows.iter().map(|(label, _)| text::visible_width(label)).max().unwrap_or(0);
+    let colors = sheet.colors_enabled();
+    let shades = if sheet.unicode_enabled() { SHADES } else { ASCII_SHADES };
+    // scanning the cells once per heatmap, not once per cell
+    let range = heatmap.value_range();
+    let cell = |value: f64| match position_in(value, range) {
+        None => " ".repeat(CELL_WIDTH),
+        Some(position) if colors => {
+            let color = StyleColor::ramp(&heatmap.ramp, position);
+            format!(
+                "\x1b[{}m{}\x1b[0m",
+                color.sgr_codes(true, false, sheet.color_depth()).join(";"),
+                " ".repeat(CELL_WIDTH)
+            )
+        },
+        Some(position) => {
+            let shade = shades[(position * (shades.len() - 1) as f64).round() as usize];
+            shade.to_string().repeat(CELL_WIDTH)
+        },
+    };
+
+    heatmap.rows.iter()
+        .map(|(label, values)| {
+            let cells: String = values.iter().map(|&value| cell(value)).collect();
+            format!("{}{} {}", label, " ".repeat(label_width - text::visible_width(label)), cells)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+    use stylesheet::StyleColor::Rgb;
+    use terminal::{ColorLevel, TerminalCapabilities};
+
+    #[test]
+    fn positions() {
+        let heatmap = Heatmap::new().row("Sol", vec![2.0, 4.0]).row("Vulcan", vec![f64::NAN, 6.0]);
+        assert_eq!(heatmap.position(2.0), Some(0.0));
+        assert_eq!(heatmap.position(5.0), Some(0.75));
+        assert_eq!(heatmap.position(f64::NAN), None);
+        assert_eq!(heatmap.value_range(), (2.0, 6.0));
+        assert_eq!(position_in(5.0, (2.0, 6.0)), Some(0.75));
+        let heatmap = heatmap.with_range(0.0, 4.0);
+        assert_eq!(heatmap.position(6.0), Some(1.0));
+        // all the values are equal
+        assert_eq!(Heatmap::new().row("Sol", vec![3.0]).position(3.0), Some(0.0));
+    }
+
+    #[test]
+    fn render() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::TrueColor, ..TerminalCapabilities::minimal() });
+        let heatmap = Heatmap::new()
+            .with_ramp(vec![Rgb(0, 0, 0), Rgb(255, 0, 0)])
+            .row("Sol", vec![0.0, f64::NAN])
+            .row("Wolf 359", vec![1.0, 2.0]);
+        assert_eq!(
+            format_heatmap(&sheet, &heatmap),
+            "Sol      \x1b[48;2;0;0;0m  \x1b[0m  \n\
+             Wolf 359 \x1b[48;2;128;0;0m  \x1b[0m\x1b[48;2;255;0;0m  \x1b[0m"
+        );
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(true);
+        // the lowest shade is a space, like empty cells
+        assert_eq!(format_heatmap(&sheet, &heatmap), format!("Sol{}\nWolf 359 ▒▒██", " ".repeat(10)));
+        assert_eq!(format_heatmap(&sheet, &Heatmap::new()), "");
+    }
+}
@@ -0,0 +1,147 @@
+use std::error::Error;
+use std::fmt;
+
+extern crate regex;
+use self::regex::Regex;
+
+
+/// Errors returned when highlight rules are added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HighlightError {
+    /// A pattern is not a valid regular expression.
+    InvalidPattern { pattern: String, message: String },
+}
+
+impl fmt::Display for HighlightError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HighlightError::InvalidPattern { pattern, message } =>
+                write!(f, "Invalid pattern \"{}\": {}", pattern, message),
+        }
+    }
+}
+
+impl Error for HighlightError {}
+
+/// A list of rules that style the parts of a text that match regular
+/// expressions, to colorize output that was not written with styles,
+/// like log files or the output of other programs.
+/// When matches overlap, the rule added first wins.
+/// The pattern syntax is the one of the regex crate:
+/// "(?i)" at the beginning makes a pattern case insensitive.
+///
+/// # Example
+///
+/// ```
+/// use common::highlight::HighlightRules;
+/// let mut rules = HighlightRules::new();
+/// rules.add_rule(r"(?i)error", "danger").unwrap();
+/// rules.add_rule(r"\d+ ms", "info").unwrap();
+/// let segments = rules.segments("ERROR after 30 ms");
+/// assert_eq!(segments[0], (Some("danger"), "ERROR"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct HighlightRules {
+    /// Patterns and the names of their styles, in order of priority.
+    rules: Vec<(Regex, String)>,
+}
+
+impl HighlightRules {
+    /// Return an empty list of rules.
+    pub fn new() -> HighlightRules {
+        HighlightRules { rules: Vec::new() }
+    }
+
+    /// Add a rule: texts that match pattern will have the given style.
+    /// Return an error if pattern is not a valid regular expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `pattern` - A regular expression.
+    /// * `style_name` - The name of the style for the matching texts.
+    pub fn add_rule(&mut self, pattern: &str, style_name: &str) -> Result<(), HighlightError> {
+        let regex = Regex::new(pattern).map_err(|err| HighlightError::InvalidPattern {
+            pattern: pattern.to_string(),
+            message: err.to_string(),
+        })?;
+        self.rules.push((regex, style_name.to_string()));
+        Ok(())
+    }
+
+    /// Return the number of rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Return true if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Split text into (style name, text) segments, according to the
+    /// rules. Texts that don't match any rule have no style name.
+    /// Empty matches are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to split.
+    pub fn segments<'a>(&'a self, text: &'a str) -> Vec<(Option<&'a str>, &'a str)> {
+        // the rule that styles every byte of text, if any
+        let mut owners: Vec<Option<usize>> = vec![None; text.len()];
+        for (i, (regex, _)) in self.rules.iter().enumerate() {
+            for found in regex.find_iter(text) {
+                for owner in &mut owners[found.start()..found.end()] {
+                    if owner.is_none() {
+                        *owner = Some(i);
+                    }
+                }
+            }
+        }
+
+        let mut segments = Vec::new();
+        let mut start = 0;
+        for end in 1..=text.len() {
+            if end == text.len() || owners[end] != owners[start] {
+                let style_name = owners[start].map(|i| self.rules[i].1.as_str());
+                segments.push((style_name, &text[start..end]));
+                start = end;
+            }
+        }
+        segments
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlight_segments() {
+        let mut rules = HighlightRules::new();
+        rules.add_rule(r"(?i)error", "danger").unwrap();
+        rules.add_rule(r"\d+ ms", "info").unwrap();
+        // overlapping matches: the first rule wins
+        rules.add_rule(r"rror \d", "success").unwrap();
+        assert_eq!(rules.segments("Error 5 after 30 ms"), vec![
+            (Some("danger"), "Error"),
+            (Some("success"), " 5"),
+            (None, " after "),
+            (Some("info"), "30 ms"),
+        ]);
+        assert_eq!(rules.segments("ok"), vec![(None, "ok")]);
+        assert!(rules.segments("").is_empty());
+        // matches never split a multibyte character
+        assert_eq!(rules.segments("érror 1"), vec![(None, "é"), (Some("success"), "rror 1")]);
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let mut rules = HighlightRules::new();
+        match rules.add_rule(r"(\d+", "info") {
+            Err(HighlightError::InvalidPattern { pattern, .. }) => assert_eq!(pattern, r"(\d+"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(rules.is_empty());
+    }
+}
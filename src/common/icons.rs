@@ -0,0 +1,108 @@
+use std::fmt;
+use std::str::FromStr;
+
+extern crate serde;
+use self::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+
+/// Icons that a style can print before messages.
+/// See Stylesheet::set_icon().
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Success,
+    Error,
+    Warning,
+    Info,
+}
+
+impl Icon {
+    /// Names accepted by from_str(), as written in stylesheet files.
+    pub const NAMES: [&'static str; 4] = ["success", "error", "warning", "info"];
+
+    /// Return the icon as a Unicode glyph, or as an ASCII character
+    /// for terminals and locales that can't show the glyphs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::icons::Icon;
+    /// assert_eq!(Icon::Success.glyph(true), "✔");
+    /// assert_eq!(Icon::Success.glyph(false), "+");
+    /// ```
+    pub fn glyph(self, unicode: bool) -> &'static str {
+        match (self, unicode) {
+            (Icon::Success, true) => "✔",
+            (Icon::Success, false) => "+",
+            (Icon::Error, true) => "✖",
+            (Icon::Error, false) => "x",
+            (Icon::Warning, true) => "⚠",
+            (Icon::Warning, false) => "!",
+            (Icon::Info, true) => "ℹ",
+            (Icon::Info, false) => "i",
+        }
+    }
+}
+
+impl FromStr for Icon {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "success" => Ok(Icon::Success),
+            "error" => Ok(Icon::Error),
+            "warning" => Ok(Icon::Warning),
+            "info" => Ok(Icon::Info),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Icon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Icon::Success => "success",
+            Icon::Error => "error",
+            Icon::Warning => "warning",
+            Icon::Info => "info",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Icons are serialized by name, as in stylesheet files.
+impl Serialize for Icon {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Icon {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&name), &"an icon name"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_from_str() {
+        for name in Icon::NAMES.iter() {
+            let icon: Icon = name.parse().unwrap();
+            assert_eq!(icon.to_string(), *name);
+        }
+        assert!("skull".parse::<Icon>().is_err());
+    }
+
+    #[test]
+    fn ascii_fallback() {
+        for name in Icon::NAMES.iter() {
+            let icon: Icon = name.parse().unwrap();
+            // must be a single printable ASCII character
+            assert!(icon.glyph(false).len() == 1 && icon.glyph(false).is_ascii());
+        }
+    }
+}
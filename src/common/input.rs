@@ -0,0 +1,364 @@
+use std::io;
+#[cfg(not(unix))]
+use std::sync::mpsc;
+#[cfg(not(unix))]
+use std::thread;
+use std::time::Duration;
+
+#[cfg(unix)]
+extern crate termios;
+#[cfg(not(unix))]
+extern crate console;
+
+
+/// A key pressed by the user, as read by RawMode::read_key().
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// A printable character.
+    Char(char),
+    /// A letter pressed with Ctrl, in lowercase. Ctrl+C is Ctrl('c').
+    Ctrl(char),
+    Enter,
+    Tab,
+    /// Shift+Tab.
+    BackTab,
+    Backspace,
+    Delete,
+    Escape,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// A sequence that is not recognized, like function keys.
+    Unknown,
+}
+
+/// Decode the first key in bytes, as sent by terminals in raw mode.
+/// Return the key and the number of bytes it takes, or None if bytes
+/// is empty or ends in the middle of a key.
+fn decode(bytes: &[u8]) -> Option<(Key, usize)> {
+    let first = *bytes.first()?;
+    match first {
+        b'\r' | b'\n' => Some((Key::Enter, 1)),
+        b'\t' => Some((Key::Tab, 1)),
+        0x7f | 0x08 => Some((Key::Backspace, 1)),
+        0x1b => decode_escape(bytes),
+        0x01..=0x1a => Some((Key::Ctrl((b'a' + first - 1) as char), 1)),
+        0x00..=0x1f => Some((Key::Unknown, 1)),
+        _ => {
+            let length = match first {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Some((Key::Unknown, 1)),
+            };
+            if bytes.len() < length {
+                return None;
+            }
+            match std::str::from_utf8(&bytes[..length]) {
+                Ok(text) => text.chars().next().map(|c| (Key::Char(c), length)),
+                Err(_) => Some((Key::Unknown, 1)),
+            }
+        },
+    }
+}
+
+/// Decode a key that starts with ESC: the Escape key itself, or a CSI
+/// or SS3 sequence like "\x1b[A". Modifiers, like Ctrl in "\x1b[1;5A",
+/// are ignored.
+fn decode_escape(bytes: &[u8]) -> Option<(Key, usize)> {
+    match bytes.get(1) {
+        None => return Some((Key::Escape, 1)),
+        Some(b'[') | Some(b'O') => (),
+        // Alt+key: the Escape is reported, the key is read next
+        Some(_) => return Some((Key::Escape, 1)),
+    }
+    // parameters, then a final byte
+    let end = bytes[2..].iter().position(|&byte| (0x40..=0x7e).contains(&byte))? + 2;
+    let parameters = String::from_utf8_lossy(&bytes[2..end]);
+    let key = match bytes[end] {
+        b'A' => Key::ArrowUp,
+        b'B' => Key::ArrowDown,
+        b'C' => Key::ArrowRight,
+        b'D' => Key::ArrowLeft,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        b'Z' => Key::BackTab,
+        b'~' => match parameters.split(';').next() {
+            Some("1") | Some("7") => Key::Home,
+            Some("4") | Some("8") => Key::End,
+            Some("3") => Key::Delete,
+            Some("5") => Key::PageUp,
+            Some("6") => Key::PageDown,
+            _ => Key::Unknown,
+        },
+        _ => Key::Unknown,
+    };
+    Some((key, end + 1))
+}
+
+
+/// The terminal in raw mode: keys are delivered as soon as they are
+/// pressed, without being shown, and Ctrl+C doesn't stop the program
+/// but is delivered as Key::Ctrl('c'). This is what interactive widgets
+/// need. The original mode is restored when RawMode is dropped, even
+/// when a panic unwinds the stack.
+/// Output is not changed: "\n" still moves to the beginning of the
+/// next line.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::input::{Key, RawMode};
+/// let mut raw = RawMode::enable().unwrap();
+/// loop {
+///     match raw.read_key().unwrap() {
+///         Key::Escape | Key::Ctrl('c') => break,
+///         key => println!("{:?}\r", key),
+///     }
+/// }
+/// ```
+#[cfg(unix)]
+pub struct RawMode {
+    tty: ::std::fs::File,
+    original: termios::Termios,
+    /// Bytes read but not decoded yet, like the rest of a paste.
+    pending: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl RawMode {
+    /// Switch the terminal to raw mode. The keys are read from the
+    /// controlling terminal, even if the standard input is redirected.
+    /// Return an error if there is no terminal.
+    pub fn enable() -> io::Result<RawMode> {
+        use std::fs::OpenOptions;
+        use std::os::unix::io::AsRawFd;
+        use self::termios::{tcsetattr, Termios, ECHO, ICANON, ICRNL, IEXTEN, ISIG, IXON, TCSANOW, VMIN, VTIME};
+
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        let original = Termios::from_fd(tty.as_raw_fd())?;
+        let mut raw = original;
+        raw.c_lflag &= !(ICANON | ECHO | ISIG | IEXTEN);
+        raw.c_iflag &= !(IXON | ICRNL);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        tcsetattr(tty.as_raw_fd(), TCSANOW, &raw)?;
+        Ok(RawMode { tty, original, pending: Vec::new() })
+    }
+
+    /// Wait for a key and return it.
+    pub fn read_key(&mut self) -> io::Result<Key> {
+        use std::io::Read;
+
+        loop {
+            if let Some((key, length)) = decode(&self.pending) {
+                self.pending.drain(..length);
+                return Ok(key);
+            }
+            let mut buffer = [0; 64];
+            let count = self.tty.read(&mut buffer)?;
+            if count == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "The terminal was closed"));
+            }
+            self.pending.extend_from_slice(&buffer[..count]);
+        }
+    }
+
+    /// Wait for a key for timeout at most, and return it, or None if
+    /// no key was pressed. Useful for screens that are updated while
+    /// waiting for keys. The timeout is rounded up to tenths of seconds,
+    /// and can't exceed 25.5 seconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait.
+    pub fn poll_key(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        use std::io::Read;
+        use std::os::unix::io::AsRawFd;
+        use self::termios::{tcsetattr, Termios, TCSANOW, VMIN, VTIME};
+
+        if let Some((key, length)) = decode(&self.pending) {
+            self.pending.drain(..length);
+            return Ok(Some(key));
+        }
+        // read returns 0 bytes if nothing arrives within VTIME tenths of second
+        let fd = self.tty.as_raw_fd();
+        let mut polling = Termios::from_fd(fd)?;
+        let tenths = timeout.as_millis().div_ceil(100);
+        polling.c_cc[VMIN] = 0;
+        polling.c_cc[VTIME] = tenths.clamp(1, 255) as u8;
+        tcsetattr(fd, TCSANOW, &polling)?;
+        let mut buffer = [0; 64];
+        let read = self.tty.read(&mut buffer);
+        polling.c_cc[VMIN] = 1;
+        polling.c_cc[VTIME] = 0;
+        tcsetattr(fd, TCSANOW, &polling)?;
+        let count = read?;
+        self.pending.extend_from_slice(&buffer[..count]);
+        match decode(&self.pending) {
+            Some((key, length)) => {
+                self.pending.drain(..length);
+                Ok(Some(key))
+            },
+            // nothing, or the beginning of a key that the next read completes
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        use std::os::unix::io::AsRawFd;
+        // nothing can be done if this fails
+        let _ = termios::tcsetattr(self.tty.as_raw_fd(), termios::TCSANOW, &self.original);
+    }
+}
+
+/// The terminal in raw mode. On this system keys are read by the
+/// console crate, that switches to raw mode for every key.
+#[cfg(not(unix))]
+pub struct RawMode {
+    /// The keys read by a thread, once poll_key() has been called.
+    keys: Option<mpsc::Receiver<io::Result<Key>>>,
+}
+
+#[cfg(not(unix))]
+impl RawMode {
+    /// Prepare to read keys. Return an error if there is no terminal.
+    pub fn enable() -> io::Result<RawMode> {
+        if console::Term::stderr().is_term() {
+            Ok(RawMode { keys: None })
+        } else {
+            Err(io::Error::other("Not a terminal"))
+        }
+    }
+
+    /// Wait for a key and return it.
+    pub fn read_key(&mut self) -> io::Result<Key> {
+        match self.keys {
+            Some(ref keys) => keys.recv().unwrap_or_else(|_| Err(reader_stopped())),
+            None => console_key(),
+        }
+    }
+
+    /// Wait for a key for timeout at most, and return it, or None if
+    /// no key was pressed. Useful for screens that are updated while
+    /// waiting for keys. On this system keys can't be read with a
+    /// timeout, so the first call starts a thread that reads them;
+    /// when RawMode is dropped, the thread stops after the next key.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait.
+    pub fn poll_key(&mut self, timeout: Duration) -> io::Result<Option<Key>> {
+        let keys = self.keys.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || loop {
+                let key = console_key();
+                let failed = key.is_err();
+                // stop when RawMode is dropped, or the terminal fails
+                if sender.send(key).is_err() || failed {
+                    break;
+                }
+            });
+            receiver
+        });
+        match keys.recv_timeout(timeout) {
+            Ok(key) => key.map(Some),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(None),
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(reader_stopped()),
+        }
+    }
+}
+
+/// Wait for a key in raw mode, with the console crate, and return it.
+#[cfg(not(unix))]
+fn console_key() -> io::Result<Key> {
+    use self::console::Key as ConsoleKey;
+    Ok(match console::Term::stderr().read_key()? {
+        ConsoleKey::Char('\u{3}') => Key::Ctrl('c'),
+        ConsoleKey::Char(c) => Key::Char(c),
+        ConsoleKey::Enter => Key::Enter,
+        ConsoleKey::Tab => Key::Tab,
+        ConsoleKey::Backspace => Key::Backspace,
+        ConsoleKey::Del => Key::Delete,
+        ConsoleKey::Escape => Key::Escape,
+        ConsoleKey::ArrowUp => Key::ArrowUp,
+        ConsoleKey::ArrowDown => Key::ArrowDown,
+        ConsoleKey::ArrowLeft => Key::ArrowLeft,
+        ConsoleKey::ArrowRight => Key::ArrowRight,
+        ConsoleKey::Home => Key::Home,
+        ConsoleKey::End => Key::End,
+        _ => Key::Unknown,
+    })
+}
+
+/// Return the error of a RawMode whose key reading thread stopped,
+/// after returning the error that stopped it.
+#[cfg(not(unix))]
+fn reader_stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::UnexpectedEof, "The terminal can't be read")
+}
+
+/// Wait for a single key, in raw mode, and return it.
+/// Return an error if there is no terminal.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::input::{self, Key};
+/// println!("Press any key to engage");
+/// if input::read_key().unwrap() != Key::Escape {
+///     println!("Engaged");
+/// }
+/// ```
+pub fn read_key() -> io::Result<Key> {
+    RawMode::enable()?.read_key()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode all the keys in bytes.
+    fn decode_all(mut bytes: &[u8]) -> Vec<Key> {
+        let mut keys = Vec::new();
+        while let Some((key, length)) = decode(bytes) {
+            keys.push(key);
+            bytes = &bytes[length..];
+        }
+        keys
+    }
+
+    #[test]
+    fn decode_keys() {
+        assert_eq!(decode_all(b"a\r\x7f\t\x03"), vec![
+            Key::Char('a'), Key::Enter, Key::Backspace, Key::Tab, Key::Ctrl('c'),
+        ]);
+        assert_eq!(decode_all("é日".as_bytes()), vec![Key::Char('é'), Key::Char('日')]);
+        // incomplete characters wait for more bytes
+        assert_eq!(decode(&"é".as_bytes()[..1]), None);
+        assert_eq!(decode(b""), None);
+    }
+
+    #[test]
+    fn decode_escape_sequences() {
+        assert_eq!(decode_all(b"\x1b[A\x1b[B\x1bOC\x1b[1;5D\x1b[3~\x1b[5~\x1b[6~\x1b[H\x1b[4~\x1b[Z"), vec![
+            Key::ArrowUp, Key::ArrowDown, Key::ArrowRight, Key::ArrowLeft, Key::Delete,
+            Key::PageUp, Key::PageDown, Key::Home, Key::End, Key::BackTab,
+        ]);
+        assert_eq!(decode_all(b"\x1b"), vec![Key::Escape]);
+        assert_eq!(decode_all(b"\x1bx"), vec![Key::Escape, Key::Char('x')]);
+        assert_eq!(decode_all(b"\x1b[15~"), vec![Key::Unknown]);
+        // incomplete sequences wait for more bytes
+        assert_eq!(decode(b"\x1b[1;5"), None);
+    }
+}
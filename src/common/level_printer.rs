@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use stylesheet::Stylesheet;
+
+
+/// The levels of LevelPrinter::default(), from the least important.
+/// Every level uses the style with the same name.
+pub const DEFAULT_LEVELS: [&str; 5] = ["debug", "info", "success", "warning", "danger"];
+
+
+/// The error returned when a level is not known by a LevelPrinter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownLevel {
+    pub level: String,
+}
+
+impl fmt::Display for UnknownLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown level \"{}\"", self.level)
+    }
+}
+
+impl Error for UnknownLevel {}
+
+
+/// A level known by a LevelPrinter.
+#[derive(Debug, Clone)]
+struct Level {
+    name: String,
+    style_name: String,
+    count: usize,
+}
+
+/// Print messages that have a level, like "info" or "danger", with the
+/// style of their level, skipping the levels less important than
+/// a minimum, and count the lines printed per level.
+/// Levels are known in order of importance. Messages with unknown levels
+/// are always printed, with the style named after their level, and
+/// counted. To print from several threads, keep the LevelPrinter in
+/// a Mutex.
+///
+/// # Example
+///
+/// ```
+/// use common::level_printer::LevelPrinter;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut printer = LevelPrinter::default();
+/// printer.set_min_level("info").unwrap();
+/// printer.println(&sheet, "debug", "Sensors calibrated").unwrap();
+/// printer.println(&sheet, "danger", "Borg cube approaching!").unwrap();
+/// assert_eq!(printer.count("debug"), 0);
+/// assert_eq!(printer.count("danger"), 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LevelPrinter {
+    /// The known levels, from the least important.
+    levels: Vec<Level>,
+    /// Messages of levels before this index are not printed.
+    min_level: usize,
+    /// Levels that were printed but are not known, in order of appearance.
+    unknown: Vec<Level>,
+}
+
+impl Default for LevelPrinter {
+    fn default() -> Self {
+        LevelPrinter::new(&DEFAULT_LEVELS)
+    }
+}
+
+impl LevelPrinter {
+    /// Return a LevelPrinter that prints all the levels.
+    ///
+    /// # Arguments
+    ///
+    /// * `levels` - The known levels, from the least important. Every
+    ///   level uses the style with the same name. If it is empty, all
+    ///   the levels are unknown, so all the messages are printed.
+    pub fn new(levels: &[&str]) -> LevelPrinter {
+        LevelPrinter {
+            levels: levels.iter().map(|name| Level::new(name, name)).collect(),
+            min_level: 0,
+            unknown: Vec::new(),
+        }
+    }
+
+    /// Use another style for a level. Return an error if the level is
+    /// not known.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The name of the level.
+    /// * `style_name` - The style of its messages.
+    pub fn set_style(&mut self, level: &str, style_name: &str) -> Result<(), UnknownLevel> {
+        let index = self.index(level)?;
+        self.levels[index].style_name = style_name.to_string();
+        Ok(())
+    }
+
+    /// Don't print the messages of levels less important than level.
+    /// Return an error if the level is not known.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - The least important level to print.
+    pub fn set_min_level(&mut self, level: &str) -> Result<(), UnknownLevel> {
+        self.min_level = self.index(level)?;
+        Ok(())
+    }
+
+    /// Return the least important level that is printed, or None if
+    /// no levels are known.
+    pub fn min_level(&self) -> Option<&str> {
+        self.levels.get(self.min_level).map(|level| level.name.as_str())
+    }
+
+    /// Return the position of a known level.
+    fn index(&self, level: &str) -> Result<usize, UnknownLevel> {
+        self.levels.iter()
+            .position(|known| known.name == level)
+            .ok_or_else(|| UnknownLevel { level: level.to_string() })
+    }
+
+    /// Return true if the messages of a level are printed.
+    pub fn shows(&self, level: &str) -> bool {
+        self.index(level).map_or(true, |index| index >= self.min_level)
+    }
+
+    /// Print a message with the style of its level, like
+    /// Stylesheet::try_println(), if the level is printed.
+    /// Return true if the message was printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `level` - The level of the message.
+    /// * `message` - The text to print.
+    pub fn println<S>(&mut self, sheet: &Stylesheet, level: &str, message: S) -> io::Result<bool> where S: AsRef<str> {
+        if !self.shows(level) {
+            return Ok(false);
+        }
+        let printed = match self.index(level) {
+            Ok(index) => &mut self.levels[index],
+            Err(_) => {
+                if !self.unknown.iter().any(|unknown| unknown.name == level) {
+                    self.unknown.push(Level::new(level, level));
+                }
+                self.unknown.iter_mut().find(|unknown| unknown.name == level).unwrap()
+            },
+        };
+        sheet.try_println(&printed.style_name, message)?;
+        printed.count += 1;
+        Ok(true)
+    }
+
+    /// Return the number of messages of a level that were printed.
+    pub fn count(&self, level: &str) -> usize {
+        self.levels.iter().chain(self.unknown.iter())
+            .find(|known| known.name == level)
+            .map_or(0, |known| known.count)
+    }
+
+    /// Return the number of messages printed, of all the levels.
+    pub fn total(&self) -> usize {
+        self.levels.iter().chain(self.unknown.iter()).map(|level| level.count).sum()
+    }
+
+    /// Return the levels and the number of messages printed, for the
+    /// levels that have messages: the known levels from the most important,
+    /// then the unknown levels.
+    pub fn counts(&self) -> Vec<(&str, usize)> {
+        self.levels.iter().rev().chain(self.unknown.iter())
+            .filter(|level| level.count > 0)
+            .map(|level| (level.name.as_str(), level.count))
+            .collect()
+    }
+
+    /// Forget the number of messages printed.
+    pub fn reset_counts(&mut self) {
+        for level in self.levels.iter_mut().chain(self.unknown.iter_mut()) {
+            level.count = 0;
+        }
+    }
+}
+
+impl Level {
+    fn new(name: &str, style_name: &str) -> Level {
+        Level { name: name.to_string(), style_name: style_name.to_string(), count: 0 }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor, StyleProperties};
+    use test_support::SharedBuffer;
+
+    #[test]
+    fn filter_and_count() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("alert", StyleProperties::builder().color(StyleColor::Red).build());
+        let mut printer = LevelPrinter::new(&["info", "danger"]);
+        printer.set_style("danger", "alert").unwrap();
+        printer.set_min_level("danger").unwrap();
+        assert_eq!(printer.set_min_level("trace"), Err(UnknownLevel { level: "trace".to_string() }));
+        assert_eq!(printer.min_level(), Some("danger"));
+
+        assert!(!printer.println(&sheet, "info", "Wormhole detected").unwrap());
+        assert!(printer.println(&sheet, "danger", "Borg cube").unwrap());
+        assert!(printer.println(&sheet, "danger", "Romulan warbird").unwrap());
+        // unknown levels are always printed
+        assert!(printer.println(&sheet, "hail", "Incoming message").unwrap());
+        assert_eq!(
+            buffer.contents(),
+            "\x1b[31mBorg cube\x1b[0m\n\x1b[31mRomulan warbird\x1b[0m\nIncoming message\n"
+        );
+        assert_eq!(printer.counts(), vec![("danger", 2), ("hail", 1)]);
+        assert_eq!(printer.count("info"), 0);
+        assert_eq!(printer.total(), 3);
+        printer.reset_counts();
+        assert_eq!(printer.total(), 0);
+    }
+
+    #[test]
+    fn no_levels() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        let mut printer = LevelPrinter::new(&[]);
+        assert_eq!(printer.min_level(), None);
+        assert!(printer.println(&sheet, "info", "Wormhole detected").unwrap());
+        assert_eq!(printer.counts(), vec![("info", 1)]);
+    }
+}
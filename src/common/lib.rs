@@ -1,2 +1,45 @@
+#[macro_use]
+extern crate lazy_static;
+
+#[macro_use]
+mod macros;
+
+#[doc(hidden)]
+pub mod macro_support;
+pub mod ansi;
+#[cfg(feature = "ansi_art")]
+pub mod ansi_art;
+pub mod chart;
+pub mod columns;
+pub mod config;
+pub mod dashboard;
+pub mod diff;
+pub mod heatmap;
+pub mod highlight;
+pub mod icons;
+pub mod input;
+pub mod level_printer;
+pub mod list;
+pub mod markdown;
+pub mod markup;
+pub mod multi_progress;
+pub mod notification;
+pub mod progress;
+pub mod prompt;
+#[cfg(feature = "qr")]
+pub mod qr;
 pub mod scanner;
+pub mod shared_stylesheet;
+pub mod spinner;
+pub mod status_line;
+pub mod styled_string;
 pub mod stylesheet;
+#[cfg(feature = "syntax")]
+pub mod syntax;
+pub mod term;
+pub mod terminal;
+#[cfg(test)]
+mod test_support;
+pub mod text;
+pub mod themes;
+pub mod tree;
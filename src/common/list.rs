@@ -0,0 +1,248 @@
+use stylesheet::Stylesheet;
+use text;
+
+
+/// The style of the bullets and numbers of lists.
+pub const MARKER_STYLE: &str = "list_marker";
+
+/// Bullets used by Marker::Bullet, one per depth. Deeper lists
+/// start again from the first one.
+pub const UNICODE_BULLETS: [&str; 3] = ["•", "◦", "▪"];
+/// Bullets used by Marker::Bullet if Unicode is not enabled.
+pub const ASCII_BULLETS: [&str; 3] = ["*", "-", "+"];
+
+
+/// What precedes the items of a List.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+    /// A bullet that depends on the depth of the list.
+    /// See UNICODE_BULLETS and ASCII_BULLETS.
+    Bullet,
+    /// The same text for every item, like "-" or "→".
+    Custom(String),
+    /// "1.", "2.", and so on.
+    Numbered,
+    /// "a.", "b.", and so on, up to "z.", then "aa.".
+    Lettered,
+}
+
+impl Marker {
+    /// Return the marker of an item.
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - The number of the item, starting from the list start.
+    /// * `depth` - The depth of the list, 0 for the outermost one.
+    /// * `unicode` - true if Unicode bullets can be used.
+    pub fn text(&self, number: usize, depth: usize, unicode: bool) -> String {
+        match self {
+            Marker::Bullet => {
+                let bullets = if unicode { UNICODE_BULLETS } else { ASCII_BULLETS };
+                bullets[depth % bullets.len()].to_string()
+            },
+            Marker::Custom(text) => text.clone(),
+            Marker::Numbered => format!("{}.", number),
+            Marker::Lettered => format!("{}.", letters(number)),
+        }
+    }
+}
+
+/// Return a number as letters: 1 is "a", 26 is "z", 27 is "aa".
+/// 0 is "".
+fn letters(mut number: usize) -> String {
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push((b'a' + (number % 26) as u8) as char);
+        number /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+
+/// An item of a List, with an optional style and an optional nested list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListItem {
+    /// The text of the item. It can span several lines.
+    pub text: String,
+    /// The style of the text, or None for the default style.
+    pub style: Option<String>,
+    /// A list shown under the text, indented.
+    pub sublist: Option<List>,
+}
+
+impl ListItem {
+    /// Return an item without style and without nested list.
+    pub fn new<S>(text: S) -> ListItem where S: Into<String> {
+        ListItem { text: text.into(), style: None, sublist: None }
+    }
+
+    /// Return the item with a style for its text.
+    pub fn with_style(mut self, style_name: &str) -> ListItem {
+        self.style = Some(style_name.to_string());
+        self
+    }
+
+    /// Return the item with a nested list.
+    pub fn with_sublist(mut self, sublist: List) -> ListItem {
+        self.sublist = Some(sublist);
+        self
+    }
+}
+
+
+/// A list printed by Stylesheet::print_list(): every item is preceded
+/// by a marker, like a bullet or a number. The markers are aligned to
+/// the right, and the text of the items is aligned to the left, so that
+/// "9." and "10." line up. Lines after the first one, and nested lists,
+/// are indented under the text.
+///
+/// # Example
+///
+/// ```
+/// use common::list::{List, ListItem};
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let findings = List::numbered()
+///     .item("Whormhole detected")
+///     .with_item(ListItem::new("Hostile ships").with_sublist(
+///         List::bullets()
+///             .styled_item("danger", "Borg cube")
+///             .styled_item("danger", "Romulan warbird")
+///     ));
+/// sheet.print_list(&findings);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct List {
+    pub marker: Marker,
+    /// The number of the first item, for Numbered and Lettered markers.
+    pub start: usize,
+    pub items: Vec<ListItem>,
+}
+
+impl List {
+    /// Return an empty list with the given marker, starting from 1.
+    pub fn new(marker: Marker) -> List {
+        List { marker, start: 1, items: Vec::new() }
+    }
+
+    /// Return an empty bulleted list.
+    pub fn bullets() -> List {
+        List::new(Marker::Bullet)
+    }
+
+    /// Return an empty numbered list.
+    pub fn numbered() -> List {
+        List::new(Marker::Numbered)
+    }
+
+    /// Return an empty list numbered with letters.
+    pub fn lettered() -> List {
+        List::new(Marker::Lettered)
+    }
+
+    /// Return the list numbered from start.
+    pub fn with_start(mut self, start: usize) -> List {
+        self.start = start;
+        self
+    }
+
+    /// Return the list with an item added after the others.
+    pub fn with_item(mut self, item: ListItem) -> List {
+        self.items.push(item);
+        self
+    }
+
+    /// Return the list with an item without style added after the others.
+    pub fn item<S>(self, text: S) -> List where S: Into<String> {
+        self.with_item(ListItem::new(text))
+    }
+
+    /// Return the list with a styled item added after the others.
+    pub fn styled_item<S>(self, style_name: &str, text: S) -> List where S: Into<String> {
+        self.with_item(ListItem::new(text).with_style(style_name))
+    }
+
+    /// Add an item after the others.
+    pub fn push(&mut self, item: ListItem) {
+        self.items.push(item);
+    }
+
+    /// Return the number of items, not counting nested lists.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Return true if the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+
+/// Return a list, one item per line. See Stylesheet::format_list().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `list` - The list.
+pub fn format_list(sheet: &Stylesheet, list: &List) -> String {
+    let mut lines = Vec::new();
+    list_lines(sheet, list, 0, "", &mut lines);
+    lines.join("\n")
+}
+
+/// Add the lines of list and its nested lists to lines, each
+/// preceded by indent.
+fn list_lines(sheet: &Stylesheet, list: &List, depth: usize, indent: &str, lines: &mut Vec<String>) {
+    let markers: Vec<String> = (0..list.items.len())
+        .map(|i| list.marker.text(list.start + i, depth, sheet.unicode_enabled()))
+        .collect();
+    let width = markers.iter().map(|marker| text::visible_width(marker)).max().unwrap_or(0);
+    let item_indent = format!("{}{}", indent, " ".repeat(width + 1));
+
+    for (item, marker) in list.items.iter().zip(markers) {
+        let padding = " ".repeat(width - text::visible_width(&marker));
+        let marker = format!("{}{}{}", indent, padding, paint(sheet, Some(MARKER_STYLE), &marker));
+        let style_name = item.style.as_deref();
+        let mut item_lines = item.text.lines();
+        // an empty item still has its marker
+        match item_lines.next() {
+            Some(line) => lines.push(format!("{} {}", marker, paint(sheet, style_name, line))),
+            None => lines.push(marker),
+        }
+        for line in item_lines {
+            lines.push(format!("{}{}", item_indent, paint(sheet, style_name, line)));
+        }
+        if let Some(ref sublist) = item.sublist {
+            list_lines(sheet, sublist, depth + 1, &item_indent, lines);
+        }
+    }
+}
+
+/// Return text with a style, or as it is if there is no style or it
+/// doesn't exist: the styles of lists are optional, so they don't
+/// trigger the FallbackPolicy.
+fn paint(sheet: &Stylesheet, style_name: Option<&str>, text: &str) -> String {
+    match style_name {
+        Some(style_name) if sheet.contains(style_name) && !text.is_empty() => sheet.format(style_name, text),
+        _ => text.to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_texts() {
+        assert_eq!(Marker::Bullet.text(1, 0, true), "•");
+        assert_eq!(Marker::Bullet.text(1, 4, false), "-");
+        assert_eq!(Marker::Custom("→".to_string()).text(3, 0, true), "→");
+        assert_eq!(Marker::Numbered.text(10, 0, true), "10.");
+        assert_eq!(Marker::Lettered.text(2, 0, true), "b.");
+        assert_eq!(letters(26), "z");
+        assert_eq!(letters(28), "ab");
+    }
+}
@@ -0,0 +1,65 @@
+// Functions used by the stylesheet!() macro. Every transformation and
+// color name is a function that applies it to a StylePropertiesBuilder,
+// so that unknown names are reported at compile time.
+
+use stylesheet::StylePropertiesBuilder;
+use stylesheet::StyleColor::*;
+
+
+macro_rules! transformations {
+    ($($name:ident),*) => {
+        $(
+            pub fn $name(builder: StylePropertiesBuilder) -> StylePropertiesBuilder {
+                builder.$name()
+            }
+        )*
+    };
+}
+
+macro_rules! colors {
+    ($($name:ident, $background_name:ident => $color:ident;)*) => {
+        $(
+            pub fn $name(builder: StylePropertiesBuilder) -> StylePropertiesBuilder {
+                builder.color($color)
+            }
+
+            pub fn $background_name(builder: StylePropertiesBuilder) -> StylePropertiesBuilder {
+                builder.background($color)
+            }
+        )*
+    };
+}
+
+transformations!(
+    blink, bold, bright, dim, italic, underlined, double_underlined, curly_underlined, reverse, strikethrough, hidden
+);
+
+colors! {
+    default, on_default => DefaultColor;
+    black, on_black => Black;
+    white, on_white => White;
+    red, on_red => Red;
+    green, on_green => Green;
+    blue, on_blue => Blue;
+    cyan, on_cyan => Cyan;
+    magenta, on_magenta => Magenta;
+    yellow, on_yellow => Yellow;
+    bright_black, on_bright_black => BrightBlack;
+    bright_white, on_bright_white => BrightWhite;
+    bright_red, on_bright_red => BrightRed;
+    bright_green, on_bright_green => BrightGreen;
+    bright_blue, on_bright_blue => BrightBlue;
+    bright_cyan, on_bright_cyan => BrightCyan;
+    bright_magenta, on_bright_magenta => BrightMagenta;
+    bright_yellow, on_bright_yellow => BrightYellow;
+    orange, on_orange => Orange;
+    purple, on_purple => Purple;
+    pink, on_pink => Pink;
+    gray, on_gray => Gray;
+    teal, on_teal => Teal;
+    brown, on_brown => Brown;
+    olive, on_olive => Olive;
+    navy, on_navy => Navy;
+    maroon, on_maroon => Maroon;
+    gold, on_gold => Gold;
+}
@@ -0,0 +1,118 @@
+/// Build a String like format!(), but every placeholder can specify
+/// a style of the given Stylesheet. See Stylesheet::format_template().
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate common;
+/// use common::stylesheet::*;
+///
+/// fn main() {
+///     let mut sheet = Stylesheet::new();
+///     sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+///     let line = styled!(sheet, "Found {:danger} in sector {}", "Borg cube", 7);
+/// }
+/// ```
+#[macro_export]
+macro_rules! styled {
+    ($sheet:expr, $template:expr) => {
+        $sheet.format_template($template, &[])
+    };
+    ($sheet:expr, $template:expr, $($arg:expr),+ $(,)?) => {
+        $sheet.format_template($template, &[$(&$arg as &dyn (::std::fmt::Display)),+])
+    };
+}
+
+/// Same as styled!(), but it returns a Result: a malformed template,
+/// or a number of arguments that doesn't match the placeholders, is an
+/// error instead of a panic. See Stylesheet::try_format_template().
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate common;
+/// use common::stylesheet::*;
+///
+/// fn main() {
+///     let sheet = Stylesheet::new();
+///     assert!(try_styled!(sheet, "Found {:danger} in sector {}", "Borg cube").is_err());
+/// }
+/// ```
+#[macro_export]
+macro_rules! try_styled {
+    ($sheet:expr, $template:expr) => {
+        $sheet.try_format_template($template, &[])
+    };
+    ($sheet:expr, $template:expr, $($arg:expr),+ $(,)?) => {
+        $sheet.try_format_template($template, &[$(&$arg as &dyn (::std::fmt::Display)),+])
+    };
+}
+
+/// Print a line like println!(), but every placeholder can specify
+/// a style of the given Stylesheet. See Stylesheet::format_template().
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate common;
+/// use common::stylesheet::*;
+///
+/// fn main() {
+///     let mut sheet = Stylesheet::new();
+///     sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+///     sheet.add_style("info", StyleProperties::builder().color(StyleColor::Green).build());
+///     sprintln!(sheet, "Found {:danger} in sector {:info}", "Borg cube", 7);
+/// }
+/// ```
+#[macro_export]
+macro_rules! sprintln {
+    ($sheet:expr, $template:expr) => {
+        $sheet.println_template($template, &[])
+    };
+    ($sheet:expr, $template:expr, $($arg:expr),+ $(,)?) => {
+        $sheet.println_template($template, &[$(&$arg as &dyn (::std::fmt::Display)),+])
+    };
+}
+
+/// Declare a whole Stylesheet at once. Every style is written as
+/// `name: definition;`, where the definition is a list of
+/// transformation and color names, separated by `+` or `,`.
+/// Background colors are written as `on_<color>`.
+/// Unknown names are reported at compile time.
+/// Colors like rgb(), fixed() and hsl() require StyleProperties::builder().
+///
+/// # Example
+///
+/// ```
+/// #[macro_use]
+/// extern crate common;
+///
+/// fn main() {
+///     let sheet = stylesheet! {
+///         danger: bold + blink, red, on_white;
+///         info: green;
+///     };
+///     sheet.println("danger", "Some text");
+/// }
+/// ```
+#[macro_export]
+macro_rules! stylesheet {
+    ($($name:ident : $($first:ident $(+ $rest:ident)*),* ;)*) => {{
+        #[allow(unused_mut)]
+        let mut sheet = $crate::stylesheet::Stylesheet::new();
+        $(
+            let builder = $crate::stylesheet::StyleProperties::builder();
+            $(
+                let builder = $crate::macro_support::$first(builder);
+                $(
+                    let builder = $crate::macro_support::$rest(builder);
+                )*
+            )*
+            sheet.add_style(stringify!($name), builder.build());
+        )*
+        sheet
+    }};
+}
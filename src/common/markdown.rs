@@ -0,0 +1,672 @@
+use list::{self, List};
+use stylesheet::{Stylesheet, StyleColor, StyleProperties};
+#[cfg(feature = "syntax")]
+use syntax;
+use text;
+
+
+/// The styles of headings: "#" uses the first one, "##" the second one,
+/// and deeper headings the last one.
+pub const HEADING_STYLES: [&str; 3] = ["md_h1", "md_h2", "md_h3"];
+/// The style of *emphasized* texts.
+pub const EMPHASIS_STYLE: &str = "md_emphasis";
+/// The style of **strong** texts.
+pub const STRONG_STYLE: &str = "md_strong";
+/// The style of `code`, and of code blocks that are not highlighted.
+pub const CODE_STYLE: &str = "md_code";
+/// The style of links.
+pub const LINK_STYLE: &str = "md_link";
+/// The style of the bar before quotes.
+pub const QUOTE_STYLE: &str = "md_quote";
+
+
+/// A part of a line of Markdown text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    /// *text* or _text_.
+    Emphasis(String),
+    /// **text** or __text__.
+    Strong(String),
+    /// `text`.
+    Code(String),
+    /// [text](url).
+    Link { text: String, url: String },
+}
+
+impl Inline {
+    /// Return the text, without markers and urls.
+    pub fn text(&self) -> &str {
+        match self {
+            Inline::Text(text) | Inline::Emphasis(text) | Inline::Strong(text) | Inline::Code(text) => text,
+            Inline::Link { text, .. } => text,
+        }
+    }
+}
+
+/// A list, with its items and their nested lists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListBlock {
+    /// true for "1." lists, false for "-", "*" and "+" lists.
+    pub numbered: bool,
+    /// The number of the first item of numbered lists.
+    pub start: usize,
+    pub items: Vec<ListBlockItem>,
+}
+
+/// An item of a ListBlock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListBlockItem {
+    pub content: Vec<Inline>,
+    pub sublist: Option<ListBlock>,
+}
+
+/// A block of a Markdown text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    /// "# text", or a line underlined with "=" or "-". Levels start from 1.
+    Heading { level: usize, content: Vec<Inline> },
+    /// Lines of text, joined with spaces.
+    Paragraph(Vec<Inline>),
+    List(ListBlock),
+    /// A block fenced by ``` or ~~~, with the language written after
+    /// the opening fence, if any.
+    Code { language: Option<String>, code: String },
+    /// Lines starting with ">", that can contain other blocks.
+    Quote(Vec<Block>),
+    /// "---", "***" or "___".
+    Rule,
+}
+
+
+/// Parse the inline formatting of a text: emphasis, strong texts,
+/// code and links. A backslash escapes punctuation characters, and
+/// markers that are not closed are kept as they are. Nested formatting
+/// is not supported: the inner markers are kept as text.
+///
+/// # Arguments
+///
+/// * `text` - The text to parse.
+///
+/// # Example
+///
+/// ```
+/// use common::markdown::{self, Inline};
+/// assert_eq!(markdown::parse_inline("Shields at **12%**"), vec![
+///     Inline::Text("Shields at ".to_string()),
+///     Inline::Strong("12%".to_string()),
+/// ]);
+/// ```
+pub fn parse_inline(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let previous = plain.chars().last();
+        let parsed = match c {
+            '\\' => match rest[1..].chars().next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => {
+                    plain.push(escaped);
+                    rest = &rest[1 + escaped.len_utf8()..];
+                    continue;
+                },
+                _ => None,
+            },
+            '`' => delimited(rest, "`").map(|(code, length)| (Inline::Code(code.to_string()), length)),
+            '*' | '_' if rest[1..].starts_with(c) => {
+                delimited(rest, &rest[..2]).map(|(strong, length)| (Inline::Strong(strong.to_string()), length))
+            },
+            // "_" inside words, like in snake_case, is not a marker
+            '_' if previous.is_some_and(char::is_alphanumeric) => None,
+            '*' | '_' => delimited(rest, &rest[..1])
+                .filter(|(emphasis, length)| {
+                    !emphasis.starts_with(' ') &&
+                        (c == '*' || !rest[*length..].chars().next().is_some_and(char::is_alphanumeric))
+                })
+                .map(|(emphasis, length)| (Inline::Emphasis(emphasis.to_string()), length)),
+            '[' => link(rest),
+            _ => None,
+        };
+        match parsed {
+            Some((inline, length)) => {
+                if !plain.is_empty() {
+                    inlines.push(Inline::Text(plain.split_off(0)));
+                }
+                inlines.push(inline);
+                rest = &rest[length..];
+            },
+            None => {
+                plain.push(c);
+                rest = &rest[c.len_utf8()..];
+            },
+        }
+    }
+    if !plain.is_empty() {
+        inlines.push(Inline::Text(plain));
+    }
+    inlines
+}
+
+/// If text starts with marker, return the non-empty text before the
+/// next marker, and the length of the whole, markers included.
+fn delimited<'a>(text: &'a str, marker: &str) -> Option<(&'a str, usize)> {
+    let inner = &text[marker.len()..];
+    let end = inner.find(marker)?;
+    if end == 0 {
+        return None;
+    }
+    Some((&inner[..end], marker.len() * 2 + end))
+}
+
+/// If text starts with a link, return it and its length. The text of
+/// the link ends at the first "]", that must be followed by "(url)".
+fn link(text: &str) -> Option<(Inline, usize)> {
+    let text_end = text.find(']')?;
+    if !text[text_end + 1..].starts_with('(') {
+        return None;
+    }
+    let url_end = text[text_end..].find(')')? + text_end;
+    let link = Inline::Link {
+        text: text[1..text_end].to_string(),
+        url: text[text_end + 2..url_end].trim().to_string(),
+    };
+    Some((link, url_end + 1))
+}
+
+
+/// The marker of a list item: its indentation, whether it's numbered,
+/// its number, and the text after it.
+fn list_marker(line: &str) -> Option<(usize, bool, usize, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+    let (numbered, number, rest) = if trimmed.starts_with(['-', '*', '+']) {
+        (false, 0, &trimmed[1..])
+    } else {
+        let digits = trimmed.find(|c: char| !c.is_ascii_digit())?;
+        if digits == 0 || !trimmed[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        (true, trimmed[..digits].parse().ok()?, &trimmed[digits + 1..])
+    };
+    match rest.chars().next() {
+        None => Some((indent, numbered, number, "")),
+        Some(' ') | Some('\t') => Some((indent, numbered, number, rest.trim())),
+        Some(_) => None,
+    }
+}
+
+/// Return the level of a "#" heading and its text.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.find(|c| c != '#').unwrap_or(line.len());
+    if level == 0 || level > 6 {
+        return None;
+    }
+    match line[level..].chars().next() {
+        None | Some(' ') | Some('\t') => Some((level, line[level..].trim().trim_end_matches('#').trim_end())),
+        Some(_) => None,
+    }
+}
+
+/// Return true if line is a horizontal rule.
+fn is_rule(line: &str) -> bool {
+    let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+    chars.len() >= 3 && ['-', '*', '_'].iter().any(|marker| chars.iter().all(|c| c == marker))
+}
+
+/// Return the fence that opens a code block, like "```", and the text after it.
+fn fence(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    ["```", "~~~"].iter()
+        .find(|fence| trimmed.starts_with(*fence))
+        .map(|fence| (*fence, trimmed[fence.len()..].trim()))
+}
+
+/// Return true if line starts a block that interrupts a paragraph.
+fn starts_block(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    heading(trimmed).is_some() || fence(line).is_some() || trimmed.starts_with('>') ||
+        is_rule(line) || list_marker(line).is_some_and(|(_, _, _, text)| !text.is_empty())
+}
+
+
+/// Parse a Markdown text into blocks. The most common syntax is
+/// supported: headings, paragraphs, lists, fenced code blocks, quotes,
+/// rules, and the inline formatting of parse_inline(). HTML, tables
+/// and indented code blocks are shown as paragraphs.
+///
+/// # Arguments
+///
+/// * `source` - The Markdown text.
+///
+/// # Example
+///
+/// ```
+/// use common::markdown::{self, Block, Inline};
+/// let blocks = markdown::parse("# Scan report\n\nAll clear.");
+/// assert_eq!(blocks[1], Block::Paragraph(vec![Inline::Text("All clear.".to_string())]));
+/// ```
+pub fn parse(source: &str) -> Vec<Block> {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            i += 1;
+        } else if let Some((marker, info)) = fence(line) {
+            let language = info.split_whitespace().next().map(str::to_string);
+            let mut code = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim_start().starts_with(marker) {
+                code.push(lines[i]);
+                i += 1;
+            }
+            // skip the closing fence
+            i += 1;
+            blocks.push(Block::Code { language, code: code.join("\n") });
+        } else if let Some((level, text)) = heading(trimmed) {
+            blocks.push(Block::Heading { level, content: parse_inline(text) });
+            i += 1;
+        } else if is_rule(line) {
+            blocks.push(Block::Rule);
+            i += 1;
+        } else if trimmed.starts_with('>') {
+            let mut quoted = Vec::new();
+            while i < lines.len() && lines[i].trim_start().starts_with('>') {
+                let text = &lines[i].trim_start()[1..];
+                quoted.push(text.strip_prefix(' ').unwrap_or(text));
+                i += 1;
+            }
+            blocks.push(Block::Quote(parse(&quoted.join("\n"))));
+        } else if let Some((indent, ..)) = list_marker(line) {
+            blocks.push(Block::List(parse_list(&lines, &mut i, indent)));
+        } else {
+            let mut text = vec![trimmed];
+            i += 1;
+            while i < lines.len() && !lines[i].trim().is_empty() && !starts_block(lines[i]) {
+                text.push(lines[i].trim());
+                i += 1;
+            }
+            // a line of "=" or "-" under a paragraph makes it a heading
+            let underline = text.last().and_then(|last| last.chars().next())
+                .filter(|c| text.len() > 1 && (*c == '=' || *c == '-'))
+                .filter(|c| text.last().unwrap().chars().all(|other| other == *c));
+            if i < lines.len() && is_rule(lines[i]) && lines[i].trim().chars().all(|c| c == '-') && underline.is_none() {
+                blocks.push(Block::Heading { level: 2, content: parse_inline(&text.join(" ")) });
+                i += 1;
+            } else if let Some(c) = underline {
+                text.pop();
+                let level = if c == '=' { 1 } else { 2 };
+                blocks.push(Block::Heading { level, content: parse_inline(&text.join(" ")) });
+            } else {
+                blocks.push(Block::Paragraph(parse_inline(&text.join(" "))));
+            }
+        }
+    }
+    blocks
+}
+
+/// Parse the list that starts at lines[*i], indented by indent, and
+/// move i after it. More indented items form nested lists.
+fn parse_list(lines: &[&str], i: &mut usize, indent: usize) -> ListBlock {
+    let (_, numbered, start, _) = list_marker(lines[*i]).unwrap();
+    let mut list = ListBlock { numbered, start, items: Vec::new() };
+    // the text of the last item, parsed when the item is complete
+    let mut text = String::new();
+    let finish = |list: &mut ListBlock, text: &mut String| {
+        if let Some(item) = list.items.last_mut() {
+            item.content = parse_inline(text);
+        }
+        text.clear();
+    };
+
+    while *i < lines.len() {
+        let line = lines[*i];
+        if line.trim().is_empty() {
+            // a blank line ends the list, unless more items follow
+            let next = lines[*i..].iter().find(|line| !line.trim().is_empty());
+            match next.and_then(|next| list_marker(next)) {
+                Some((next_indent, ..)) if next_indent >= indent => *i += 1,
+                _ => break,
+            }
+            continue;
+        }
+        match list_marker(line) {
+            Some((item_indent, ..)) if item_indent < indent => break,
+            Some((item_indent, _, _, item_text)) if item_indent < indent + 2 || list.items.is_empty() => {
+                finish(&mut list, &mut text);
+                list.items.push(ListBlockItem { content: Vec::new(), sublist: None });
+                text.push_str(item_text);
+                *i += 1;
+            },
+            Some((item_indent, ..)) => {
+                let sublist = parse_list(lines, i, item_indent);
+                list.items.last_mut().unwrap().sublist = Some(sublist);
+            },
+            None if starts_block(line) => break,
+            None => {
+                // the continuation of the last item
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(line.trim());
+                *i += 1;
+            },
+        }
+    }
+    finish(&mut list, &mut text);
+    list
+}
+
+
+/// Return a Markdown text rendered for the terminal.
+/// See Stylesheet::format_markdown().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `source` - The Markdown text.
+/// * `width` - The maximum width of paragraphs.
+pub fn format_markdown(sheet: &Stylesheet, source: &str, width: usize) -> String {
+    block_lines(sheet, &parse(source), width).join("\n")
+}
+
+/// Return the lines of Markdown blocks, separated by empty lines.
+fn block_lines(sheet: &Stylesheet, blocks: &[Block], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            lines.push(String::new());
+        }
+        match block {
+            Block::Heading { level, content } => {
+                let style_name = HEADING_STYLES[(level - 1).min(HEADING_STYLES.len() - 1)];
+                // the plain parts have the heading style, the others keep theirs
+                let text: String = content.iter()
+                    .map(|inline| match inline {
+                        Inline::Text(text) => paint(sheet, style_name, text),
+                        _ => format_inline(sheet, inline),
+                    })
+                    .collect();
+                lines.push(text);
+            },
+            Block::Paragraph(content) => lines.extend(wrap_inline(sheet, content, width)),
+            Block::List(list) => {
+                lines.extend(list::format_list(sheet, &rendered_list(sheet, list)).split('\n').map(str::to_string));
+            },
+            Block::Code { language, code } => {
+                let code = format_code(sheet, language.as_deref(), code);
+                lines.extend(code.split('\n').map(|line| format!("    {}", line)));
+            },
+            Block::Quote(blocks) => {
+                let bar = paint(sheet, QUOTE_STYLE, if sheet.unicode_enabled() { "│ " } else { "| " });
+                let quoted = block_lines(sheet, blocks, width.saturating_sub(2));
+                lines.extend(quoted.iter().map(|line| format!("{}{}", bar, line)));
+            },
+            Block::Rule => {
+                let character = if sheet.unicode_enabled() { '─' } else { '-' };
+                lines.push(sheet.format_hr(Stylesheet::DEFAULT_STYLE, character, width));
+            },
+        }
+    }
+    lines
+}
+
+/// Return a part of a Markdown line with its style.
+fn format_inline(sheet: &Stylesheet, inline: &Inline) -> String {
+    match inline {
+        Inline::Text(text) => text.clone(),
+        Inline::Emphasis(text) => paint(sheet, EMPHASIS_STYLE, text),
+        Inline::Strong(text) => paint(sheet, STRONG_STYLE, text),
+        Inline::Code(text) => paint(sheet, CODE_STYLE, text),
+        Inline::Link { text, url } => sheet.format_link(sheet.existing_style(LINK_STYLE), text, url),
+    }
+}
+
+/// Return styled Markdown text wrapped to width. Links are never split.
+fn wrap_inline(sheet: &Stylesheet, content: &[Inline], width: usize) -> Vec<String> {
+    // the styled words, with their widths
+    let mut words: Vec<(String, usize)> = Vec::new();
+    let mut word = (String::new(), 0);
+    for inline in content {
+        if let Inline::Link { .. } = inline {
+            let link = format_inline(sheet, inline);
+            word.1 += text::visible_width(&link);
+            word.0.push_str(&link);
+            continue;
+        }
+        for (i, part) in inline.text().split(char::is_whitespace).enumerate() {
+            if i > 0 && word.1 > 0 {
+                words.push(::std::mem::take(&mut word));
+            }
+            if !part.is_empty() {
+                word.0.push_str(&format_inline(sheet, &match inline {
+                    Inline::Emphasis(_) => Inline::Emphasis(part.to_string()),
+                    Inline::Strong(_) => Inline::Strong(part.to_string()),
+                    Inline::Code(_) => Inline::Code(part.to_string()),
+                    _ => Inline::Text(part.to_string()),
+                }));
+                word.1 += text::visible_width(part);
+            }
+        }
+    }
+    if word.1 > 0 {
+        words.push(word);
+    }
+
+    let mut lines = Vec::new();
+    let mut line = (String::new(), 0);
+    for (word, word_width) in words {
+        if line.1 > 0 && line.1 + 1 + word_width > width {
+            lines.push(::std::mem::take(&mut line).0);
+        }
+        if line.1 > 0 {
+            line.0.push(' ');
+            line.1 += 1;
+        }
+        line.0.push_str(&word);
+        line.1 += word_width;
+    }
+    if line.1 > 0 || lines.is_empty() {
+        lines.push(line.0);
+    }
+    lines
+}
+
+/// Return a Markdown list as a List, with styled items.
+fn rendered_list(sheet: &Stylesheet, list: &ListBlock) -> List {
+    let mut rendered = if list.numbered { List::numbered().with_start(list.start) } else { List::bullets() };
+    for item in &list.items {
+        let text: String = item.content.iter().map(|inline| format_inline(sheet, inline)).collect();
+        let mut rendered_item = list::ListItem::new(text);
+        if let Some(ref sublist) = item.sublist {
+            rendered_item = rendered_item.with_sublist(rendered_list(sheet, sublist));
+        }
+        rendered.push(rendered_item);
+    }
+    rendered
+}
+
+/// Return a Markdown code block, highlighted if the language is supported.
+#[cfg(feature = "syntax")]
+fn format_code(sheet: &Stylesheet, language: Option<&str>, code: &str) -> String {
+    match language.and_then(|language| language.parse().ok()) {
+        Some(language) => syntax::format_code(sheet, code, language),
+        None => format_plain_code(sheet, code),
+    }
+}
+
+/// Return a Markdown code block.
+#[cfg(not(feature = "syntax"))]
+fn format_code(sheet: &Stylesheet, _language: Option<&str>, code: &str) -> String {
+    format_plain_code(sheet, code)
+}
+
+/// Return a Markdown code block with CODE_STYLE.
+fn format_plain_code(sheet: &Stylesheet, code: &str) -> String {
+    code.split('\n')
+        .map(|line| paint(sheet, CODE_STYLE, line))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Return text with a style, or with the default style if it doesn't
+/// exist: the styles of Markdown are optional, so they don't trigger
+/// the FallbackPolicy.
+fn paint(sheet: &Stylesheet, style_name: &str, text: &str) -> String {
+    sheet.format(sheet.existing_style(style_name), text)
+}
+
+
+/// Add the styles used to render Markdown, with bold and underlined
+/// headings, italic emphasis and colored code and links. Styles with
+/// the same names are replaced, so add them before loading the user
+/// configuration.
+///
+/// # Arguments
+///
+/// * `sheet` - The stylesheet to modify.
+pub fn add_markdown_styles(sheet: &mut Stylesheet) {
+    sheet.add_styles(vec![
+        (HEADING_STYLES[0], StyleProperties::builder().bold().underlined().build()),
+        (HEADING_STYLES[1], StyleProperties::builder().bold().build()),
+        (HEADING_STYLES[2], StyleProperties::builder().underlined().build()),
+        (EMPHASIS_STYLE, StyleProperties::builder().italic().build()),
+        (STRONG_STYLE, StyleProperties::builder().bold().build()),
+        (CODE_STYLE, StyleProperties::builder().color(StyleColor::Yellow).build()),
+        (LINK_STYLE, StyleProperties::builder().color(StyleColor::Blue).underlined().build()),
+        (QUOTE_STYLE, StyleProperties::builder().dim().build()),
+    ]);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+    use stylesheet::StyleColor::Cyan;
+
+    fn text(text: &str) -> Inline {
+        Inline::Text(text.to_string())
+    }
+
+    #[test]
+    fn inline_formatting() {
+        assert_eq!(parse_inline("*Borg* cube, __shields__ `down`, [chart](https://example.com)"), vec![
+            Inline::Emphasis("Borg".to_string()),
+            text(" cube, "),
+            Inline::Strong("shields".to_string()),
+            text(" "),
+            Inline::Code("down".to_string()),
+            text(", "),
+            Inline::Link { text: "chart".to_string(), url: "https://example.com".to_string() },
+        ]);
+        // markers that are not closed, escaped or inside words are text
+        assert_eq!(parse_inline("min_threat * 2 \\*not\\* **open"), vec![text("min_threat * 2 *not* **open")]);
+        assert_eq!(parse_inline(""), vec![]);
+        // the text of a link ends at the first "]"
+        assert_eq!(parse_inline("[x] done, see [log](l.txt)"), vec![
+            text("[x] done, see "),
+            Inline::Link { text: "log".to_string(), url: "l.txt".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn blocks() {
+        let source = concat!(
+            "Scan report\n",
+            "===========\n",
+            "\n",
+            "## Threats ##\n",
+            "Two ships\n",
+            "detected.\n",
+            "> **Red alert**\n",
+            "---\n",
+            "```toml\n",
+            "min_threat = 50\n",
+            "\n",
+            "```\n",
+        );
+        assert_eq!(parse(source), vec![
+            Block::Heading { level: 1, content: vec![text("Scan report")] },
+            Block::Heading { level: 2, content: vec![text("Threats")] },
+            Block::Paragraph(vec![text("Two ships detected.")]),
+            Block::Quote(vec![Block::Paragraph(vec![Inline::Strong("Red alert".to_string())])]),
+            Block::Rule,
+            Block::Code { language: Some("toml".to_string()), code: "min_threat = 50\n".to_string() },
+        ]);
+    }
+
+    #[test]
+    fn lists() {
+        let source = "3. Sol\n   - Earth\n     and the Moon\n   - Mars\n\n4. Wolf 359\n\nEnd";
+        let item = |content: &str, sublist: Option<ListBlock>| ListBlockItem { content: vec![text(content)], sublist };
+        assert_eq!(parse(source), vec![
+            Block::List(ListBlock {
+                numbered: true,
+                start: 3,
+                items: vec![
+                    item("Sol", Some(ListBlock {
+                        numbered: false,
+                        start: 0,
+                        items: vec![item("Earth and the Moon", None), item("Mars", None)],
+                    })),
+                    item("Wolf 359", None),
+                ],
+            }),
+            Block::Paragraph(vec![text("End")]),
+        ]);
+    }
+
+    #[test]
+    fn render() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        let source = concat!(
+            "# Scan report\n",
+            "Two *hostile* ships detected near `Wolf 359`, see the [chart](https://example.com).\n",
+            "- Borg cube\n",
+            "  1. threat: 95\n",
+            "> Red alert\n",
+            "\n",
+            "```\n",
+            "shields = 12\n",
+            "```\n",
+            "***",
+        );
+        assert_eq!(format_markdown(&sheet, source, 30), [
+            "Scan report",
+            "",
+            "Two hostile ships detected",
+            "near Wolf 359, see the",
+            "chart (https://example.com).",
+            "",
+            "* Borg cube",
+            "  1. threat: 95",
+            "",
+            "| Red alert",
+            "",
+            "    shields = 12",
+            "",
+            &"-".repeat(30),
+        ].join("\n"));
+
+        // words keep their styles when they are wrapped
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style(STRONG_STYLE, StyleProperties::builder().bold().build());
+        assert_eq!(
+            format_markdown(&sheet, "**red alert**!", 5),
+            "\x1b[1mred\x1b[0m\n\x1b[1malert\x1b[0m!"
+        );
+        // headings keep their inline formatting
+        sheet.add_style(HEADING_STYLES[0], StyleProperties::builder().underlined().build());
+        sheet.add_style(CODE_STYLE, StyleProperties::builder().color(Cyan).build());
+        sheet.set_hyperlinks(false);
+        assert_eq!(
+            format_markdown(&sheet, "# Sector `001` [map](m.html)", 80),
+            "\x1b[4mSector \x1b[0m\x1b[36m001\x1b[0m\x1b[4m \x1b[0mmap (m.html)"
+        );
+    }
+}
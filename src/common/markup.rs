@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::fmt;
+
+
+/// Errors found while parsing a markup text.
+/// Positions are byte offsets in the markup text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupError {
+    /// A '<' without the matching '>'.
+    UnterminatedTag { position: usize },
+    /// A tag name is empty or contains invalid characters.
+    InvalidTagName { position: usize, name: String },
+    /// A closing tag doesn't match the last opened tag.
+    UnexpectedClosingTag { position: usize, name: String, expected: Option<String> },
+    /// A tag was opened and never closed.
+    UnclosedTag { position: usize, name: String },
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkupError::UnterminatedTag { position } =>
+                write!(f, "Unterminated tag at position {}", position),
+            MarkupError::InvalidTagName { position, name } =>
+                write!(f, "Invalid tag name '{}' at position {}", name, position),
+            MarkupError::UnexpectedClosingTag { position, name, expected: Some(expected) } =>
+                write!(f, "Unexpected </{}> at position {}, expected </{}>", name, position, expected),
+            MarkupError::UnexpectedClosingTag { position, name, expected: None } =>
+                write!(f, "Unexpected </{}> at position {}, no tag is open", name, position),
+            MarkupError::UnclosedTag { position, name } =>
+                write!(f, "Tag <{}> opened at position {} is never closed", name, position),
+        }
+    }
+}
+
+impl Error for MarkupError {}
+
+/// Return true if name can be used as a tag name.
+fn is_valid_tag_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Parse a markup text, where substrings are enclosed in tags named
+/// after a style: "Shields at <danger>12%</danger>".
+/// Tags can be nested; the text is styled with the innermost tag.
+/// A backslash escapes '<', '>' and '\'. Any other backslash is kept as is.
+///
+/// Return a list of (style name, text) segments. Texts outside of tags
+/// have no style name. Empty segments are omitted.
+///
+/// # Arguments
+///
+/// * `markup` - The text to parse.
+///
+/// # Example
+///
+/// ```
+/// use common::markup;
+/// let segments = markup::parse("Shields at <danger>12%</danger>").unwrap();
+/// assert_eq!(segments[1], (Some("danger".to_string()), "12%".to_string()));
+/// ```
+pub fn parse(markup: &str) -> Result<Vec<(Option<String>, String)>, MarkupError> {
+    Ok(parse_nested(markup)?
+        .into_iter()
+        .map(|(mut styles, text)| (styles.pop(), text))
+        .collect())
+}
+
+/// Parse a markup text, like parse(), but return all the tags that
+/// are open for every segment, from the outermost to the innermost.
+/// This allows to combine nested styles: in
+/// "<info>Engines at <danger>12%</danger></info>" the text "12%" has
+/// the styles ["info", "danger"].
+///
+/// # Arguments
+///
+/// * `markup` - The text to parse.
+///
+/// # Example
+///
+/// ```
+/// use common::markup;
+/// let segments = markup::parse_nested("<info>at <danger>12%</danger></info>").unwrap();
+/// assert_eq!(segments[1], (vec!["info".to_string(), "danger".to_string()], "12%".to_string()));
+/// ```
+pub fn parse_nested(markup: &str) -> Result<Vec<(Vec<String>, String)>, MarkupError> {
+    let mut segments: Vec<(Vec<String>, String)> = Vec::new();
+    // opened tags, with their positions
+    let mut open_tags: Vec<(String, usize)> = Vec::new();
+    let mut text = String::new();
+    let mut chars = markup.char_indices();
+
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '\\' => {
+                let rest = &markup[position + 1..];
+                match rest.chars().next() {
+                    Some(escaped) if escaped == '<' || escaped == '>' || escaped == '\\' => {
+                        text.push(escaped);
+                        chars.next();
+                    },
+                    _ => text.push('\\'),
+                }
+            },
+            '<' => {
+                let rest = &markup[position + 1..];
+                let end = match rest.find('>') {
+                    Some(end) => end,
+                    None => return Err(MarkupError::UnterminatedTag { position }),
+                };
+                let tag = &rest[..end];
+                // skip the tag contents and '>'
+                for _ in tag.chars() {
+                    chars.next();
+                }
+                chars.next();
+
+                if !text.is_empty() {
+                    let current_styles = open_tags.iter().map(|(name, _)| name.clone()).collect();
+                    segments.push((current_styles, text));
+                    text = String::new();
+                }
+
+                let (closing, name) = match tag.strip_prefix('/') {
+                    Some(name) => (true, name),
+                    None => (false, tag),
+                };
+                if !is_valid_tag_name(name) {
+                    return Err(MarkupError::InvalidTagName { position, name: name.to_string() });
+                }
+                if !closing {
+                    open_tags.push((name.to_string(), position));
+                    continue;
+                }
+                match open_tags.pop() {
+                    Some((ref open, _)) if open == name => (),
+                    other => return Err(MarkupError::UnexpectedClosingTag {
+                        position,
+                        name: name.to_string(),
+                        expected: other.map(|(open, _)| open),
+                    }),
+                }
+            },
+            _ => text.push(c),
+        }
+    }
+
+    if let Some((name, position)) = open_tags.pop() {
+        return Err(MarkupError::UnclosedTag { position, name });
+    }
+    if !text.is_empty() {
+        segments.push((Vec::new(), text));
+    }
+    Ok(segments)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(style: Option<&str>, text: &str) -> (Option<String>, String) {
+        (style.map(|style| style.to_string()), text.to_string())
+    }
+
+    #[test]
+    fn parse_plain_text() {
+        assert_eq!(parse("no tags").unwrap(), vec![segment(None, "no tags")]);
+        assert!(parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_tags() {
+        let segments = parse("Shields at <danger>12%</danger>, engines <info>nominal</info>").unwrap();
+        assert_eq!(segments, vec![
+            segment(None, "Shields at "),
+            segment(Some("danger"), "12%"),
+            segment(None, ", engines "),
+            segment(Some("info"), "nominal"),
+        ]);
+    }
+
+    #[test]
+    fn parse_nested_tags() {
+        // inner tags win, outer tags are restored when inner tags are closed
+        let segments = parse("<info>a<danger>b</danger>c</info>").unwrap();
+        assert_eq!(segments, vec![
+            segment(Some("info"), "a"),
+            segment(Some("danger"), "b"),
+            segment(Some("info"), "c"),
+        ]);
+    }
+
+    #[test]
+    fn parse_nested_styles() {
+        let segments = parse_nested("a<info>b<danger>c</danger></info>").unwrap();
+        assert_eq!(segments, vec![
+            (Vec::new(), "a".to_string()),
+            (vec!["info".to_string()], "b".to_string()),
+            (vec!["info".to_string(), "danger".to_string()], "c".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn parse_escapes() {
+        let segments = parse(r"1 \< 2, \\ and \n <info>\<b\></info>").unwrap();
+        assert_eq!(segments, vec![
+            segment(None, r"1 < 2, \ and \n "),
+            segment(Some("info"), "<b>"),
+        ]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse("a <danger"), Err(MarkupError::UnterminatedTag { position: 2 }));
+        assert_eq!(
+            parse("a <dan ger>"),
+            Err(MarkupError::InvalidTagName { position: 2, name: "dan ger".to_string() })
+        );
+        assert_eq!(parse("<>"), Err(MarkupError::InvalidTagName { position: 0, name: String::new() }));
+        assert_eq!(
+            parse("<info>a</danger>"),
+            Err(MarkupError::UnexpectedClosingTag {
+                position: 7, name: "danger".to_string(), expected: Some("info".to_string())
+            })
+        );
+        assert_eq!(
+            parse("a</info>"),
+            Err(MarkupError::UnexpectedClosingTag { position: 1, name: "info".to_string(), expected: None })
+        );
+        assert_eq!(
+            parse("<info>a"),
+            Err(MarkupError::UnclosedTag { position: 0, name: "info".to_string() })
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use progress::ProgressBar;
+use spinner::Spinner;
+use stylesheet::Stylesheet;
+use term;
+
+
+/// An item shown by MultiProgress, on its own line.
+#[derive(Debug)]
+enum Item {
+    Bar(ProgressBar),
+    Spinner(Spinner),
+}
+
+/// Show several progress bars and spinners at the same time, one per
+/// line, like the progress of parallel scanners. Items are added and
+/// then updated through the ids returned by add_bar() and add_spinner().
+/// draw() writes all the lines with a single write, moving the cursor
+/// back to the first line instead of clearing the screen, so the output
+/// doesn't flicker. Spinners are animated by draw(): they don't use
+/// their own threads. To update the items from several threads, keep
+/// the MultiProgress in an Arc<Mutex<_>>.
+///
+/// # Example
+///
+/// ```
+/// use common::multi_progress::MultiProgress;
+/// use common::progress::ProgressBar;
+/// use common::spinner::Spinner;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut multi = MultiProgress::new();
+/// let bar = multi.add_bar(ProgressBar::new(10));
+/// let spinner = multi.add_spinner(Spinner::new("Waiting for long range sensors..."));
+/// for _ in 0..10 {
+///     multi.bar_mut(bar).unwrap().inc(1);
+///     multi.draw(&sheet).unwrap();
+/// }
+/// multi.spinner_mut(spinner).unwrap().set_message("Long range sensors online");
+/// multi.finish(&sheet).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MultiProgress {
+    items: Vec<Item>,
+    /// The number of lines written by the last draw().
+    drawn: usize,
+    started: Instant,
+}
+
+impl Default for MultiProgress {
+    fn default() -> Self {
+        MultiProgress::new()
+    }
+}
+
+impl MultiProgress {
+    /// Return an empty MultiProgress. Spinners animation starts now.
+    pub fn new() -> MultiProgress {
+        MultiProgress {
+            items: Vec::new(),
+            drawn: 0,
+            started: Instant::now(),
+        }
+    }
+
+    /// Add a progress bar below the other items, and return its id.
+    pub fn add_bar(&mut self, bar: ProgressBar) -> usize {
+        self.items.push(Item::Bar(bar));
+        self.items.len() - 1
+    }
+
+    /// Add a spinner below the other items, and return its id.
+    /// The spinner must not be started: MultiProgress animates it.
+    pub fn add_spinner(&mut self, spinner: Spinner) -> usize {
+        self.items.push(Item::Spinner(spinner));
+        self.items.len() - 1
+    }
+
+    /// Return the number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Return true if there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Return the progress bar with the given id, or None if id
+    /// doesn't exist or is a spinner.
+    pub fn bar_mut(&mut self, id: usize) -> Option<&mut ProgressBar> {
+        match self.items.get_mut(id) {
+            Some(Item::Bar(bar)) => Some(bar),
+            _ => None,
+        }
+    }
+
+    /// Return the spinner with the given id, or None if id doesn't
+    /// exist or is a progress bar.
+    pub fn spinner_mut(&mut self, id: usize) -> Option<&mut Spinner> {
+        match self.items.get_mut(id) {
+            Some(Item::Spinner(spinner)) => Some(spinner),
+            _ => None,
+        }
+    }
+
+    /// Return true if all the progress bars are finished.
+    /// Spinners are not considered, because they have no end.
+    pub fn is_finished(&self) -> bool {
+        self.items.iter().all(|item| match item {
+            Item::Bar(bar) => bar.is_finished(),
+            Item::Spinner(_) => true,
+        })
+    }
+
+    /// Return the lines of the items, with the styles applied.
+    fn lines(&self, sheet: &Stylesheet, elapsed: Duration) -> Vec<String> {
+        self.items.iter().map(|item| match item {
+            Item::Bar(bar) => bar.format(sheet),
+            Item::Spinner(spinner) => spinner.format(sheet, elapsed),
+        }).collect()
+    }
+
+    /// Redraw all the items, if the output is a terminal. Otherwise
+    /// nothing is printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn draw(&mut self, sheet: &Stylesheet) -> io::Result<()> {
+        if !sheet.is_interactive() {
+            return Ok(());
+        }
+        let lines = self.lines(sheet, self.started.elapsed());
+        sheet.try_print_raw(&term::replace_lines(self.drawn, &lines))?;
+        self.drawn = lines.len();
+        sheet.flush()
+    }
+
+    /// Print all the items for the last time, and move to the next line.
+    /// If the output is not a terminal, this is the only time the items
+    /// are printed. Drawing again after finish() starts a new block.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn finish(&mut self, sheet: &Stylesheet) -> io::Result<()> {
+        let lines = self.lines(sheet, self.started.elapsed());
+        if sheet.is_interactive() {
+            sheet.try_print_raw(&format!("{}\n", term::replace_lines(self.drawn, &lines)))?;
+        } else {
+            for line in &lines {
+                sheet.try_print_raw(&format!("{}\n", line))?;
+            }
+        }
+        self.drawn = 0;
+        sheet.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+    use test_support::SharedBuffer;
+
+    #[test]
+    fn redraw_in_place() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        let mut multi = MultiProgress::new();
+        let mut bar = ProgressBar::new(2);
+        bar.set_width(2);
+        let bar = multi.add_bar(bar);
+        let mut spinner = Spinner::new("Scanning");
+        spinner.set_frames(&["*"]);
+        let spinner = multi.add_spinner(spinner);
+        assert!(multi.bar_mut(spinner).is_none());
+        assert!(multi.spinner_mut(bar).is_none());
+
+        multi.bar_mut(bar).unwrap().inc(2);
+        assert!(multi.is_finished());
+        let lines = multi.lines(&sheet, Duration::from_secs(0));
+        assert!(lines[0].starts_with("[##] 100% 2/2"));
+        assert_eq!(lines[1], "* Scanning");
+
+        // the first draw starts on the current line, the next ones
+        // move back to the first line
+        let buffer = SharedBuffer::default();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        multi.draw(&sheet).unwrap();
+        assert!(buffer.contents().starts_with("\r[##]"));
+        buffer.clear();
+        multi.finish(&sheet).unwrap();
+        assert!(buffer.contents().starts_with("\x1b[1A\r[##]"));
+        assert!(buffer.contents().ends_with("* Scanning\x1b[K\x1b[J\n"));
+    }
+}
@@ -0,0 +1,98 @@
+use std::io;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+
+
+/// Return the command that shows a desktop notification on this system,
+/// or None if notifications are not supported.
+fn notify_command(title: &str, message: &str) -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            applescript_string(message), applescript_string(title)
+        ));
+        Some(command)
+    } else if cfg!(unix) {
+        let mut command = Command::new("notify-send");
+        command.arg("--").arg(title).arg(message);
+        Some(command)
+    } else {
+        None
+    }
+}
+
+/// Return text as an AppleScript string literal.
+fn applescript_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Show a desktop notification, through notify-send on Linux and other
+/// Unix systems, or osascript on macOS. The output of the command is
+/// discarded, so it doesn't mix with the output of the program.
+/// Return an error if the command is not installed or fails, or if
+/// the system is not supported.
+///
+/// # Arguments
+///
+/// * `title` - The title of the notification.
+/// * `message` - The text of the notification.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::notification;
+/// notification::notify("Long range scanner", "Borg cube approaching!").unwrap();
+/// ```
+pub fn notify(title: &str, message: &str) -> io::Result<()> {
+    let status = spawn_notify_command(title, message)?.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("The notification command failed: {}", status)))
+    }
+}
+
+/// Same as notify(), but it doesn't wait for the notification to be
+/// shown, so it doesn't block loops that redraw the screen. Only the
+/// errors that happen while starting the command are returned.
+///
+/// # Arguments
+///
+/// * `title` - The title of the notification.
+/// * `message` - The text of the notification.
+pub fn notify_in_background(title: &str, message: &str) -> io::Result<()> {
+    let mut child = spawn_notify_command(title, message)?;
+    // waiting for the command in a thread doesn't leave a zombie process
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Start the command that shows a notification, with its output discarded.
+fn spawn_notify_command(title: &str, message: &str) -> io::Result<Child> {
+    let mut command = notify_command(title, message)
+        .ok_or_else(|| io::Error::other("Desktop notifications are not supported on this system"))?;
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(
+                err.kind(),
+                format!("{} is not installed", command.get_program().to_string_lossy())
+            ),
+            _ => err,
+        })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_applescript() {
+        assert_eq!(applescript_string("Borg \"cube\" \\ 1"), "\"Borg \\\"cube\\\" \\\\ 1\"");
+    }
+}
@@ -0,0 +1,284 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use stylesheet::Stylesheet;
+use term;
+
+
+/// The default style of the completed part of progress bars.
+pub const FILLED_STYLE: &str = "progress_filled";
+/// The default style of the remaining part of progress bars.
+pub const EMPTY_STYLE: &str = "progress_empty";
+/// The default style of the label and the statistics of progress bars.
+pub const LABEL_STYLE: &str = "progress_label";
+
+/// The default width of the bar, in characters.
+const DEFAULT_WIDTH: usize = 30;
+
+
+/// A progress bar for operations whose amount of work is known,
+/// like scanning a list of sectors. Every part of the bar is printed
+/// with a style of the Stylesheet, so progress bars look like the
+/// rest of the output. A line looks like this:
+///
+/// ```text
+/// Scanning [###############---------------]  50% 5/10 2.5/s ETA 00:02
+/// ```
+///
+/// On terminals the line is redrawn in place; when the output is
+/// redirected, only the final line is printed by finish().
+///
+/// # Example
+///
+/// ```
+/// use common::progress::ProgressBar;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut bar = ProgressBar::new(10);
+/// bar.set_label("Scanning");
+/// for _ in 0..10 {
+///     bar.inc(1);
+///     bar.draw(&sheet).unwrap();
+/// }
+/// bar.finish(&sheet).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ProgressBar {
+    total: u64,
+    position: u64,
+    label: String,
+    width: usize,
+    filled_style: String,
+    empty_style: String,
+    label_style: String,
+    started: Instant,
+}
+
+impl ProgressBar {
+    /// Return a new progress bar, at position 0. The time used to
+    /// compute the rate and the ETA starts now.
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - The amount of work, like the number of items to process.
+    pub fn new(total: u64) -> ProgressBar {
+        ProgressBar {
+            total,
+            position: 0,
+            label: String::new(),
+            width: DEFAULT_WIDTH,
+            filled_style: FILLED_STYLE.to_string(),
+            empty_style: EMPTY_STYLE.to_string(),
+            label_style: LABEL_STYLE.to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Set the text printed before the bar.
+    pub fn set_label<S>(&mut self, label: S) where S: Into<String> {
+        self.label = label.into();
+    }
+
+    /// Set the width of the bar, in characters, without the label and
+    /// the statistics.
+    pub fn set_width(&mut self, width: usize) {
+        self.width = width;
+    }
+
+    /// Use other styles instead of FILLED_STYLE, EMPTY_STYLE and LABEL_STYLE.
+    ///
+    /// # Arguments
+    ///
+    /// * `filled` - The style of the completed part of the bar.
+    /// * `empty` - The style of the remaining part of the bar.
+    /// * `label` - The style of the label and the statistics.
+    pub fn set_styles(&mut self, filled: &str, empty: &str, label: &str) {
+        self.filled_style = filled.to_string();
+        self.empty_style = empty.to_string();
+        self.label_style = label.to_string();
+    }
+
+    /// Return the amount of work done.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Return the total amount of work.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Set the amount of work done. It can't exceed the total.
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position.min(self.total);
+    }
+
+    /// Add delta to the amount of work done.
+    pub fn inc(&mut self, delta: u64) {
+        self.set_position(self.position.saturating_add(delta));
+    }
+
+    /// Return true if all the work is done.
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.total
+    }
+
+    /// Return the time passed since the progress bar was created.
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Return the amount of work done per second, on average.
+    pub fn rate(&self) -> f64 {
+        rate(self.position, self.elapsed())
+    }
+
+    /// Return the estimated time needed to complete the work, at the
+    /// current rate, or None if nothing was done yet.
+    pub fn eta(&self) -> Option<Duration> {
+        eta(self.position, self.total, self.elapsed())
+    }
+
+    /// Return the line of the progress bar, with the styles applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet containing the styles.
+    pub fn format(&self, sheet: &Stylesheet) -> String {
+        self.format_at(sheet, self.elapsed())
+    }
+
+    /// Same as format(), as if elapsed time had passed since the start.
+    fn format_at(&self, sheet: &Stylesheet, elapsed: Duration) -> String {
+        let ratio = if self.total == 0 { 1.0 } else { self.position as f64 / self.total as f64 };
+        let filled = ((ratio * self.width as f64).round() as usize).min(self.width);
+        let (filled_char, empty_char) = if sheet.unicode_enabled() { ('█', '░') } else { ('#', '-') };
+
+        let mut line = String::new();
+        if !self.label.is_empty() {
+            line.push_str(&sheet.format(&self.label_style, format!("{} ", self.label)));
+        }
+        line.push('[');
+        line.push_str(&sheet.format(&self.filled_style, filled_char.to_string().repeat(filled)));
+        line.push_str(&sheet.format(&self.empty_style, empty_char.to_string().repeat(self.width - filled)));
+        line.push(']');
+        let eta = match eta(self.position, self.total, elapsed) {
+            Some(eta) => format_duration(eta),
+            None => "--:--".to_string(),
+        };
+        let stats = format!(
+            " {:>3}% {}/{} {:.1}/s ETA {}",
+            (ratio * 100.0).floor() as u64, self.position, self.total, rate(self.position, elapsed), eta
+        );
+        line.push_str(&sheet.format(&self.label_style, stats));
+        line
+    }
+
+    /// Redraw the progress bar on the current line, if the output is a
+    /// terminal. Otherwise nothing is printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn draw(&self, sheet: &Stylesheet) -> io::Result<()> {
+        if !sheet.is_interactive() {
+            return Ok(());
+        }
+        sheet.try_print_raw(&term::replace_line(&self.format(sheet)))?;
+        sheet.flush()
+    }
+
+    /// Print the progress bar for the last time, and move to the next
+    /// line. If the output is not a terminal, this is the only time the
+    /// bar is printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn finish(&self, sheet: &Stylesheet) -> io::Result<()> {
+        if sheet.is_interactive() {
+            sheet.try_print_raw(&format!("{}\n", term::replace_line(&self.format(sheet))))?;
+        } else {
+            sheet.try_print_raw(&format!("{}\n", self.format(sheet)))?;
+        }
+        sheet.flush()
+    }
+}
+
+/// Return the amount of work done per second.
+fn rate(position: u64, elapsed: Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds > 0.0 { position as f64 / seconds } else { 0.0 }
+}
+
+/// Return the estimated time needed to complete the work.
+fn eta(position: u64, total: u64, elapsed: Duration) -> Option<Duration> {
+    if position == 0 {
+        return None;
+    }
+    let remaining = total.saturating_sub(position) as f64;
+    Some(Duration::from_secs_f64(remaining / position as f64 * elapsed.as_secs_f64()))
+}
+
+/// Return a duration as "MM:SS", or "H:MM:SS" if it's at least an hour.
+/// Fractions of seconds are rounded up, so that 00:00 means done.
+pub fn format_duration(duration: Duration) -> String {
+    let mut seconds = duration.as_secs();
+    if duration.subsec_nanos() > 0 {
+        seconds += 1;
+    }
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}", minutes, seconds)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor, StyleProperties};
+
+    fn plain_sheet() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        sheet
+    }
+
+    #[test]
+    fn format_progress() {
+        let sheet = plain_sheet();
+        let mut bar = ProgressBar::new(10);
+        bar.set_width(10);
+        bar.set_label("Scanning");
+        assert_eq!(bar.format_at(&sheet, Duration::from_secs(0)), "Scanning [----------]   0% 0/10 0.0/s ETA --:--");
+        bar.inc(5);
+        assert_eq!(bar.format_at(&sheet, Duration::from_secs(2)), "Scanning [#####-----]  50% 5/10 2.5/s ETA 00:02");
+        // the position can't exceed the total
+        bar.inc(20);
+        assert!(bar.is_finished());
+        assert_eq!(bar.format_at(&sheet, Duration::from_secs(4)), "Scanning [##########] 100% 10/10 2.5/s ETA 00:00");
+    }
+
+    #[test]
+    fn styled_progress() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_unicode(false);
+        sheet.add_style(FILLED_STYLE, StyleProperties::builder().color(StyleColor::Green).build());
+        let mut bar = ProgressBar::new(2);
+        bar.set_width(2);
+        bar.inc(1);
+        assert!(bar.format_at(&sheet, Duration::from_secs(1)).starts_with("[\x1b[32m#\x1b[0m-]"));
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "00:00");
+        assert_eq!(format_duration(Duration::from_millis(61_200)), "01:02");
+        assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+    }
+}
@@ -0,0 +1,748 @@
+use std::io::{self, BufRead};
+
+extern crate console;
+
+use input::{Key, RawMode};
+use styled_string::StyledString;
+use stylesheet::Stylesheet;
+use term;
+use text;
+
+
+/// The default style of the questions.
+pub const QUESTION_STYLE: &str = "prompt_question";
+/// The default style of the hints after the questions, like "[y/N]".
+pub const HINT_STYLE: &str = "prompt_hint";
+/// The default style of the messages about invalid answers.
+pub const ERROR_STYLE: &str = "prompt_error";
+/// The default style of the current item of menus.
+pub const SELECTED_STYLE: &str = "prompt_selected";
+/// The default style of the answers shown when a menu is closed.
+pub const ANSWER_STYLE: &str = "prompt_answer";
+/// The default style of the characters that match the filter of fuzzy menus.
+pub const MATCH_STYLE: &str = "prompt_match";
+
+/// The maximum number of items shown at the same time by menus.
+/// Longer menus scroll.
+const MAX_VISIBLE_ITEMS: usize = 10;
+
+
+/// Return the question followed by its hint, with the styles applied.
+fn question_line(sheet: &Stylesheet, question: &str, hint: Option<&str>) -> String {
+    let mut text = sheet.format(QUESTION_STYLE, question);
+    if let Some(hint) = hint {
+        text.push(' ');
+        text.push_str(&sheet.format(HINT_STYLE, format!("[{}]", hint)));
+    }
+    text
+}
+
+/// Print the question and its hint, without a newline.
+fn ask(sheet: &Stylesheet, question: &str, hint: Option<&str>) -> io::Result<()> {
+    sheet.try_print_raw(&format!("{} ", question_line(sheet, question, hint)))?;
+    sheet.flush()
+}
+
+/// Print an error about an invalid answer.
+fn print_error(sheet: &Stylesheet, message: &str) -> io::Result<()> {
+    sheet.try_print_raw(&format!("{}\n", sheet.format(ERROR_STYLE, message)))
+}
+
+/// Read a line from input, without the line terminator.
+/// Return None at the end of the input.
+fn read_answer<R: BufRead>(input: &mut R) -> io::Result<Option<String>> {
+    let mut answer = String::new();
+    if input.read_line(&mut answer)? == 0 {
+        return Ok(None);
+    }
+    let length = answer.trim_end_matches(['\r', '\n']).len();
+    answer.truncate(length);
+    Ok(Some(answer))
+}
+
+/// Ask a yes/no question on the standard input, like "Raise shields? [y/N]".
+/// The accepted answers are y, yes, n and no, in any case. An empty
+/// answer or the end of the input selects the default. Other answers
+/// print an error and repeat the question.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `question` - The question.
+/// * `default` - The answer selected by pressing Enter.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::prompt;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// if prompt::confirm(&sheet, "Raise shields?", false).unwrap() {
+///     sheet.println("success", "Shields up");
+/// }
+/// ```
+pub fn confirm(sheet: &Stylesheet, question: &str, default: bool) -> io::Result<bool> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    confirm_from(sheet, &mut input, question, default)
+}
+
+/// Same as confirm(), but read the answers from input.
+pub fn confirm_from<R: BufRead>(sheet: &Stylesheet, input: &mut R, question: &str, default: bool) -> io::Result<bool> {
+    let hint = if default { "Y/n" } else { "y/N" };
+    loop {
+        ask(sheet, question, Some(hint))?;
+        let answer = match read_answer(input)? {
+            Some(answer) => answer,
+            None => {
+                sheet.try_print_raw("\n")?;
+                return Ok(default);
+            },
+        };
+        match answer.trim().to_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => print_error(sheet, "Please answer y or n.")?,
+        }
+    }
+}
+
+/// Ask for a line of text on the standard input. The default, if any,
+/// is shown after the question and is returned if the answer is empty.
+/// Surrounding spaces are removed from the answer.
+/// At the end of the input, return the default or an UnexpectedEof error.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `question` - The question.
+/// * `default` - The answer selected by pressing Enter.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::prompt;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let sector = prompt::input(&sheet, "Sector to scan:", Some("001")).unwrap();
+/// ```
+pub fn input(sheet: &Stylesheet, question: &str, default: Option<&str>) -> io::Result<String> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    input_from(sheet, &mut input, question, default)
+}
+
+/// Same as input(), but read the answer from input.
+pub fn input_from<R: BufRead>(sheet: &Stylesheet, input: &mut R, question: &str, default: Option<&str>) -> io::Result<String> {
+    ask(sheet, question, default)?;
+    match read_answer(input)? {
+        Some(ref answer) if !answer.trim().is_empty() => Ok(answer.trim().to_string()),
+        Some(_) => Ok(default.unwrap_or("").to_string()),
+        None => {
+            sheet.try_print_raw("\n")?;
+            default.map(|default| default.to_string())
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "No answer"))
+        },
+    }
+}
+
+/// Ask for a password, or another secret, without showing what is typed.
+/// If there is no terminal, the password is read from the standard input,
+/// so that scripts can pipe it. The answer is returned as typed.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `question` - The question.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::prompt;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let code = prompt::password(&sheet, "Self destruct code:").unwrap();
+/// ```
+pub fn password(sheet: &Stylesheet, question: &str) -> io::Result<String> {
+    let tty = console::Term::stderr();
+    if !tty.is_term() {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        ask(sheet, question, None)?;
+        return read_answer(&mut input)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "No answer"));
+    }
+    ask(sheet, question, None)?;
+    // echo is disabled while the line is read, then a newline is printed
+    tty.read_secure_line()
+}
+
+/// What a menu does after a key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuAction {
+    Continue,
+    Accept,
+    Cancel,
+}
+
+/// The state of a select or multi-select menu: the items, the current
+/// one and, for multi-select menus, the checked ones. Fuzzy menus also
+/// have a query that filters the items.
+#[derive(Debug, Clone)]
+struct Menu<'a> {
+    items: &'a [&'a str],
+    /// The indexes of the items shown, in order.
+    shown: Vec<usize>,
+    /// The position of the current item in shown.
+    cursor: usize,
+    /// The position in shown of the first visible item.
+    offset: usize,
+    /// The maximum number of visible items.
+    page: usize,
+    /// For multi-select menus, whether every item is checked.
+    checked: Option<Vec<bool>>,
+    /// For fuzzy menus, the text typed to filter the items.
+    query: Option<String>,
+    /// For every item in shown, the byte offsets of the characters
+    /// that match the query.
+    highlights: Vec<Vec<usize>>,
+}
+
+impl<'a> Menu<'a> {
+    fn new(items: &'a [&'a str], cursor: usize, checked: Option<Vec<bool>>, page: usize) -> Menu<'a> {
+        let mut menu = Menu {
+            items,
+            shown: (0..items.len()).collect(),
+            cursor: 0,
+            offset: 0,
+            page: page.max(1),
+            checked,
+            query: None,
+            highlights: vec![Vec::new(); items.len()],
+        };
+        menu.move_to(cursor);
+        menu
+    }
+
+    /// Return the index of the current item, if any item is shown.
+    fn current(&self) -> Option<usize> {
+        self.shown.get(self.cursor).cloned()
+    }
+
+    /// Return the indexes of the checked items.
+    fn checked_items(&self) -> Vec<usize> {
+        match self.checked {
+            Some(ref checked) => (0..checked.len()).filter(|&item| checked[item]).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Show only the items that match the query, the best matches first,
+    /// and make the first one the current item.
+    fn filter(&mut self) {
+        let query = self.query.clone().unwrap_or_default();
+        let mut matches: Vec<(i64, usize, Vec<usize>)> = self.items.iter().enumerate()
+            .filter_map(|(item, text)| text::fuzzy_match(&query, text).map(|(score, offsets)| (score, item, offsets)))
+            .collect();
+        // the sort is stable: equal scores keep the order of the items
+        matches.sort_by_key(|&(score, _, _)| -score);
+        self.shown = matches.iter().map(|&(_, item, _)| item).collect();
+        self.highlights = matches.into_iter().map(|(_, _, offsets)| offsets).collect();
+        self.offset = 0;
+        self.move_to(0);
+    }
+
+    /// Make the item at position the current one, scrolling if necessary.
+    fn move_to(&mut self, position: usize) {
+        self.cursor = position.min(self.shown.len().saturating_sub(1));
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + self.page {
+            self.offset = self.cursor + 1 - self.page;
+        }
+    }
+
+    /// Update the menu after key was pressed. Moving past the first or
+    /// the last item wraps around.
+    /// In fuzzy menus, characters and Backspace change the query.
+    fn handle(&mut self, key: &Key) -> MenuAction {
+        if let Some(query) = self.query.as_mut() {
+            match key {
+                Key::Char(c) if !c.is_control() => query.push(*c),
+                Key::Backspace => {
+                    query.pop();
+                },
+                // nothing to accept if no item matches
+                Key::Enter if self.shown.is_empty() => return MenuAction::Continue,
+                _ => return self.handle_move(key),
+            }
+            self.filter();
+            return MenuAction::Continue;
+        }
+        self.handle_move(key)
+    }
+
+    /// Same as handle(), without the query.
+    fn handle_move(&mut self, key: &Key) -> MenuAction {
+        let last = self.shown.len().saturating_sub(1);
+        match key {
+            Key::ArrowUp | Key::Char('k') => {
+                let position = if self.cursor == 0 { last } else { self.cursor - 1 };
+                self.move_to(position);
+            },
+            Key::ArrowDown | Key::Char('j') => {
+                let position = if self.cursor >= last { 0 } else { self.cursor + 1 };
+                self.move_to(position);
+            },
+            Key::Home => self.move_to(0),
+            Key::End => self.move_to(last),
+            Key::Char(' ') => {
+                if let (Some(item), Some(checked)) = (self.current(), self.checked.as_mut()) {
+                    checked[item] = !checked[item];
+                }
+            },
+            Key::Enter => return MenuAction::Accept,
+            // in raw mode, Ctrl+C doesn't stop the program
+            Key::Escape | Key::Char('q') | Key::Ctrl('c') => return MenuAction::Cancel,
+            _ => (),
+        }
+        MenuAction::Continue
+    }
+
+    /// Return the lines of the visible items, with the styles applied.
+    /// The characters that match the query have MATCH_STYLE.
+    fn lines(&self, sheet: &Stylesheet) -> Vec<String> {
+        if self.shown.is_empty() {
+            return vec![sheet.format(HINT_STYLE, "  No matches")];
+        }
+        let (pointer, on, off) = if sheet.unicode_enabled() { ("❯", "◉", "◯") } else { (">", "[x]", "[ ]") };
+        self.shown.iter().enumerate().skip(self.offset).take(self.page).map(|(position, &item)| {
+            let mut prefix = String::new();
+            prefix.push_str(if position == self.cursor { pointer } else { " " });
+            prefix.push(' ');
+            if let Some(ref checked) = self.checked {
+                prefix.push_str(if checked[item] { on } else { off });
+                prefix.push(' ');
+            }
+            let style_name = if position == self.cursor { Some(SELECTED_STYLE) } else { None };
+            let mut line = StyledString::new();
+            let mut push = |style_name: Option<&str>, text: &str| match style_name {
+                Some(style_name) => line.push(style_name, text),
+                None => line.push_plain(text),
+            };
+            push(style_name, &prefix);
+            let highlights = &self.highlights[position];
+            for (offset, c) in self.items[item].char_indices() {
+                let text = c.encode_utf8(&mut [0; 4]).to_string();
+                push(if highlights.contains(&offset) { Some(MATCH_STYLE) } else { style_name }, &text);
+            }
+            line.render(sheet)
+        }).collect()
+    }
+}
+
+/// Return the maximum number of visible items of menus, so that they fit
+/// in the terminal with their question.
+fn menu_page(tty: &console::Term) -> usize {
+    (tty.size().0 as usize).saturating_sub(2).clamp(1, MAX_VISIBLE_ITEMS)
+}
+
+/// Return the terminal that menus read keys from, or None if menus
+/// can't be shown: the keys are read from the terminal of stderr, but
+/// menus are drawn with sheet, so its output must be interactive too.
+fn menu_terminal(sheet: &Stylesheet) -> Option<console::Term> {
+    let tty = console::Term::stderr();
+    if tty.is_term() && sheet.is_interactive() { Some(tty) } else { None }
+}
+
+/// Show menu below the question until an item is accepted or the menu is
+/// cancelled. The keys are read from the terminal. In the end the menu is
+/// erased, and the cursor is at the beginning of the question line.
+fn run_menu(sheet: &Stylesheet, question: &str, hint: &str, menu: &mut Menu) -> io::Result<MenuAction> {
+    let question = question_line(sheet, question, Some(hint));
+    let mut drawn = 0;
+    let mut raw = RawMode::enable()?;
+    let hidden = term::hide_cursor(sheet)?;
+    let result = loop {
+        let mut lines = vec![match menu.query {
+            Some(ref query) => format!("{} {}", question, query),
+            None => question.clone(),
+        }];
+        lines.extend(menu.lines(sheet));
+        if let Err(err) = sheet.try_print_raw(&term::replace_lines(drawn, &lines)).and_then(|_| sheet.flush()) {
+            break Err(err);
+        }
+        drawn = lines.len();
+        match raw.read_key().map(|key| menu.handle(&key)) {
+            Ok(MenuAction::Continue) => (),
+            other => break other,
+        }
+    };
+    // the terminal must be restored even after errors
+    drop(raw);
+    drop(hidden);
+    sheet.try_print_raw(&term::replace_lines(drawn, &[String::new()]))?;
+    result
+}
+
+/// Print the question followed by the answer, and a newline.
+fn print_answer(sheet: &Stylesheet, question: &str, answer: &str) -> io::Result<()> {
+    sheet.try_print_raw(&format!("{} {}\n", question_line(sheet, question, None), sheet.format(ANSWER_STYLE, answer)))?;
+    sheet.flush()
+}
+
+/// Return the error for menus without items.
+fn no_items() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, "No items to choose from")
+}
+
+/// Print the items, numbered from 1. Used when there is no terminal.
+fn print_numbered(sheet: &Stylesheet, items: &[&str]) -> io::Result<()> {
+    for (i, item) in items.iter().enumerate() {
+        sheet.try_print_raw(&format!("{} {}\n", sheet.format(HINT_STYLE, format!("{:>3})", i + 1)), item))?;
+    }
+    Ok(())
+}
+
+/// Let the user choose an item from a menu, moving with the arrow keys
+/// (or j and k) and selecting with Enter. Return the index of the chosen
+/// item, or None if the menu was cancelled with Esc or q.
+/// If there is no terminal, or the output of sheet is not interactive,
+/// the items are numbered and the number of the chosen item is read
+/// from the standard input, like select_from().
+/// Return an InvalidInput error if items is empty.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `question` - The question.
+/// * `items` - The items to choose from.
+/// * `default` - The index of the item selected at the beginning.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::prompt;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let themes = ["dark", "light", "matrix"];
+/// if let Some(theme) = prompt::select(&sheet, "Theme:", &themes, 0).unwrap() {
+///     println!("You chose {}", themes[theme]);
+/// }
+/// ```
+pub fn select(sheet: &Stylesheet, question: &str, items: &[&str], default: usize) -> io::Result<Option<usize>> {
+    if items.is_empty() {
+        return Err(no_items());
+    }
+    let tty = match menu_terminal(sheet) {
+        Some(tty) => tty,
+        None => {
+            let stdin = io::stdin();
+            let mut input = stdin.lock();
+            return select_from(sheet, &mut input, question, items, default).map(Some);
+        },
+    };
+    let mut menu = Menu::new(items, default, None, menu_page(&tty));
+    match run_menu(sheet, question, "arrows to move, Enter to select", &mut menu)? {
+        MenuAction::Accept => {
+            let item = menu.current().ok_or_else(no_items)?;
+            print_answer(sheet, question, items[item])?;
+            Ok(Some(item))
+        },
+        _ => {
+            print_answer(sheet, question, "")?;
+            Ok(None)
+        },
+    }
+}
+
+/// Same as select(), without a terminal: print the numbered items, and
+/// read the number of the chosen item from input. Invalid numbers print
+/// an error and repeat the question.
+pub fn select_from<R: BufRead>(sheet: &Stylesheet, input: &mut R, question: &str, items: &[&str], default: usize) -> io::Result<usize> {
+    if items.is_empty() {
+        return Err(no_items());
+    }
+    print_numbered(sheet, items)?;
+    let default = (default.min(items.len() - 1) + 1).to_string();
+    loop {
+        match input_from(sheet, input, question, Some(&default))?.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= items.len() => return Ok(number - 1),
+            _ => print_error(sheet, &format!("Please answer a number from 1 to {}.", items.len()))?,
+        }
+    }
+}
+
+/// Let the user choose any number of items from a menu, moving with the
+/// arrow keys (or j and k), checking and unchecking the current item with
+/// Space, and confirming with Enter. Return the indexes of the checked
+/// items, or None if the menu was cancelled with Esc or q.
+/// If there is no terminal, or the output of sheet is not interactive,
+/// the items are numbered and the numbers of the chosen items are read
+/// from the standard input, like multi_select_from().
+/// Return an InvalidInput error if items is empty.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `question` - The question.
+/// * `items` - The items to choose from.
+/// * `checked` - The items checked at the beginning. Missing values are false.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::prompt;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let scanners = ["radar", "lidar", "tachyon"];
+/// let chosen = prompt::multi_select(&sheet, "Scanners:", &scanners, &[true, true]).unwrap();
+/// ```
+pub fn multi_select(sheet: &Stylesheet, question: &str, items: &[&str], checked: &[bool]) -> io::Result<Option<Vec<usize>>> {
+    if items.is_empty() {
+        return Err(no_items());
+    }
+    let tty = match menu_terminal(sheet) {
+        Some(tty) => tty,
+        None => {
+            let stdin = io::stdin();
+            let mut input = stdin.lock();
+            return multi_select_from(sheet, &mut input, question, items, checked).map(Some);
+        },
+    };
+    let checked = (0..items.len()).map(|item| checked.get(item).cloned().unwrap_or(false)).collect();
+    let mut menu = Menu::new(items, 0, Some(checked), menu_page(&tty));
+    match run_menu(sheet, question, "arrows to move, Space to check, Enter to confirm", &mut menu)? {
+        MenuAction::Accept => {
+            let chosen = menu.checked_items();
+            let answer: Vec<&str> = chosen.iter().map(|&item| items[item]).collect();
+            print_answer(sheet, question, &answer.join(", "))?;
+            Ok(Some(chosen))
+        },
+        _ => {
+            print_answer(sheet, question, "")?;
+            Ok(None)
+        },
+    }
+}
+
+/// Let the user choose an item from a menu that is filtered while
+/// typing, for long lists. The items that contain the typed characters,
+/// in the same order, are shown, the best matches first, and the
+/// matching characters are highlighted with MATCH_STYLE. The arrow keys
+/// move the selection, Backspace deletes the last character and Enter
+/// selects the current item. Return the index of the chosen item, or
+/// None if the menu was cancelled with Esc.
+/// If there is no terminal, or the output of sheet is not interactive,
+/// the items are numbered and the number of the chosen item is read
+/// from the standard input, like select_from().
+/// Return an InvalidInput error if items is empty.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `question` - The question.
+/// * `items` - The items to choose from.
+///
+/// # Example
+///
+/// ```no_run
+/// use common::prompt;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let events = ["Warp core breach", "Hull breach", "Shields down", "Red alert"];
+/// if let Some(event) = prompt::fuzzy_select(&sheet, "Event:", &events).unwrap() {
+///     println!("You chose {}", events[event]);
+/// }
+/// ```
+pub fn fuzzy_select(sheet: &Stylesheet, question: &str, items: &[&str]) -> io::Result<Option<usize>> {
+    if items.is_empty() {
+        return Err(no_items());
+    }
+    let tty = match menu_terminal(sheet) {
+        Some(tty) => tty,
+        None => {
+            let stdin = io::stdin();
+            let mut input = stdin.lock();
+            return select_from(sheet, &mut input, question, items, 0).map(Some);
+        },
+    };
+    let mut menu = Menu::new(items, 0, None, menu_page(&tty));
+    menu.query = Some(String::new());
+    match run_menu(sheet, question, "type to filter, Enter to select", &mut menu)? {
+        MenuAction::Accept => {
+            let item = menu.current().ok_or_else(no_items)?;
+            print_answer(sheet, question, items[item])?;
+            Ok(Some(item))
+        },
+        _ => {
+            print_answer(sheet, question, "")?;
+            Ok(None)
+        },
+    }
+}
+
+/// Same as multi_select(), without a terminal: print the numbered items,
+/// and read the numbers of the chosen items from input, separated by
+/// commas or spaces. Invalid numbers print an error and repeat the question.
+/// At the end of the input, the checked items are chosen.
+pub fn multi_select_from<R: BufRead>(sheet: &Stylesheet, input: &mut R, question: &str, items: &[&str], checked: &[bool]) -> io::Result<Vec<usize>> {
+    if items.is_empty() {
+        return Err(no_items());
+    }
+    print_numbered(sheet, items)?;
+    let default: Vec<String> = (0..items.len())
+        .filter(|&item| checked.get(item).cloned().unwrap_or(false))
+        .map(|item| (item + 1).to_string())
+        .collect();
+    let default = default.join(",");
+    loop {
+        let answer = match input_from(sheet, input, question, if default.is_empty() { None } else { Some(&default) }) {
+            Ok(answer) => answer,
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let numbers: Result<Vec<usize>, _> = answer.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|number| !number.is_empty())
+            .map(|number| number.parse::<usize>())
+            .collect();
+        match numbers {
+            Ok(ref numbers) if numbers.iter().all(|&number| number >= 1 && number <= items.len()) => {
+                let mut chosen: Vec<usize> = numbers.iter().map(|number| number - 1).collect();
+                chosen.sort_unstable();
+                chosen.dedup();
+                return Ok(chosen);
+            },
+            _ => print_error(sheet, &format!("Please answer numbers from 1 to {}, separated by commas.", items.len()))?,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor, StyleProperties};
+    use test_support::SharedBuffer;
+
+    fn plain_sheet(buffer: &SharedBuffer) -> Stylesheet {
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet
+    }
+
+    #[test]
+    fn confirm_answers() {
+        let buffer = SharedBuffer::default();
+        let sheet = plain_sheet(&buffer);
+        assert!(confirm_from(&sheet, &mut Cursor::new("YES\n"), "Raise shields?", false).unwrap());
+        assert!(!confirm_from(&sheet, &mut Cursor::new("\n"), "Raise shields?", false).unwrap());
+        // the end of the input selects the default
+        assert!(confirm_from(&sheet, &mut Cursor::new(""), "Raise shields?", true).unwrap());
+        assert_eq!(buffer.contents(), "Raise shields? [y/N] Raise shields? [y/N] Raise shields? [Y/n] \n");
+    }
+
+    #[test]
+    fn confirm_repeats_question() {
+        let buffer = SharedBuffer::default();
+        let sheet = plain_sheet(&buffer);
+        assert!(!confirm_from(&sheet, &mut Cursor::new("maybe\nn\n"), "Raise shields?", true).unwrap());
+        assert_eq!(buffer.contents(), "Raise shields? [Y/n] Please answer y or n.\nRaise shields? [Y/n] ");
+    }
+
+    #[test]
+    fn input_answers() {
+        let buffer = SharedBuffer::default();
+        let sheet = plain_sheet(&buffer);
+        assert_eq!(input_from(&sheet, &mut Cursor::new(" 042 \r\n"), "Sector:", Some("001")).unwrap(), "042");
+        assert_eq!(input_from(&sheet, &mut Cursor::new("\n"), "Sector:", Some("001")).unwrap(), "001");
+        assert_eq!(input_from(&sheet, &mut Cursor::new(""), "Sector:", Some("001")).unwrap(), "001");
+        assert_eq!(input_from(&sheet, &mut Cursor::new(""), "Sector:", None).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(buffer.contents().starts_with("Sector: [001] "));
+    }
+
+    #[test]
+    fn menu_keys() {
+        let mut sheet = plain_sheet(&SharedBuffer::default());
+        sheet.set_unicode(false);
+        let items = ["dark", "light", "matrix"];
+        let mut menu = Menu::new(&items, 1, None, 2);
+        assert_eq!(menu.lines(&sheet), vec!["  dark", "> light"]);
+        assert_eq!(menu.handle(&Key::ArrowDown), MenuAction::Continue);
+        // the menu scrolls to show the current item
+        assert_eq!(menu.lines(&sheet), vec!["  light", "> matrix"]);
+        // and wraps around
+        menu.handle(&Key::ArrowDown);
+        assert_eq!(menu.current(), Some(0));
+        menu.handle(&Key::ArrowUp);
+        assert_eq!(menu.current(), Some(2));
+        assert_eq!(menu.handle(&Key::Enter), MenuAction::Accept);
+        assert_eq!(menu.handle(&Key::Escape), MenuAction::Cancel);
+    }
+
+    #[test]
+    fn multi_select_menu() {
+        let mut sheet = plain_sheet(&SharedBuffer::default());
+        sheet.set_unicode(false);
+        let items = ["radar", "lidar", "tachyon"];
+        let mut menu = Menu::new(&items, 0, Some(vec![true, false, false]), 10);
+        menu.handle(&Key::Char(' '));
+        menu.handle(&Key::End);
+        menu.handle(&Key::Char(' '));
+        assert_eq!(menu.checked_items(), vec![2]);
+        assert_eq!(menu.lines(&sheet), vec!["  [ ] radar", "  [ ] lidar", "> [x] tachyon"]);
+    }
+
+    #[test]
+    fn select_without_terminal() {
+        let buffer = SharedBuffer::default();
+        let sheet = plain_sheet(&buffer);
+        let items = ["dark", "light"];
+        assert_eq!(select_from(&sheet, &mut Cursor::new("4\n2\n"), "Theme:", &items, 0).unwrap(), 1);
+        assert_eq!(
+            buffer.contents(),
+            "  1) dark\n  2) light\nTheme: [1] Please answer a number from 1 to 2.\nTheme: [1] "
+        );
+        assert_eq!(select_from(&sheet, &mut Cursor::new(""), "Theme:", &items, 9).unwrap(), 1);
+        let items = ["radar", "lidar", "tachyon"];
+        assert_eq!(multi_select_from(&sheet, &mut Cursor::new("3, 1 3\n"), "Scanners:", &items, &[]).unwrap(), vec![0, 2]);
+        assert_eq!(multi_select_from(&sheet, &mut Cursor::new("\n"), "Scanners:", &items, &[false, true]).unwrap(), vec![1]);
+        assert!(multi_select_from(&sheet, &mut Cursor::new(""), "Scanners:", &items, &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn fuzzy_menu() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_unicode(false);
+        sheet.add_style(MATCH_STYLE, StyleProperties::builder().color(StyleColor::Yellow).build());
+        let items = ["Hull breach", "Red alert", "Warp core breach"];
+        let mut menu = Menu::new(&items, 0, None, 10);
+        menu.query = Some(String::new());
+        // typed characters filter the menu, even the ones that move in other menus
+        for c in "wcb".chars() {
+            assert_eq!(menu.handle(&Key::Char(c)), MenuAction::Continue);
+        }
+        assert_eq!(menu.query, Some("wcb".to_string()));
+        assert_eq!(menu.shown, vec![2]);
+        assert_eq!(
+            menu.lines(&sheet),
+            vec!["> \x1b[33mW\x1b[0marp \x1b[33mc\x1b[0more \x1b[33mb\x1b[0mreach"]
+        );
+        menu.handle(&Key::Char('x'));
+        assert!(menu.shown.is_empty());
+        assert_eq!(menu.handle(&Key::Enter), MenuAction::Continue);
+        // the best matches come first
+        for _ in 0..4 {
+            menu.handle(&Key::Backspace);
+        }
+        assert_eq!(menu.shown, vec![0, 1, 2]);
+        menu.handle(&Key::Char('r'));
+        assert_eq!(menu.shown, vec![1, 0, 2]);
+        assert_eq!(menu.handle(&Key::ArrowDown), MenuAction::Continue);
+        assert_eq!(menu.current(), Some(0));
+        assert_eq!(menu.handle(&Key::Enter), MenuAction::Accept);
+    }
+}
@@ -0,0 +1,149 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use stylesheet::{Stylesheet, StyleColor, StyleProperties};
+
+extern crate qrcode;
+use self::qrcode::{Color, QrCode};
+
+
+/// The style of QR codes. The light modules are drawn with the text
+/// color, so it should be light on a dark background.
+pub const QR_STYLE: &str = "qr";
+
+/// The number of light modules around the code, that readers need to
+/// find it.
+pub const QUIET_ZONE: usize = 2;
+
+
+/// The error returned when a text doesn't fit in a QR code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataTooLong {
+    /// The length of the text, in bytes.
+    pub length: usize,
+}
+
+impl fmt::Display for DataTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The text is too long for a QR code ({} bytes)", self.length)
+    }
+}
+
+impl Error for DataTooLong {}
+
+
+/// Return the modules of the QR code of data, with the quiet zone
+/// around them: true for the light modules, row by row.
+fn light_modules(data: &str) -> Result<Vec<Vec<bool>>, DataTooLong> {
+    let code = QrCode::new(data).map_err(|_| DataTooLong { length: data.len() })?;
+    let width = code.width() + QUIET_ZONE * 2;
+    let light = |x: usize, y: usize| {
+        x < QUIET_ZONE || y < QUIET_ZONE || x >= code.width() + QUIET_ZONE || y >= code.width() + QUIET_ZONE
+            || code[(x - QUIET_ZONE, y - QUIET_ZONE)] == Color::Light
+    };
+    Ok((0..width).map(|y| (0..width).map(|x| light(x, y)).collect()).collect())
+}
+
+/// Return the QR code of a text, like a link, drawn with block
+/// characters so that it can be scanned from the terminal. Light
+/// modules are drawn and dark modules are left empty, which works on
+/// terminals with a dark background. With Unicode, every character
+/// contains two rows of modules; otherwise every module is "##" or
+/// two spaces. Every line has QR_STYLE, if sheet contains it.
+/// Return an error if the text is too long.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing QR_STYLE.
+/// * `data` - The text to encode.
+///
+/// # Example
+///
+/// ```
+/// use common::qr;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::new();
+/// let code = qr::format_qr(&sheet, "https://example.com/report.html").unwrap();
+/// assert!(code.lines().count() > 10);
+/// ```
+pub fn format_qr(sheet: &Stylesheet, data: &str) -> Result<String, DataTooLong> {
+    let modules = light_modules(data)?;
+    let lines: Vec<String> = if sheet.unicode_enabled() {
+        modules.chunks(2)
+            .map(|rows| {
+                let bottom_row = rows.get(1);
+                rows[0].iter().enumerate()
+                    .map(|(x, &top)| match (top, bottom_row.is_some_and(|row| row[x])) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    })
+                    .collect()
+            })
+            .collect()
+    } else {
+        modules.iter()
+            .map(|row| row.iter().map(|&light| if light { "##" } else { "  " }).collect())
+            .collect()
+    };
+    Ok(lines.iter()
+        .map(|line| if sheet.contains(QR_STYLE) { sheet.format(QR_STYLE, line) } else { line.clone() })
+        .collect::<Vec<String>>()
+        .join("\n"))
+}
+
+/// Print the QR code of a text. See format_qr().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `data` - The text to encode.
+pub fn print_qr(sheet: &Stylesheet, data: &str) -> io::Result<()> {
+    let code = format_qr(sheet, data).map_err(io::Error::other)?;
+    sheet.try_print_raw(&format!("{}\n", code))
+}
+
+/// Add QR_STYLE: white on black, so that codes can be scanned on
+/// terminals with a light background too. A style with the same name
+/// is replaced, so add it before loading the user configuration.
+///
+/// # Arguments
+///
+/// * `sheet` - The stylesheet to modify.
+pub fn add_qr_styles(sheet: &mut Stylesheet) {
+    sheet.add_style(
+        QR_STYLE,
+        StyleProperties::builder().color(StyleColor::BrightWhite).background(StyleColor::Black).build()
+    );
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+
+    #[test]
+    fn format() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_unicode(false);
+        let code = format_qr(&sheet, "USS Enterprise").unwrap();
+        let lines: Vec<&str> = code.lines().collect();
+        // version 1 codes have 21 modules per side
+        assert_eq!(lines.len(), 21 + QUIET_ZONE * 2);
+        assert!(lines.iter().all(|line| line.len() == lines.len() * 2));
+        assert_eq!(lines[0], "#".repeat(lines.len() * 2));
+        // the dark border of the top left finder pattern
+        assert!(lines[QUIET_ZONE].starts_with(&format!("####{}##", " ".repeat(14))));
+
+        sheet.set_unicode(true);
+        assert_eq!(format_qr(&sheet, "USS Enterprise").unwrap().lines().count(), (21 + QUIET_ZONE * 2).div_ceil(2));
+        sheet.set_color_mode(ColorMode::Always);
+        add_qr_styles(&mut sheet);
+        assert!(format_qr(&sheet, "USS Enterprise").unwrap().starts_with("\x1b["));
+
+        assert_eq!(format_qr(&sheet, &"x".repeat(3000)), Err(DataTooLong { length: 3000 }));
+    }
+}
@@ -1,18 +1,205 @@
-use stylesheet::Stylesheet;
+use std::collections::BTreeMap;
+use std::io;
+
+use level_printer::LevelPrinter;
+use notification;
+use stylesheet::{ColorMode, Stylesheet, StyleColor, StyleProperties};
+use term;
+
+extern crate serde;
+use self::serde::Deserialize;
+
+
+/// Colors of threat scores, from 0 to 100.
+const THREAT_COLORS: [StyleColor; 3] = [
+    StyleColor::Rgb(0, 200, 0), StyleColor::Rgb(255, 200, 0), StyleColor::Rgb(220, 0, 0),
+];
 
 
 #[derive(Debug, Clone, Copy)]
 pub struct Event {
     pub level: &'static str,
     pub message: &'static str,
+    /// How dangerous the event is, from 0 to 100.
+    pub threat: u8,
 }
 
 impl Event {
-    pub fn print(&self, sheet: &Stylesheet) {
-        sheet.println(self.level, self.message);
+    /// Print the threat score, colored with the styles added by
+    /// add_threat_styles(), followed by the event message, with the
+    /// style named after its level.
+    pub fn print(&self, sheet: &Stylesheet) -> io::Result<()> {
+        self.print_with(sheet, &mut LevelPrinter::default()).map(|_| ())
+    }
+
+    /// Same as print(), but the message is printed by printer, with the
+    /// style of its level, and counted. Nothing is printed if printer
+    /// doesn't show the level. Return true if the event was printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `printer` - The LevelPrinter that filters and counts the events.
+    pub fn print_with(&self, sheet: &Stylesheet, printer: &mut LevelPrinter) -> io::Result<bool> {
+        if !printer.shows(self.level) {
+            return Ok(false);
+        }
+        sheet.try_print(&threat_style(self.threat), format!("[{:>3}] ", self.threat))?;
+        printer.println(sheet, self.level, self.message)
+    }
+
+    /// Return the threat score and the event message, with the same
+    /// styles used by print(), but without prefixes and timestamps.
+    /// Useful to show events in other layouts, like dashboards.
+    pub fn format(&self, sheet: &Stylesheet) -> String {
+        format!(
+            "{}{}",
+            sheet.format(&threat_style(self.threat), format!("[{:>3}] ", self.threat)),
+            sheet.format(self.level, self.message)
+        )
+    }
+}
+
+/// How the user is alerted about the events of a level, besides
+/// printing them. Unset options are None, like in ScannerSettings,
+/// and mean false.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertSettings {
+    /// Ring the terminal bell.
+    #[serde(default)]
+    pub bell: Option<bool>,
+    /// Show a desktop notification.
+    #[serde(default)]
+    pub notify: Option<bool>,
+}
+
+impl AlertSettings {
+    /// Return these settings, overridden by the settings that are
+    /// set in other.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The settings with a higher precedence.
+    pub fn merge(&self, other: &AlertSettings) -> AlertSettings {
+        AlertSettings {
+            bell: other.bell.or(self.bell),
+            notify: other.notify.or(self.notify),
+        }
+    }
+}
+
+/// Settings of the scanner, read from configuration files.
+/// See config::ConfigFiles. Unset settings are None, so that files
+/// with a higher precedence only override the settings they contain.
+/// Alerts are configured per level:
+///
+/// ```toml
+/// min_threat = 10
+///
+/// [alerts.danger]
+/// bell = true
+/// notify = true
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScannerSettings {
+    /// When to use colors: "always", "auto" or "never".
+    #[serde(default)]
+    pub color: Option<ColorMode>,
+    /// Events with a lower threat score are not printed.
+    #[serde(default)]
+    pub min_threat: Option<u8>,
+    /// Alerts for the events of each level.
+    #[serde(default)]
+    pub alerts: BTreeMap<String, AlertSettings>,
+}
+
+impl ScannerSettings {
+    /// Return these settings, overridden by the settings that are
+    /// set in other.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The settings with a higher precedence.
+    pub fn merge(&self, other: &ScannerSettings) -> ScannerSettings {
+        let mut alerts = self.alerts.clone();
+        for (level, settings) in &other.alerts {
+            let merged = alerts.get(level).cloned().unwrap_or_default().merge(settings);
+            alerts.insert(level.clone(), merged);
+        }
+        ScannerSettings {
+            color: other.color.or(self.color),
+            min_threat: other.min_threat.or(self.min_threat),
+            alerts,
+        }
+    }
+
+    /// Return true if an event must be printed, according to min_threat.
+    pub fn shows(&self, event: &Event) -> bool {
+        event.threat >= self.min_threat.unwrap_or(0)
+    }
+
+    /// Return the alerts configured for the level of an event.
+    pub fn alerts_for(&self, event: &Event) -> AlertSettings {
+        self.alerts.get(event.level).cloned().unwrap_or_default()
+    }
+
+    /// Alert the user about an event, as configured for its level.
+    /// The bell only rings if the output of sheet is a terminal.
+    /// Desktop notifications are shown in the background, so this
+    /// doesn't block; errors starting them are returned after ringing
+    /// the bell.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `event` - The event, usually one that shows() accepts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::scanner::{long_range_scanner, AlertSettings, ScannerSettings};
+    /// use common::stylesheet::Stylesheet;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let mut settings = ScannerSettings::default();
+    /// settings.alerts.insert("danger".to_string(), AlertSettings { bell: Some(true), notify: None });
+    /// let event = long_range_scanner::scan();
+    /// event.print(&sheet).unwrap();
+    /// settings.alert(&sheet, &event).unwrap();
+    /// ```
+    pub fn alert(&self, sheet: &Stylesheet, event: &Event) -> io::Result<()> {
+        let alerts = self.alerts_for(event);
+        if alerts.bell.unwrap_or(false) {
+            term::print(sheet, term::BELL)?;
+            sheet.flush()?;
+        }
+        if alerts.notify.unwrap_or(false) {
+            notification::notify_in_background(&format!("Scanner: {}", event.level), event.message)?;
+        }
+        Ok(())
     }
 }
 
+/// Return the name of the style used for a threat score.
+/// Scores are rounded to the tens.
+fn threat_style(threat: u8) -> String {
+    format!("threat_{}", (u32::from(threat.min(100)) + 5) / 10 * 10)
+}
+
+/// Add the styles used to show threat scores: "threat_0", "threat_10"
+/// and so on, up to "threat_100". Their colors go from green to red.
+///
+/// # Arguments
+///
+/// * `sheet` - The stylesheet to modify.
+pub fn add_threat_styles(sheet: &mut Stylesheet) {
+    let colors = StyleColor::palette(&THREAT_COLORS, 11);
+    sheet.add_styles(colors.into_iter().enumerate().map(|(i, color)| {
+        (format!("threat_{}", i * 10), StyleProperties::builder().color(color).build())
+    }));
+}
+
 
 pub mod long_range_scanner {
     use super::Event;
@@ -24,13 +211,90 @@ pub mod long_range_scanner {
     pub fn scan() -> Event {
         // Detect a random event
         let possible_events: [Event; 4] = [
-            Event { level: "info", message: "Whormhole detected" },
-            Event { level: "info", message: "Vulcan ship deteced" },
-            Event { level: "danger", message: "Romulan ship approaching!" },
-            Event { level: "danger", message: "Borg cube approaching!" },
+            Event { level: "info", message: "Whormhole detected", threat: 20 },
+            Event { level: "info", message: "Vulcan ship deteced", threat: 5 },
+            Event { level: "danger", message: "Romulan ship approaching!", threat: 70 },
+            Event { level: "danger", message: "Borg cube approaching!", threat: 95 },
         ];
 
         let rnd = rand::thread_rng().gen_range(0, possible_events.len());
         possible_events[rnd]
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::SharedBuffer;
+
+    extern crate toml;
+
+    #[test]
+    fn threat_styles() {
+        let mut sheet = Stylesheet::new();
+        add_threat_styles(&mut sheet);
+        for threat in [0, 4, 5, 95, 100, 255].iter() {
+            // every score must have a style
+            assert!(sheet.contains(&threat_style(*threat)));
+        }
+        assert_eq!(threat_style(74), "threat_70");
+        assert_eq!(sheet.get_properties("threat_0").unwrap().color, Some(THREAT_COLORS[0].clone()));
+        assert_eq!(sheet.get_properties("threat_100").unwrap().color, Some(THREAT_COLORS[2].clone()));
+    }
+
+    #[test]
+    fn merge_settings() {
+        let system = ScannerSettings { color: Some(ColorMode::Never), min_threat: Some(10), ..Default::default() };
+        let user = ScannerSettings { color: None, min_threat: Some(50), ..Default::default() };
+        let merged = system.merge(&user);
+        // unset settings must not override the others
+        assert_eq!(merged, ScannerSettings { color: Some(ColorMode::Never), min_threat: Some(50), ..Default::default() });
+        let event = Event { level: "info", message: "Whormhole detected", threat: 20 };
+        assert!(!merged.shows(&event));
+        assert!(ScannerSettings::default().shows(&event));
+    }
+
+    #[test]
+    fn merge_alerts() {
+        let system: ScannerSettings = toml::from_str("[alerts.danger]\nbell = true\nnotify = true\n").unwrap();
+        let user: ScannerSettings = toml::from_str("[alerts.danger]\nnotify = false\n[alerts.info]\nbell = true\n").unwrap();
+        let merged = system.merge(&user);
+        let borg = Event { level: "danger", message: "Borg cube approaching!", threat: 95 };
+        let wormhole = Event { level: "info", message: "Whormhole detected", threat: 20 };
+        let vulcan = Event { level: "vulcan", message: "Vulcan ship deteced", threat: 5 };
+        assert_eq!(merged.alerts_for(&borg), AlertSettings { bell: Some(true), notify: Some(false) });
+        assert_eq!(merged.alerts_for(&wormhole), AlertSettings { bell: Some(true), notify: None });
+        assert_eq!(merged.alerts_for(&vulcan), AlertSettings::default());
+    }
+
+    #[test]
+    fn bell_only_on_terminals() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        let settings: ScannerSettings = toml::from_str("[alerts.danger]\nbell = true\n").unwrap();
+        let borg = Event { level: "danger", message: "Borg cube approaching!", threat: 95 };
+        let wormhole = Event { level: "info", message: "Whormhole detected", threat: 20 };
+        settings.alert(&sheet, &borg).unwrap();
+        assert_eq!(buffer.contents(), "");
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        settings.alert(&sheet, &wormhole).unwrap();
+        settings.alert(&sheet, &borg).unwrap();
+        assert_eq!(buffer.contents(), "\x07");
+    }
+
+    #[test]
+    fn print_with_level_printer() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        let mut printer = LevelPrinter::default();
+        printer.set_min_level("warning").unwrap();
+        let borg = Event { level: "danger", message: "Borg cube approaching!", threat: 95 };
+        let vulcan = Event { level: "info", message: "Vulcan ship detected", threat: 5 };
+        assert!(printer.shows(borg.level));
+        assert!(!vulcan.print_with(&sheet, &mut printer).unwrap());
+        assert!(borg.print_with(&sheet, &mut printer).unwrap());
+        assert_eq!(buffer.contents(), "[ 95] Borg cube approaching!\n");
+        assert_eq!(printer.counts(), vec![("danger", 1)]);
+    }
+}
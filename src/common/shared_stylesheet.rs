@@ -0,0 +1,348 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use stylesheet::{ConflictPolicy, Stylesheet, StylesheetError};
+
+
+lazy_static! {
+    static ref GLOBAL: SharedStylesheet = SharedStylesheet::new(Stylesheet::new());
+}
+
+
+/// A Stylesheet that can be shared between threads.
+/// Cloning a SharedStylesheet returns a new handle to the same Stylesheet,
+/// so all threads print with the same styles, and changes made by one
+/// thread are seen by the others.
+#[derive(Clone, Default)]
+pub struct SharedStylesheet {
+    sheet: Arc<RwLock<Stylesheet>>,
+}
+
+impl SharedStylesheet {
+    /// Return a new SharedStylesheet wrapping an existing Stylesheet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::thread;
+    /// use common::stylesheet::Stylesheet;
+    /// use common::shared_stylesheet::SharedStylesheet;
+    /// let shared = SharedStylesheet::new(Stylesheet::new());
+    /// let handle = shared.clone();
+    /// thread::spawn(move || handle.println("info", "From a thread")).join().unwrap();
+    /// ```
+    pub fn new(sheet: Stylesheet) -> SharedStylesheet {
+        SharedStylesheet {
+            sheet: Arc::new(RwLock::new(sheet)),
+        }
+    }
+
+    /// Lock the Stylesheet for reading. Many threads can read at the same time.
+    /// Don't keep the lock longer than needed, because it blocks write().
+    pub fn read(&self) -> RwLockReadGuard<'_, Stylesheet> {
+        // a thread that panicked while holding the lock (for example because it
+        // added a style to a frozen sheet) doesn't leave the Stylesheet in an
+        // inconsistent state, so poisoning can be ignored
+        self.sheet.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Lock the Stylesheet for modifying it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::shared_stylesheet::SharedStylesheet;
+    /// let shared = SharedStylesheet::default();
+    /// shared.write().add_style("info", StyleProperties {
+    ///     transformation: [].to_vec(), color: Some(Green), background: None, underline_color: None
+    /// });
+    /// ```
+    pub fn write(&self) -> RwLockWriteGuard<'_, Stylesheet> {
+        self.sheet.write().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Same as Stylesheet::println(), but it locks the Stylesheet,
+    /// so lines printed by different threads are not mixed.
+    pub fn println<S>(&self, style_name: &str, message: S) where S: AsRef<str> {
+        self.read().println(style_name, message);
+    }
+
+    /// Same as Stylesheet::print(), but it locks the Stylesheet.
+    pub fn print<S>(&self, style_name: &str, message: S) where S: AsRef<str> {
+        self.read().print(style_name, message);
+    }
+
+    /// Replace the styles with the ones of a stylesheet file, keeping
+    /// the other settings. See Stylesheet::from_file() for the supported
+    /// formats. The file is parsed before locking the Stylesheet, and
+    /// the styles are replaced all together: other threads never see
+    /// a partially loaded stylesheet. If an error occurs, the current
+    /// styles are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the stylesheet file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::shared_stylesheet::SharedStylesheet;
+    /// let shared = SharedStylesheet::default();
+    /// shared.reload_file("styles.toml").unwrap();
+    /// ```
+    pub fn reload_file<P>(&self, path: P) -> Result<(), StylesheetError> where P: AsRef<Path> {
+        let sheet = Stylesheet::from_file(path)?;
+        self.write().replace_styles(&sheet)
+    }
+
+    /// Same as reload_file(), but the styles of the file are merged over
+    /// the styles of base, like configuration files: styles that are
+    /// not in the file keep their definition in base.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The styles that the file overrides.
+    /// * `path` - The path of the stylesheet file.
+    pub fn reload_file_over<P>(&self, base: &Stylesheet, path: P) -> Result<(), StylesheetError> where P: AsRef<Path> {
+        let mut sheet = base.clone_unfrozen();
+        sheet.merge(&Stylesheet::from_file(path)?, ConflictPolicy::Override)?;
+        self.write().replace_styles(&sheet)
+    }
+
+    /// Watch a stylesheet file, and reload it with reload_file() when it
+    /// changes, so the styles of a long-running program can be modified
+    /// without restarting it. The file is checked every interval, in a
+    /// separate thread, until the returned StylesheetWatcher is dropped.
+    /// Reload errors are passed to on_error, and the current styles are
+    /// kept; while the file doesn't exist, nothing happens.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the stylesheet file.
+    /// * `interval` - How often the file is checked.
+    /// * `on_error` - Called when the file changed but can't be loaded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use common::shared_stylesheet::SharedStylesheet;
+    /// let shared = SharedStylesheet::default();
+    /// shared.reload_file("styles.toml").unwrap();
+    /// let _watcher = shared.watch_file("styles.toml", Duration::from_secs(1), |err| eprintln!("{}", err));
+    /// shared.println("info", "Styles can be edited while we run");
+    /// ```
+    pub fn watch_file<P, F>(&self, path: P, interval: Duration, on_error: F) -> StylesheetWatcher
+            where P: Into<PathBuf>, F: Fn(&StylesheetError) + Send + 'static {
+        self.watch(path.into(), interval, |shared, path| shared.reload_file(path), on_error)
+    }
+
+    /// Same as watch_file(), but the file is reloaded with
+    /// reload_file_over(), so that it only overrides the styles of base.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the stylesheet file.
+    /// * `base` - The styles that the file overrides.
+    /// * `interval` - How often the file is checked.
+    /// * `on_error` - Called when the file changed but can't be loaded.
+    pub fn watch_file_over<P, F>(&self, path: P, base: Stylesheet, interval: Duration, on_error: F) -> StylesheetWatcher
+            where P: Into<PathBuf>, F: Fn(&StylesheetError) + Send + 'static {
+        self.watch(path.into(), interval, move |shared, path| shared.reload_file_over(&base, path), on_error)
+    }
+
+    /// Call reload when the file at path changes. See watch_file().
+    fn watch<R, F>(&self, path: PathBuf, interval: Duration, reload: R, on_error: F) -> StylesheetWatcher
+            where R: Fn(&SharedStylesheet, &Path) -> Result<(), StylesheetError> + Send + 'static,
+                  F: Fn(&StylesheetError) + Send + 'static {
+        let shared = self.clone();
+        // dropping the sender wakes up the thread and stops it
+        let (stop, stopped) = mpsc::channel::<()>();
+        // changes made after this call must be seen
+        let mut version = file_version(&path);
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                let current = file_version(&path);
+                if current == version || current.is_none() {
+                    continue;
+                }
+                version = current;
+                if let Err(err) = reload(&shared, &path) {
+                    on_error(&err);
+                }
+            }
+        });
+        StylesheetWatcher { stop: Some(stop), thread: Some(thread) }
+    }
+}
+
+/// Return what tells if a file changed: its modification time and size.
+/// Return None if the file can't be read.
+fn file_version(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+
+/// Watches a stylesheet file, returned by SharedStylesheet::watch_file().
+/// When dropped, it stops watching.
+#[derive(Debug)]
+pub struct StylesheetWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StylesheetWatcher {
+    /// Stop watching the file. Same as dropping the watcher.
+    pub fn stop(self) {}
+}
+
+impl Drop for StylesheetWatcher {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            // on_error may have panicked: there is nothing left to stop
+            let _ = thread.join();
+        }
+    }
+}
+
+
+/// Return a handle to the process-global Stylesheet.
+/// Until set_global() is called, it only contains the default style.
+///
+/// # Example
+///
+/// ```
+/// use common::shared_stylesheet;
+/// shared_stylesheet::global().println("info", "Some text");
+/// ```
+pub fn global() -> SharedStylesheet {
+    GLOBAL.clone()
+}
+
+/// Replace the process-global Stylesheet. Handles returned by global()
+/// before this call will use the new Stylesheet too.
+///
+/// # Example
+///
+/// ```
+/// use common::stylesheet::Stylesheet;
+/// use common::shared_stylesheet;
+/// shared_stylesheet::set_global(Stylesheet::new());
+/// ```
+pub fn set_global(sheet: Stylesheet) {
+    *GLOBAL.write() = sheet;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use std::sync::Mutex;
+    use super::*;
+    use stylesheet::StyleProperties;
+    use stylesheet::StyleColor::*;
+
+
+    #[test]
+    fn stylesheet_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Stylesheet>();
+        assert_send_sync::<SharedStylesheet>();
+    }
+
+    #[test]
+    fn print_from_threads() {
+        let shared = SharedStylesheet::new(Stylesheet::new());
+        let threads: Vec<_> = (0..4).map(|i| {
+            let handle = shared.clone();
+            thread::spawn(move || {
+                handle.write().add_style("info", StyleProperties {
+                    transformation: [].to_vec(), color: Some(Green), background: None, underline_color: None,
+                });
+                handle.println("info", format!("Thread {}", i));
+            })
+        }).collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        // all threads modified the same sheet
+        assert!(shared.read().to_json().contains("info"));
+    }
+
+    #[test]
+    fn reload_watched_file() {
+        let path = env::temp_dir().join(format!("cli_exp_reload_{}.toml", process::id()));
+        fs::write(&path, "[info]\ncolor = \"green\"\n").unwrap();
+        let shared = SharedStylesheet::new(Stylesheet::new());
+        shared.reload_file(&path).unwrap();
+        assert_eq!(shared.read().get_properties("info").unwrap().color, Some(Green));
+        // the format is deduced from the extension
+        assert!(matches!(shared.reload_file("styles.txt"), Err(StylesheetError::UnknownFormat(_))));
+
+        let errors = Arc::new(Mutex::new(0));
+        let counter = errors.clone();
+        let watcher = shared.watch_file(&path, Duration::from_millis(10), move |_| *counter.lock().unwrap() += 1);
+        // a broken file must be reported, and must not replace the styles
+        fs::write(&path, "[info\n").unwrap();
+        wait_for(|| *errors.lock().unwrap() > 0);
+        assert!(shared.read().contains("info"));
+        fs::write(&path, "[info]\ncolor = \"magenta\"\n[danger]\ncolor = \"red\"\n").unwrap();
+        wait_for(|| shared.read().contains("danger"));
+        assert_eq!(shared.read().get_properties("info").unwrap().color, Some(Magenta));
+        watcher.stop();
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reload_file_over_base() {
+        let path = env::temp_dir().join(format!("cli_exp_reload_over_{}.toml", process::id()));
+        fs::write(&path, "[info]\ncolor = \"green\"\n").unwrap();
+        let mut base = Stylesheet::new();
+        base.add_style("danger", StyleProperties::builder().color(Red).build());
+        base.add_style("info", StyleProperties::builder().color(Cyan).build());
+        let base = base.into_frozen();
+        let shared = SharedStylesheet::new(base.clone_unfrozen());
+        let watcher = shared.watch_file_over(&path, base.clone_unfrozen(), Duration::from_millis(10), |_| ());
+        fs::write(&path, "[info]\ncolor = \"magenta\"\n").unwrap();
+        wait_for(|| shared.read().get_properties("info").unwrap().color == Some(Magenta));
+        // the styles that are not in the file are kept
+        assert_eq!(shared.read().get_properties("danger").unwrap().color, Some(Red));
+        watcher.stop();
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Wait until condition is true, or fail after some seconds.
+    fn wait_for<F>(condition: F) where F: Fn() -> bool {
+        for _ in 0..500 {
+            if condition() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("FATAL: timeout");
+    }
+
+    #[test]
+    fn survives_a_panicking_thread() {
+        let shared = SharedStylesheet::new(Stylesheet::new());
+        shared.write().freeze();
+        let handle = shared.clone();
+        let result = thread::spawn(move || {
+            handle.write().add_style("info", StyleProperties {
+                transformation: [].to_vec(), color: None, background: None, underline_color: None,
+            });
+        }).join();
+        assert!(result.is_err());
+        // the lock is poisoned, but still usable
+        shared.println("info", "Still working");
+    }
+}
@@ -0,0 +1,293 @@
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use icons::Icon;
+use stylesheet::Stylesheet;
+use term;
+
+
+/// The default style of the spinner frames.
+pub const SPINNER_STYLE: &str = "spinner";
+/// The default style of the spinner message.
+pub const MESSAGE_STYLE: &str = "spinner_message";
+/// The style used by Spinner::success().
+pub const SUCCESS_STYLE: &str = "success";
+/// The style used by Spinner::fail().
+pub const FAIL_STYLE: &str = "danger";
+
+/// Braille dots, the default frames on Unicode terminals.
+pub const DOTS: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+/// A rotating line, the default frames on ASCII terminals.
+pub const LINE: [&str; 4] = ["-", "\\", "|", "/"];
+
+/// The default time between two frames.
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(80);
+
+
+/// An animation shown during operations whose duration is unknown,
+/// like "Scanning sector...". The spinner runs in a separate thread
+/// from start() until stop(), success() or fail(); it is only drawn
+/// when the output is a terminal. The frames and the message are
+/// printed with styles of the Stylesheet.
+///
+/// # Example
+///
+/// ```
+/// use std::thread;
+/// use std::time::Duration;
+/// use common::spinner::Spinner;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut spinner = Spinner::new("Scanning sector 7...");
+/// spinner.start(&sheet);
+/// thread::sleep(Duration::from_millis(200));
+/// spinner.success(&sheet, "Sector 7 is clear").unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Spinner {
+    message: String,
+    /// None means the default frames: DOTS or LINE.
+    frames: Option<Vec<String>>,
+    interval: Duration,
+    spinner_style: String,
+    message_style: String,
+    /// While running: the channel that stops the animation, and its thread.
+    running: Option<(Sender<()>, JoinHandle<()>)>,
+}
+
+impl Spinner {
+    /// Return a new spinner, not started yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The text printed after the spinner.
+    pub fn new<S>(message: S) -> Spinner where S: Into<String> {
+        Spinner {
+            message: message.into(),
+            frames: None,
+            interval: DEFAULT_INTERVAL,
+            spinner_style: SPINNER_STYLE.to_string(),
+            message_style: MESSAGE_STYLE.to_string(),
+            running: None,
+        }
+    }
+
+    /// Use other frames instead of DOTS or LINE. Frames should have
+    /// the same width. If frames is empty, the default ones are used.
+    pub fn set_frames(&mut self, frames: &[&str]) {
+        self.frames = if frames.is_empty() {
+            None
+        } else {
+            Some(frames.iter().map(|frame| frame.to_string()).collect())
+        };
+    }
+
+    /// Set the time between two frames. It takes effect at the next start().
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Use other styles instead of SPINNER_STYLE and MESSAGE_STYLE.
+    ///
+    /// # Arguments
+    ///
+    /// * `spinner` - The style of the frames.
+    /// * `message` - The style of the message.
+    pub fn set_styles(&mut self, spinner: &str, message: &str) {
+        self.spinner_style = spinner.to_string();
+        self.message_style = message.to_string();
+    }
+
+    /// Change the text printed after the spinner. It takes effect at
+    /// the next start().
+    pub fn set_message<S>(&mut self, message: S) where S: Into<String> {
+        self.message = message.into();
+    }
+
+    /// Return the text printed after the spinner.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Return true if the spinner was started and not stopped.
+    pub fn is_running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    /// Return the frames to show with sheet.
+    fn frames_for(&self, sheet: &Stylesheet) -> Vec<String> {
+        match self.frames {
+            Some(ref frames) => frames.clone(),
+            None if sheet.unicode_enabled() => DOTS.iter().map(|frame| frame.to_string()).collect(),
+            None => LINE.iter().map(|frame| frame.to_string()).collect(),
+        }
+    }
+
+    /// Return the line that shows a frame and the message.
+    fn format_frame(&self, sheet: &Stylesheet, frame: &str) -> String {
+        format!(
+            "{} {}",
+            sheet.format(&self.spinner_style, frame),
+            sheet.format(&self.message_style, &self.message)
+        )
+    }
+
+    /// Return the line of the spinner, with the styles applied, showing
+    /// the frame that is due after elapsed time. This allows to draw the
+    /// spinner without its thread, like MultiProgress does.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet containing the styles.
+    /// * `elapsed` - The time passed since the animation started.
+    pub fn format(&self, sheet: &Stylesheet, elapsed: Duration) -> String {
+        let frames = self.frames_for(sheet);
+        let interval = self.interval.as_millis().max(1);
+        let index = (elapsed.as_millis() / interval % frames.len() as u128) as usize;
+        self.format_frame(sheet, &frames[index])
+    }
+
+    /// Start the animation, if the output is a terminal and the spinner
+    /// is not running. The thread prints with a clone of sheet, that
+    /// shares its writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn start(&mut self, sheet: &Stylesheet) {
+        if self.running.is_some() || !sheet.is_interactive() {
+            return;
+        }
+        let lines: Vec<String> = self.frames_for(sheet).iter()
+            .map(|frame| term::replace_line(&self.format_frame(sheet, frame)))
+            .collect();
+        let interval = self.interval;
+        let sheet = sheet.clone();
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::spawn(move || {
+            for line in lines.iter().cycle() {
+                // write errors only stop the animation
+                if sheet.try_print_raw(line).and_then(|_| sheet.flush()).is_err() {
+                    return;
+                }
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => (),
+                    _ => return,
+                }
+            }
+        });
+        self.running = Some((stop, thread));
+    }
+
+    /// Stop the animation and clear its line.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn stop(&mut self, sheet: &Stylesheet) -> io::Result<()> {
+        if let Some((stop, thread)) = self.running.take() {
+            drop(stop);
+            // the thread doesn't panic, it only prints
+            let _ = thread.join();
+            sheet.try_print_raw(&term::replace_line(""))?;
+            sheet.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Stop the animation, and print message with a success icon and
+    /// SUCCESS_STYLE. This is printed even if the output is not a terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `message` - The final message.
+    pub fn success(&mut self, sheet: &Stylesheet, message: &str) -> io::Result<()> {
+        self.finish(sheet, Icon::Success, SUCCESS_STYLE, message)
+    }
+
+    /// Stop the animation, and print message with an error icon and
+    /// FAIL_STYLE. This is printed even if the output is not a terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `message` - The final message.
+    pub fn fail(&mut self, sheet: &Stylesheet, message: &str) -> io::Result<()> {
+        self.finish(sheet, Icon::Error, FAIL_STYLE, message)
+    }
+
+    /// Stop the animation and replace it with a final line.
+    fn finish(&mut self, sheet: &Stylesheet, icon: Icon, style_name: &str, message: &str) -> io::Result<()> {
+        self.stop(sheet)?;
+        let line = format!("{} {}", icon.glyph(sheet.unicode_enabled()), message);
+        sheet.try_print_raw(&format!("{}\n", sheet.format(style_name, line)))?;
+        sheet.flush()
+    }
+}
+
+/// A spinner that is dropped while running stops its thread, but
+/// doesn't clear its line.
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        if let Some((stop, thread)) = self.running.take() {
+            drop(stop);
+            let _ = thread.join();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+    use test_support::SharedBuffer;
+
+    #[test]
+    fn animate_and_finish() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        let mut spinner = Spinner::new("Scanning");
+        spinner.set_frames(&["a", "b"]);
+        spinner.set_interval(Duration::from_millis(1));
+        spinner.start(&sheet);
+        assert!(spinner.is_running());
+        while !buffer.contents().contains("\rb Scanning") {
+            thread::sleep(Duration::from_millis(1));
+        }
+        spinner.fail(&sheet, "Sector 7 unreachable").unwrap();
+        assert!(!spinner.is_running());
+        assert!(buffer.contents().starts_with("\ra Scanning\x1b[K\rb Scanning\x1b[K"));
+        assert!(buffer.contents().ends_with("\r\x1b[Kx Sector 7 unreachable\n"));
+    }
+
+    #[test]
+    fn frame_at() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        let mut spinner = Spinner::new("Scanning");
+        spinner.set_frames(&["a", "b", "c"]);
+        spinner.set_interval(Duration::from_millis(100));
+        assert_eq!(spinner.format(&sheet, Duration::from_millis(0)), "a Scanning");
+        assert_eq!(spinner.format(&sheet, Duration::from_millis(150)), "b Scanning");
+        assert_eq!(spinner.format(&sheet, Duration::from_millis(300)), "a Scanning");
+    }
+
+    #[test]
+    fn not_animated_when_redirected() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_unicode(false);
+        let mut spinner = Spinner::new("Scanning");
+        spinner.start(&sheet);
+        assert!(!spinner.is_running());
+        spinner.success(&sheet, "Done").unwrap();
+        assert_eq!(buffer.contents(), "+ Done\n");
+    }
+}
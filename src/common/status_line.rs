@@ -0,0 +1,173 @@
+use std::io;
+
+use stylesheet::Stylesheet;
+use term;
+use text;
+
+
+/// The default style of status lines.
+pub const STATUS_STYLE: &str = "status_line";
+
+
+/// A line at the bottom of the output that can be updated in place,
+/// like "events: 42 | last: Borg cube", while other lines are printed
+/// above it. The status line is always the last line: lines printed
+/// with println() erase it, and it is redrawn below them. It is
+/// truncated to the width of the terminal, because a line that wraps
+/// can't be redrawn. If the output is not a terminal, the status line
+/// is not shown and println() prints normally.
+/// Lines printed without the StatusLine would mix with it. To print
+/// from several threads, keep the StatusLine in a Mutex.
+///
+/// # Example
+///
+/// ```
+/// use common::status_line::StatusLine;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut status = StatusLine::new();
+/// for (n, event) in ["Borg cube", "Romulan warbird"].iter().enumerate() {
+///     status.println(&sheet, "danger", format!("Detected: {}", event)).unwrap();
+///     status.update(&sheet, format!("events: {} | last: {}", n + 1, event)).unwrap();
+/// }
+/// status.clear(&sheet).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatusLine {
+    text: String,
+    style_name: String,
+    /// true if the status line is on the screen.
+    visible: bool,
+}
+
+impl Default for StatusLine {
+    fn default() -> Self {
+        StatusLine::new()
+    }
+}
+
+impl StatusLine {
+    /// Return an empty status line, not shown yet.
+    pub fn new() -> StatusLine {
+        StatusLine {
+            text: String::new(),
+            style_name: STATUS_STYLE.to_string(),
+            visible: false,
+        }
+    }
+
+    /// Use another style instead of STATUS_STYLE.
+    pub fn set_style(&mut self, style_name: &str) {
+        self.style_name = style_name.to_string();
+    }
+
+    /// Return the text of the status line.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Return the line to draw, no wider than width.
+    fn line(&self, sheet: &Stylesheet, width: usize) -> String {
+        // the last column is not used, so the cursor never wraps
+        let text = text::truncate(&self.text, width.saturating_sub(1));
+        term::replace_line(&sheet.format(&self.style_name, text))
+    }
+
+    /// Draw the status line, replacing the current line.
+    fn draw(&mut self, sheet: &Stylesheet) -> io::Result<()> {
+        sheet.try_print_raw(&self.line(sheet, sheet.output_width()))?;
+        self.visible = true;
+        sheet.flush()
+    }
+
+    /// Replace the text of the status line and redraw it. If the output
+    /// is not a terminal, nothing is printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `text` - The new text.
+    pub fn update<S>(&mut self, sheet: &Stylesheet, text: S) -> io::Result<()> where S: Into<String> {
+        self.text = text.into();
+        if !sheet.is_interactive() {
+            return Ok(());
+        }
+        self.draw(sheet)
+    }
+
+    /// Print a line above the status line, like Stylesheet::try_println().
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    /// * `style_name` - The name of the style to use.
+    /// * `message` - The text to print.
+    pub fn println<S>(&mut self, sheet: &Stylesheet, style_name: &str, message: S) -> io::Result<()> where S: AsRef<str> {
+        if !self.visible {
+            return sheet.try_println(style_name, message);
+        }
+        sheet.try_print_raw(&term::replace_line(""))?;
+        sheet.try_println(style_name, message)?;
+        self.draw(sheet)
+    }
+
+    /// Remove the status line from the screen. The text is kept, and
+    /// update() shows it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet used to print.
+    pub fn clear(&mut self, sheet: &Stylesheet) -> io::Result<()> {
+        if !self.visible {
+            return Ok(());
+        }
+        self.visible = false;
+        sheet.try_print_raw(&term::replace_line(""))?;
+        sheet.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+    use test_support::SharedBuffer;
+
+    #[test]
+    fn lines_above_status() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        let mut status = StatusLine::new();
+        status.println(&sheet, "info", "first").unwrap();
+        status.update(&sheet, "events: 1").unwrap();
+        status.println(&sheet, "info", "second").unwrap();
+        status.clear(&sheet).unwrap();
+        assert_eq!(
+            buffer.contents(),
+            "first\n\revents: 1\x1b[K\r\x1b[Ksecond\n\revents: 1\x1b[K\r\x1b[K"
+        );
+    }
+
+    #[test]
+    fn truncated_status() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        let mut status = StatusLine::new();
+        status.text = "events: 42 | last: Borg cube".to_string();
+        assert_eq!(status.line(&sheet, 12), "\revents: 42…\x1b[K");
+    }
+
+    #[test]
+    fn not_shown_when_redirected() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        let mut status = StatusLine::new();
+        status.update(&sheet, "events: 1").unwrap();
+        status.println(&sheet, "info", "line").unwrap();
+        status.clear(&sheet).unwrap();
+        assert_eq!(buffer.contents(), "line\n");
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::ops::{Add, AddAssign};
+use std::slice;
+
+use stylesheet::Stylesheet;
+
+
+/// A line made of several texts, each one with its own style.
+/// It can be built piece by piece and concatenated with +, and only
+/// becomes a string with escape sequences when it is rendered with
+/// a Stylesheet. When it is displayed, the plain text is written.
+///
+/// # Example
+///
+/// ```
+/// use common::styled_string::StyledString;
+/// use common::stylesheet::Stylesheet;
+/// let sheet = Stylesheet::theme_dark();
+/// let mut line = StyledString::new();
+/// line.push("success", "[ OK ]");
+/// line.push_plain(" ");
+/// let line = line + StyledString::styled("info", "service started");
+/// assert_eq!(line.plain(), "[ OK ] service started");
+/// sheet.println_styled(&line);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyledString {
+    /// (style name, text) pairs. None is the default style.
+    segments: Vec<(Option<String>, String)>,
+}
+
+impl StyledString {
+    /// Return an empty StyledString.
+    pub fn new() -> StyledString {
+        StyledString { segments: Vec::new() }
+    }
+
+    /// Return a StyledString containing a single text with a style.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The text to add.
+    pub fn styled<S>(style_name: &str, text: S) -> StyledString where S: Into<String> {
+        let mut string = StyledString::new();
+        string.push(style_name, text);
+        string
+    }
+
+    /// Add a text with a style at the end.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The text to add.
+    pub fn push<S>(&mut self, style_name: &str, text: S) where S: Into<String> {
+        self.push_segment(Some(style_name.to_string()), text.into());
+    }
+
+    /// Add a text without a style at the end. It is shown with the
+    /// default style of the Stylesheet.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to add.
+    pub fn push_plain<S>(&mut self, text: S) where S: Into<String> {
+        self.push_segment(None, text.into());
+    }
+
+    /// Add a segment, merging it with the last one if they have the same
+    /// style. Empty texts are not added.
+    fn push_segment(&mut self, style_name: Option<String>, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(last) = self.segments.last_mut() {
+            if last.0 == style_name {
+                last.1.push_str(&text);
+                return;
+            }
+        }
+        self.segments.push((style_name, text));
+    }
+
+    /// Return the segments, as (style name, text) pairs.
+    /// None is the default style.
+    pub fn segments(&self) -> &[(Option<String>, String)] {
+        &self.segments
+    }
+
+    /// Return an iterator over the segments. See segments().
+    pub fn iter(&self) -> slice::Iter<'_, (Option<String>, String)> {
+        self.segments.iter()
+    }
+
+    /// Return true if there are no texts.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Return the text, without styles.
+    pub fn plain(&self) -> String {
+        self.segments.iter().map(|(_, text)| text.as_str()).collect()
+    }
+
+    /// Return the text with the escape sequences of the styles.
+    /// Same as Stylesheet::format_styled().
+    ///
+    /// # Arguments
+    ///
+    /// * `sheet` - The Stylesheet containing the styles.
+    pub fn render(&self, sheet: &Stylesheet) -> String {
+        sheet.format_styled(self)
+    }
+}
+
+impl fmt::Display for StyledString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (_, text) in &self.segments {
+            f.write_str(text)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a StyledString {
+    type Item = &'a (Option<String>, String);
+    type IntoIter = slice::Iter<'a, (Option<String>, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl AddAssign<StyledString> for StyledString {
+    fn add_assign(&mut self, other: StyledString) {
+        for (style_name, text) in other.segments {
+            self.push_segment(style_name, text);
+        }
+    }
+}
+
+impl<'a> AddAssign<&'a str> for StyledString {
+    fn add_assign(&mut self, text: &'a str) {
+        self.push_plain(text);
+    }
+}
+
+impl Add<StyledString> for StyledString {
+    type Output = StyledString;
+
+    fn add(mut self, other: StyledString) -> StyledString {
+        self += other;
+        self
+    }
+}
+
+impl<'a> Add<&'a str> for StyledString {
+    type Output = StyledString;
+
+    fn add(mut self, text: &'a str) -> StyledString {
+        self += text;
+        self
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::{ColorMode, StyleColor, StyleProperties};
+
+    #[test]
+    fn concatenation() {
+        let mut line = StyledString::styled("danger", "Borg") + " cube" + StyledString::styled("danger", "s");
+        line += StyledString::styled("info", "!");
+        // empty texts are ignored, and consecutive texts with the same style are merged
+        line.push("info", "");
+        line.push("info", "!");
+        assert_eq!(line.segments(), &[
+            (Some("danger".to_string()), "Borg".to_string()),
+            (None, " cube".to_string()),
+            (Some("danger".to_string()), "s".to_string()),
+            (Some("info".to_string()), "!!".to_string()),
+        ]);
+        assert_eq!(line.plain(), "Borg cubes!!");
+        assert_eq!(line.to_string(), "Borg cubes!!");
+        assert_eq!(line.iter().count(), 4);
+    }
+
+    #[test]
+    fn render() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+        let line = StyledString::styled("danger", "down") + " now";
+        assert_eq!(line.render(&sheet), "\x1b[31mdown\x1b[0m now");
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(line.render(&sheet), "down now");
+    }
+}
@@ -1,11 +1,57 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+extern crate chrono;
+use self::chrono::format::{Item, StrftimeItems};
 
 extern crate console;
-use self::console::Style;
+
+use ansi;
+use columns;
+use diff;
+use heatmap::{self, Heatmap};
+use highlight::HighlightRules;
+use icons::Icon;
+use list::{self, List};
+use markdown;
+use markup;
+use markup::MarkupError;
+use progress;
+use styled_string::StyledString;
+use term;
+use terminal::{self, ColorLevel, TerminalCapabilities};
+use text;
+use tree::{self, TreeNode};
+
+extern crate serde;
+use self::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+extern crate serde_json;
+
+extern crate serde_yaml;
+
+extern crate textwrap;
+
+extern crate toml;
+use self::toml::Spanned;
 
 
 /// Transformations that can be applied to texts.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StyleTransformation {
     Blink,
     Bold,
@@ -13,10 +59,21 @@ pub enum StyleTransformation {
     Dim,
     Italic,
     Underlined,
+    /// A double underline. Terminals that don't support it show
+    /// a single underline.
+    DoubleUnderlined,
+    /// A wavy underline, like the ones of spell checkers. Terminals
+    /// that don't support it show a single underline.
+    CurlyUnderlined,
+    /// Swap text and background colors.
+    Reverse,
+    Strikethrough,
+    /// Don't show the text. Useful for redacted contents.
+    Hidden,
 }
 
 /// Colors that can be used for texts and/or their backgrounds.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StyleColor {
     DefaultColor,
     Black,
@@ -27,323 +84,6828 @@ pub enum StyleColor {
     Cyan,
     Magenta,
     Yellow,
+    BrightBlack,
+    BrightWhite,
+    BrightRed,
+    BrightGreen,
+    BrightBlue,
+    BrightCyan,
+    BrightMagenta,
+    BrightYellow,
+    /// Extended colors, shown as colors of the 256-color palette.
+    /// On terminals that only support 16 colors, the most similar
+    /// of them is used instead.
+    Orange,
+    Purple,
+    Pink,
+    Gray,
+    Teal,
+    Brown,
+    Olive,
+    Navy,
+    Maroon,
+    Gold,
+    /// A 24-bit color (red, green, blue). On terminals that don't support
+    /// truecolor, the most similar supported color is used instead.
+    Rgb(u8, u8, u8),
+    /// A color from the xterm 256-color palette. On terminals that only
+    /// support 16 colors, the most similar of them is used instead.
+    Fixed(u8),
+    /// A color defined by hue (0-359 degrees), saturation (0-100%) and
+    /// lightness (0-100%). Styles store it as an Rgb color, so it is
+    /// shown like Rgb colors.
+    Hsl(u16, u8, u8),
 }
 
-/// All properties that form a style.
-#[derive(Debug, Clone)]
-pub struct StyleProperties {
-    /// A vector of all the transformations to apply to texts.
-    /// Can be empty. Remember that it's a vector, not an array.
-    ///
-    /// # Examples
-    /// [].to_vec()
-    /// [StyleTransformation.Bold, StyleTransformation.Underlined].to_vec()
-    pub transformation: Vec<StyleTransformation>,
-
-    /// The color to apply to the text. The type is Option<StyleColor>.
-    /// None means default.
-    pub color: Option<StyleColor>,
-
-    /// The color to apply to the text background. The type is Option<StyleColor>.
-    /// None means default.
-    pub background: Option<StyleColor>,
+impl StyleTransformation {
+    /// The transformation names accepted in stylesheet files.
+    pub const NAMES: &'static [&'static str] = &[
+        "blink", "bold", "bright", "dim", "italic", "underlined", "double_underlined", "curly_underlined",
+        "reverse", "strikethrough", "hidden",
+    ];
 }
 
+impl FromStr for StyleTransformation {
+    type Err = ();
 
-/// Stylesheet struct creates and handles a stylesheet.
-/// A stylesheet is a library of named styles that can be applied to texts.
-/// A Stylesheet style will play in your code the same role that a named
-/// CSS style plays in an HTML document.
-pub struct Stylesheet {
-    styles: HashMap<&'static str, Style>,
-    is_frozen: bool,
+    /// Parse a transformation name, as written in stylesheet files.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "blink" => Ok(StyleTransformation::Blink),
+            "bold" => Ok(StyleTransformation::Bold),
+            "bright" => Ok(StyleTransformation::Bright),
+            "dim" => Ok(StyleTransformation::Dim),
+            "italic" => Ok(StyleTransformation::Italic),
+            "underlined" => Ok(StyleTransformation::Underlined),
+            "double_underlined" => Ok(StyleTransformation::DoubleUnderlined),
+            "curly_underlined" => Ok(StyleTransformation::CurlyUnderlined),
+            "reverse" => Ok(StyleTransformation::Reverse),
+            "strikethrough" => Ok(StyleTransformation::Strikethrough),
+            "hidden" => Ok(StyleTransformation::Hidden),
+            _ => Err(()),
+        }
+    }
 }
 
-impl Stylesheet {
-    const DEFAULT_STYLE: &'static str = "_default";
-
+impl StyleColor {
+    /// The color names accepted in stylesheet files.
+    /// Colors can also be written as "rgb(R, G, B)", "fixed(N)",
+    /// "hsl(H, S%, L%)", "#RRGGBB" or "#RGB".
+    pub const NAMES: &'static [&'static str] = &[
+        "default", "black", "white", "red", "green", "blue", "cyan", "magenta", "yellow",
+        "bright_black", "bright_white", "bright_red", "bright_green",
+        "bright_blue", "bright_cyan", "bright_magenta", "bright_yellow",
+        "orange", "purple", "pink", "gray", "teal", "brown", "olive", "navy", "maroon", "gold",
+    ];
 
-    /// Return a new stylesheet. It only contains DEFAULT_STYLE,
-    /// that can be used explicitally and is used implicitally when
-    /// we try to use a non-existing style.
-    pub fn new() -> Stylesheet {
-        let mut hash: HashMap<&str, Style> = HashMap::new();
-        hash.insert(Stylesheet::DEFAULT_STYLE, Style::new());
+    /// Parse a color written as "#RRGGBB" or "#RGB", with hexadecimal
+    /// digits, and return it as an Rgb color.
+    /// "#RGB" is the same as "#RRGGBB" with every digit repeated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::StyleColor;
+    /// assert_eq!(StyleColor::from_hex("#ff8800"), Some(StyleColor::Rgb(255, 136, 0)));
+    /// assert_eq!(StyleColor::from_hex("#f80"), Some(StyleColor::Rgb(255, 136, 0)));
+    /// ```
+    pub fn from_hex(text: &str) -> Option<StyleColor> {
+        let digits = text.strip_prefix('#')?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let component = |i: usize, len: usize| u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).ok();
+        match digits.len() {
+            6 => Some(StyleColor::Rgb(component(0, 2)?, component(1, 2)?, component(2, 2)?)),
+            3 => Some(StyleColor::Rgb(component(0, 1)? * 17, component(1, 1)? * 17, component(2, 1)? * 17)),
+            _ => None,
+        }
+    }
 
-        Stylesheet {
-            styles: hash,
-            is_frozen: false,
+    /// Return the same color as Rgb, if it is an Hsl color.
+    /// Other colors are returned as they are.
+    /// Values out of range are clamped: hues are taken modulo 360,
+    /// saturation and lightness can't exceed 100%.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::StyleColor;
+    /// assert_eq!(StyleColor::Hsl(120, 100, 50).to_rgb(), StyleColor::Rgb(0, 255, 0));
+    /// ```
+    pub fn to_rgb(&self) -> StyleColor {
+        match *self {
+            StyleColor::Hsl(h, s, l) => {
+                let (r, g, b) = hsl_to_rgb(h, s, l);
+                StyleColor::Rgb(r, g, b)
+            },
+            ref color => color.clone(),
         }
     }
 
-    #[cfg(test)]
-    fn len(&self) -> usize {
-        self.styles.len()
+    /// Return one of the 16 basic colors, by its index in the terminal
+    /// palette: 0-7 are the normal colors, in SGR order (black, red,
+    /// green, yellow, blue, magenta, cyan, white), 8-15 their bright
+    /// versions. Return None if index is greater than 15.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::StyleColor;
+    /// assert_eq!(StyleColor::basic(1), Some(StyleColor::Red));
+    /// assert_eq!(StyleColor::basic(9), Some(StyleColor::BrightRed));
+    /// ```
+    pub fn basic(index: u8) -> Option<StyleColor> {
+        BASIC_COLORS.get(index as usize).cloned()
     }
 
-    fn contains(&self, style_name: &str) -> bool {
-        self.styles.contains_key(style_name)
+    /// Return the RGB value of this color, or None for DefaultColor.
+    /// Basic colors have the values that xterm uses by default;
+    /// terminals can show them differently.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::StyleColor;
+    /// assert_eq!(StyleColor::Fixed(196).rgb_value(), Some((255, 0, 0)));
+    /// assert_eq!(StyleColor::DefaultColor.rgb_value(), None);
+    /// ```
+    pub fn rgb_value(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            StyleColor::DefaultColor => None,
+            StyleColor::Rgb(r, g, b) => Some((r, g, b)),
+            StyleColor::Hsl(h, s, l) => Some(hsl_to_rgb(h, s, l)),
+            StyleColor::Fixed(index) => Some(fixed_to_rgb(index)),
+            ref color => match color.extended_index() {
+                Some(index) => Some(fixed_to_rgb(index)),
+                None => BASIC_COLORS.iter().position(|basic| basic == color).map(|index| BASIC_COLORS_RGB[index]),
+            },
+        }
     }
 
-    /// Add a style to an existing stylesheet.
+    /// Return an Rgb color between a and b. t is the position of the
+    /// result: 0.0 returns a, 1.0 returns b, 0.5 returns the color in
+    /// the middle. t is clamped to 0.0 - 1.0.
+    /// DefaultColor can't be mixed: if a or b is DefaultColor, the
+    /// closest of them is returned.
     ///
     /// # Arguments
     ///
-    /// * `style_name` - The name of the style to be added to the stylesheet (&str).
-    /// * `style_definition` - The definition of the style to be added to the stylesheet (StyleProperties).
+    /// * `a` - The color at position 0.0.
+    /// * `b` - The color at position 1.0.
+    /// * `t` - The position of the returned color.
     ///
     /// # Example
     ///
     /// ```
-    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor;
     /// use common::stylesheet::StyleColor::*;
-    /// use common::stylesheet::StyleTransformation::*;
-    /// let mut sheet = Stylesheet::new();
-    /// sheet.add_style("danger", StyleProperties {
-    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White)
-    /// });
+    /// assert_eq!(StyleColor::blend(&Rgb(0, 0, 0), &Rgb(200, 100, 50), 0.5), Rgb(100, 50, 25));
     /// ```
-    pub fn add_style(
-            &mut self,
-            style_name: &'static str,
-            style_definition: StyleProperties,
-        ) {
-        if self.is_frozen {
-            panic!("FATAL: Trying to add a style to a frozen Stylesheet");
-        }
+    pub fn blend(a: &StyleColor, b: &StyleColor, t: f64) -> StyleColor {
+        let t = t.clamp(0.0, 1.0);
+        let (from, to) = match (a.rgb_value(), b.rgb_value()) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return if t < 0.5 { a.clone() } else { b.clone() },
+        };
+        let mix = |from: u8, to: u8| (f64::from(from) + (f64::from(to) - f64::from(from)) * t).round() as u8;
+        StyleColor::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+    }
 
-        // style is a handler from console::Style.
-        // Based on the contents of style_definition call style functions
-        // to create a proper style.
-        let mut style: Style = Style::new();
-        // apply all specified transformations, if any
-        for s in &style_definition.transformation {
-            match s {
-                StyleTransformation::Blink => style = style.blink(),
-                StyleTransformation::Bold => style = style.bold(),
-                StyleTransformation::Bright => style = style.bright(),
-                StyleTransformation::Dim => style = style.dim(),
-                StyleTransformation::Italic => style = style.italic(),
-                StyleTransformation::Underlined => style = style.underlined(),
-            }
-        }
-        // apply specified text color, unless it is None
-        if style_definition.color.is_some() {
-            let color = &style_definition.color.unwrap();
-            match color {
-                StyleColor::DefaultColor => (),
-                StyleColor::Black => style = style.black(),
-                StyleColor::White => style = style.white(),
-                StyleColor::Red => style = style.red(),
-                StyleColor::Green => style = style.green(),
-                StyleColor::Blue => style = style.blue(),
-                StyleColor::Cyan => style = style.cyan(),
-                StyleColor::Magenta => style = style.magenta(),
-                StyleColor::Yellow => style = style.yellow(),
-            }
-        }
-        // apply specified background color, unless it is None
-        if style_definition.background.is_some() {
-            let color = &style_definition.background.unwrap();
-            match color {
-                StyleColor::DefaultColor => (),
-                StyleColor::Black => style = style.on_black(),
-                StyleColor::White => style = style.on_white(),
-                StyleColor::Red => style = style.on_red(),
-                StyleColor::Green => style = style.on_green(),
-                StyleColor::Blue => style = style.on_blue(),
-                StyleColor::Cyan => style = style.on_cyan(),
-                StyleColor::Magenta => style = style.on_magenta(),
-                StyleColor::Yellow => style = style.on_yellow(),
-            }
+    /// Return the color at position t of a ramp that goes through
+    /// all the stops, evenly spaced. t is clamped to 0.0 - 1.0.
+    /// This maps a value, like a percentage, to a color.
+    /// With no stops, return DefaultColor.
+    ///
+    /// # Arguments
+    ///
+    /// * `stops` - The colors of the ramp, from position 0.0 to 1.0.
+    /// * `t` - The position of the returned color.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::StyleColor;
+    /// use common::stylesheet::StyleColor::*;
+    /// let stops = [Rgb(0, 255, 0), Rgb(255, 255, 0), Rgb(255, 0, 0)];
+    /// let threat = 75;
+    /// assert_eq!(StyleColor::ramp(&stops, f64::from(threat) / 100.0), Rgb(255, 128, 0));
+    /// ```
+    pub fn ramp(stops: &[StyleColor], t: f64) -> StyleColor {
+        match stops.len() {
+            0 => return StyleColor::DefaultColor,
+            1 => return stops[0].clone(),
+            _ => (),
         }
-        //println!("{:?}", style);
-
-        self.styles.insert(style_name, style);
+        let position = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (position.floor() as usize).min(stops.len() - 2);
+        StyleColor::blend(&stops[i], &stops[i + 1], position - i as f64)
     }
 
-    /// Freeze the Stylesheet. It will not be possible to modify it again.
-    /// However, trying to freeze it will not cause any complain.
+    /// Return count colors from a ramp that goes through all the stops:
+    /// the first and the last colors are the first and last stops.
+    /// See ramp().
+    ///
+    /// # Arguments
+    ///
+    /// * `stops` - The colors of the ramp.
+    /// * `count` - The number of colors to return.
     ///
     /// # Example
     ///
     /// ```
-    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor;
     /// use common::stylesheet::StyleColor::*;
-    /// use common::stylesheet::StyleTransformation::*;
-    /// let mut sheet = Stylesheet::new();
-    /// sheet.add_style("danger", StyleProperties {
-    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White)
-    /// });
-    /// sheet.freeze();
+    /// let palette = StyleColor::palette(&[Rgb(0, 0, 0), Rgb(255, 255, 255)], 3);
+    /// assert_eq!(palette, vec![Rgb(0, 0, 0), Rgb(128, 128, 128), Rgb(255, 255, 255)]);
     /// ```
-    pub fn freeze(&mut self) {
-        self.is_frozen = true;
+    pub fn palette(stops: &[StyleColor], count: usize) -> Vec<StyleColor> {
+        let last = count.max(2) - 1;
+        (0..count).map(|i| StyleColor::ramp(stops, i as f64 / last as f64)).collect()
     }
 
-    /// Print a line (string reference), applying to it a single style.
+    /// Return the most similar color that can be shown on terminals
+    /// with the given color depth: a Fixed color for Ansi256, and one
+    /// of the 16 basic colors for Basic. Colors supported by depth are
+    /// returned as they are, except that Hsl colors become Rgb and the
+    /// extended named colors (Orange, Purple...) become Fixed.
     ///
     /// # Arguments
     ///
-    /// * `style_name` - The name of the style to use (&str).
-    /// * `message` - The string struct or string reference to print.
+    /// * `depth` - The color level supported by the terminal.
     ///
     /// # Example
     ///
     /// ```
-    /// use common::stylesheet::*;
-    /// use common::stylesheet::StyleColor::*;
-    /// use common::stylesheet::StyleTransformation::*;
-    /// let mut sheet = Stylesheet::new();
-    /// sheet.add_style("danger", StyleProperties {
-    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White)
-    /// });
-    /// sheet.println("danger", "Some text");
+    /// use common::stylesheet::StyleColor;
+    /// use common::terminal::ColorLevel;
+    /// assert_eq!(StyleColor::Rgb(255, 0, 0).downsample(ColorLevel::Ansi256), StyleColor::Fixed(196));
+    /// assert_eq!(StyleColor::Rgb(255, 0, 0).downsample(ColorLevel::Basic), StyleColor::BrightRed);
     /// ```
-    pub fn println<S>(
-            &self,
-            mut style_name: &str,
-            message: S,
-        ) where S: AsRef<str> {
-        // if the requested style doesn't exist we fall back to default
-        if !self.contains(style_name) {
-            style_name = Stylesheet::DEFAULT_STYLE;
+    pub fn downsample(&self, depth: ColorLevel) -> StyleColor {
+        let (rgb, needed) = match *self {
+            StyleColor::Rgb(r, g, b) => ((r, g, b), ColorLevel::TrueColor),
+            StyleColor::Hsl(h, s, l) => (hsl_to_rgb(h, s, l), ColorLevel::TrueColor),
+            StyleColor::Fixed(index) => (fixed_to_rgb(index), ColorLevel::Ansi256),
+            ref color => match color.extended_index() {
+                Some(index) => return StyleColor::Fixed(index).downsample(depth),
+                None => return color.clone(),
+            },
+        };
+        if depth >= needed {
+            return self.to_rgb();
+        }
+        match depth {
+            ColorLevel::Basic => BASIC_COLORS[nearest_basic_color(rgb) as usize].clone(),
+            _ => StyleColor::Fixed(nearest_fixed_color(rgb)),
         }
-        let style = self.styles.get(style_name).unwrap();
-        println!("{}", style.apply_to(message.as_ref()));
     }
 
-    /// Similar to println(), but print() doesn't append a newline character.
+    /// Return the index in the 256-color palette of an extended named color.
+    fn extended_index(&self) -> Option<u8> {
+        match self {
+            StyleColor::Orange => Some(208),
+            StyleColor::Purple => Some(91),
+            StyleColor::Pink => Some(218),
+            StyleColor::Gray => Some(244),
+            StyleColor::Teal => Some(30),
+            StyleColor::Brown => Some(130),
+            StyleColor::Olive => Some(100),
+            StyleColor::Navy => Some(18),
+            StyleColor::Maroon => Some(88),
+            StyleColor::Gold => Some(220),
+            _ => None,
+        }
+    }
+
+    /// Return the SGR parameters that select this color for underlines.
+    /// There are no codes for the 16 basic colors, so they are selected
+    /// from the 256-color palette.
+    fn underline_sgr_codes(&self, depth: ColorLevel) -> Vec<String> {
+        let color = self.downsample(depth);
+        match color {
+            StyleColor::Rgb(r, g, b) => vec![format!("58;2;{};{};{}", r, g, b)],
+            StyleColor::Fixed(index) => vec![format!("58;5;{}", index)],
+            _ => match BASIC_COLORS.iter().position(|basic| *basic == color) {
+                Some(index) => vec![format!("58;5;{}", index)],
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Return the SGR parameters that select this color, to be joined
+    /// with ";". Colors that need more colors than depth are replaced by
+    /// the most similar supported color.
     ///
     /// # Arguments
     ///
-    /// * `style_name` - The name of the style to use (&str).
-    /// * `message` - The string struct or string reference to print.
+    /// * `background` - true to select the background color.
+    /// * `bright` - true to select the bright version of the 8 basic colors.
+    /// * `depth` - The colors supported by the terminal. See
+    ///   Stylesheet::color_depth().
     ///
     /// # Example
     ///
     /// ```
-    /// use common::stylesheet::*;
-    /// use common::stylesheet::StyleColor::*;
-    /// use common::stylesheet::StyleTransformation::*;
-    /// let mut sheet = Stylesheet::new();
-    /// sheet.add_style("danger", StyleProperties {
-    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White)
-    /// });
-    /// sheet.print("danger", "Some text");
+    /// use common::stylesheet::StyleColor;
+    /// use common::terminal::ColorLevel;
+    /// assert_eq!(StyleColor::Red.sgr_codes(true, false, ColorLevel::Basic), vec!["41"]);
     /// ```
-    pub fn print<S>(
-            &self,
-            mut style_name: &str,
-            message: S,
-        ) where S: AsRef<str> {
-        // if the requested style doesn't exist we fall back to default
-        if !self.contains(style_name) {
-            style_name = Stylesheet::DEFAULT_STYLE;
+    pub fn sgr_codes(&self, background: bool, bright: bool, depth: ColorLevel) -> Vec<String> {
+        let (normal, light, extended) = if background { (40, 100, 48) } else { (30, 90, 38) };
+        let color = self.downsample(depth);
+        let index = match color {
+            StyleColor::Rgb(r, g, b) => return vec![format!("{};2;{};{};{}", extended, r, g, b)],
+            StyleColor::Fixed(index) => return vec![format!("{};5;{}", extended, index)],
+            // only DefaultColor is not one of the basic colors
+            _ => match BASIC_COLORS.iter().position(|basic| *basic == color) {
+                Some(index) => index as u8,
+                None => return Vec::new(),
+            },
+        };
+        let index = if bright && index < 8 { index + 8 } else { index };
+        if index < 8 {
+            vec![(normal + index).to_string()]
+        } else {
+            vec![(light + index - 8).to_string()]
         }
-        let style = self.styles.get(style_name).unwrap();
-        println!("{}", style.apply_to(message.as_ref()));
     }
 }
 
+/// RGB values of the 16 basic colors, as xterm shows them by default.
+/// The first 8 are the normal colors, in SGR order; the other 8 are their
+/// bright versions.
+const BASIC_COLORS_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0), (205, 0, 0), (0, 205, 0), (205, 205, 0),
+    (0, 0, 238), (205, 0, 205), (0, 205, 205), (229, 229, 229),
+    (127, 127, 127), (255, 0, 0), (0, 255, 0), (255, 255, 0),
+    (92, 92, 255), (255, 0, 255), (0, 255, 255), (255, 255, 255),
+];
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use super::StyleColor::*;
-    use super::StyleTransformation::*;
+/// The 16 basic colors, in the same order as BASIC_COLORS_RGB.
+const BASIC_COLORS: [StyleColor; 16] = [
+    StyleColor::Black, StyleColor::Red, StyleColor::Green, StyleColor::Yellow,
+    StyleColor::Blue, StyleColor::Magenta, StyleColor::Cyan, StyleColor::White,
+    StyleColor::BrightBlack, StyleColor::BrightRed, StyleColor::BrightGreen, StyleColor::BrightYellow,
+    StyleColor::BrightBlue, StyleColor::BrightMagenta, StyleColor::BrightCyan, StyleColor::BrightWhite,
+];
+
+/// Return the RGB value of a color from the xterm 256-color palette.
+fn fixed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC_COLORS_RGB[index as usize],
+        // 6x6x6 color cube
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + 40 * value };
+            let index = index - 16;
+            (level(index / 36), level((index / 6) % 6), level(index % 6))
+        },
+        // grayscale ramp
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        },
+    }
+}
 
+/// Convert a color from HSL to RGB.
+fn hsl_to_rgb(hue: u16, saturation: u8, lightness: u8) -> (u8, u8, u8) {
+    let h = f64::from(hue % 360) / 60.0;
+    let s = f64::from(saturation.min(100)) / 100.0;
+    let l = f64::from(lightness.min(100)) / 100.0;
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = l - chroma / 2.0;
+    let component = |value: f64| ((value + m) * 255.0).round() as u8;
+    (component(r), component(g), component(b))
+}
 
-    #[test]
-    fn create_stylesheet() {
-        let sheet: Stylesheet = Stylesheet::new();
-        // must be a HashMap of Style's and only contain DEFAULT_STYLE key
-        assert!(sheet.contains(Stylesheet::DEFAULT_STYLE));
-        assert_eq!(sheet.len(), 1);
+/// Return the index (16-255) of the color of the 256-color palette
+/// that is closest to rgb. The first 16 colors are not considered,
+/// because terminals often redefine them.
+fn nearest_fixed_color(rgb: (u8, u8, u8)) -> u8 {
+    (16..=255).min_by_key(|index| color_distance(rgb, fixed_to_rgb(*index))).unwrap()
+}
+
+/// Return the squared distance between two RGB colors.
+fn color_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = i32::from(a.0) - i32::from(b.0);
+    let dg = i32::from(a.1) - i32::from(b.1);
+    let db = i32::from(a.2) - i32::from(b.2);
+    dr * dr + dg * dg + db * db
+}
+
+/// Return the index (0-15) of the basic color that is closest to rgb.
+fn nearest_basic_color(rgb: (u8, u8, u8)) -> u8 {
+    let distance = |other: &(u8, u8, u8)| color_distance(rgb, *other);
+    let mut nearest = 0;
+    for (i, color) in BASIC_COLORS_RGB.iter().enumerate() {
+        if distance(color) < distance(&BASIC_COLORS_RGB[nearest]) {
+            nearest = i;
+        }
     }
+    nearest as u8
+}
 
-    #[test]
-    fn add_empty_style() {
-        let mut sheet = Stylesheet::new();
-        // must be able to retrieve an empty style and retrieve it
-        // there are more ways to achieve this
+/// Return true if the standard output is a terminal. If it is redirected
+/// to a file or piped into another program, styles should not be used.
+pub fn stdout_is_tty() -> bool {
+    console::Term::stdout().is_term()
+}
 
-        let style_name = "empty_guy";
-        sheet.add_style(style_name,
-            StyleProperties { transformation: [].to_vec(), color: None, background: None, }
-        );
-        assert!(sheet.contains(style_name));
+/// Return true if the standard error is a terminal.
+pub fn stderr_is_tty() -> bool {
+    console::Term::stderr().is_term()
+}
 
-        let style_name = "empty_lady";
-        sheet.add_style(style_name,
-            StyleProperties { transformation: [].to_vec(), color: Some(DefaultColor), background: Some(DefaultColor), }
-        );
-        assert!(sheet.contains(style_name));
+/// The exit codes of the shell when the pager command is not found or
+/// can't be executed.
+const PAGER_NOT_FOUND_CODES: [i32; 3] = [126, 127, 9009];
+
+/// Return the pager command and its arguments, given the value of the
+/// PAGER environment variable. Return None if no pager must be used.
+/// Like git, the value is run by the shell, so it can contain quotes
+/// and other shell syntax.
+fn pager_command(pager: Option<String>) -> Option<Vec<String>> {
+    let pager = pager.unwrap_or_else(|| "less -R".to_string());
+    let pager = pager.trim();
+    if pager.is_empty() || pager == "cat" {
+        return None;
     }
+    let shell: [&str; 2] = if cfg!(windows) { ["cmd", "/C"] } else { ["sh", "-c"] };
+    Some(vec![shell[0].to_string(), shell[1].to_string(), pager.to_string()])
+}
 
-    #[test]
-    fn add_style() {
-        let mut sheet = Stylesheet::new();
-        // must be able to retrieve a regular style and retrieve it
-        let style_name = "new_guy";
-        sheet.add_style(
-            style_name,
-            StyleProperties {
-                transformation: [ Bold, Underlined, ].to_vec(),
-                color: Some(Blue),
-                background: Some(White),
-            }
-        );
-        assert!(sheet.contains(style_name));
+impl FromStr for StyleColor {
+    type Err = ();
+
+    /// Parse a color name, as written in stylesheet files.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "default" => Ok(StyleColor::DefaultColor),
+            "black" => Ok(StyleColor::Black),
+            "white" => Ok(StyleColor::White),
+            "red" => Ok(StyleColor::Red),
+            "green" => Ok(StyleColor::Green),
+            "blue" => Ok(StyleColor::Blue),
+            "cyan" => Ok(StyleColor::Cyan),
+            "magenta" => Ok(StyleColor::Magenta),
+            "yellow" => Ok(StyleColor::Yellow),
+            "bright_black" => Ok(StyleColor::BrightBlack),
+            "bright_white" => Ok(StyleColor::BrightWhite),
+            "bright_red" => Ok(StyleColor::BrightRed),
+            "bright_green" => Ok(StyleColor::BrightGreen),
+            "bright_blue" => Ok(StyleColor::BrightBlue),
+            "bright_cyan" => Ok(StyleColor::BrightCyan),
+            "bright_magenta" => Ok(StyleColor::BrightMagenta),
+            "bright_yellow" => Ok(StyleColor::BrightYellow),
+            "orange" => Ok(StyleColor::Orange),
+            "purple" => Ok(StyleColor::Purple),
+            "pink" => Ok(StyleColor::Pink),
+            "gray" => Ok(StyleColor::Gray),
+            "teal" => Ok(StyleColor::Teal),
+            "brown" => Ok(StyleColor::Brown),
+            "olive" => Ok(StyleColor::Olive),
+            "navy" => Ok(StyleColor::Navy),
+            "maroon" => Ok(StyleColor::Maroon),
+            "gold" => Ok(StyleColor::Gold),
+            _ => parse_rgb(name)
+                .or_else(|| parse_fixed(name))
+                .or_else(|| parse_hsl(name))
+                .or_else(|| StyleColor::from_hex(name))
+                .ok_or(()),
+        }
     }
+}
 
-    #[test]
-    fn freeze() {
-        let mut sheet = Stylesheet::new();
-        // should be able to freeze (become immutable)
-        sheet.freeze();
-        // did not panick
-        assert!(true);
+/// Parse a color written as "rgb(R, G, B)", where every component is 0-255.
+fn parse_rgb(text: &str) -> Option<StyleColor> {
+    let components = text.strip_prefix("rgb(")?.strip_suffix(')')?;
+    let components: Vec<u8> = components.split(',')
+        .map(|component| component.trim().parse())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match components[..] {
+        [r, g, b] => Some(StyleColor::Rgb(r, g, b)),
+        _ => None,
     }
+}
 
-    #[test]
-    fn freeze_twice() {
-        let mut sheet = Stylesheet::new();
-        // should be able to freeze (become immutable)
-        sheet.freeze();
-        sheet.freeze();
-        // did not panick
-        assert!(true);
+/// Parse a color written as "fixed(N)", where N is 0-255.
+fn parse_fixed(text: &str) -> Option<StyleColor> {
+    let index = text.strip_prefix("fixed(")?.strip_suffix(')')?;
+    index.trim().parse().ok().map(StyleColor::Fixed)
+}
+
+/// Parse a color written as "hsl(H, S%, L%)", where H is 0-359
+/// and S and L are 0-100. The '%' signs are optional.
+fn parse_hsl(text: &str) -> Option<StyleColor> {
+    let components = text.strip_prefix("hsl(")?.strip_suffix(')')?;
+    let components: Vec<u16> = components.split(',')
+        .map(|component| component.trim().trim_end_matches('%').parse())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match components[..] {
+        [h, s, l] if h < 360 && s <= 100 && l <= 100 => Some(StyleColor::Hsl(h, s as u8, l as u8)),
+        _ => None,
     }
+}
 
-    #[test]
-    #[should_panic(expected = "FATAL:")]
-    fn freeze_and_try_to_modify() {
-        let mut sheet = Stylesheet::new();
-        sheet.freeze();
-        sheet.add_style(
-            "test",
-            StyleProperties { transformation: [].to_vec(), color: None, background: None, }
-        );
+/// Return the color values accepted in stylesheet files, to report errors.
+fn allowed_colors() -> Vec<String> {
+    let mut allowed: Vec<String> = StyleColor::NAMES.iter().map(|name| name.to_string()).collect();
+    allowed.push("rgb(R, G, B)".to_string());
+    allowed.push("fixed(N)".to_string());
+    allowed.push("hsl(H, S%, L%)".to_string());
+    allowed.push("#RRGGBB".to_string());
+    allowed.push("#RGB".to_string());
+    allowed
+}
+
+impl fmt::Display for StyleTransformation {
+    /// Write the transformation name, as written in stylesheet files.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StyleTransformation::Blink => "blink",
+            StyleTransformation::Bold => "bold",
+            StyleTransformation::Bright => "bright",
+            StyleTransformation::Dim => "dim",
+            StyleTransformation::Italic => "italic",
+            StyleTransformation::Underlined => "underlined",
+            StyleTransformation::DoubleUnderlined => "double_underlined",
+            StyleTransformation::CurlyUnderlined => "curly_underlined",
+            StyleTransformation::Reverse => "reverse",
+            StyleTransformation::Strikethrough => "strikethrough",
+            StyleTransformation::Hidden => "hidden",
+        };
+        f.write_str(name)
     }
+}
 
-    #[test]
-    fn println() {
-        let sheet = Stylesheet::new();
-        // must be able to println a &str and a String without panicking
-        sheet.println(Stylesheet::DEFAULT_STYLE, "Just a test String reference");
-        sheet.println(Stylesheet::DEFAULT_STYLE, "A cool String struct");
-        // did not panick
-        assert!(true);
+/// Transformations are serialized by name, as in stylesheet files.
+impl Serialize for StyleTransformation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
+}
 
-    #[test]
-    fn print() {
-        let sheet = Stylesheet::new();
-        // must be able to print a &str and a String without panicking
-        sheet.print(Stylesheet::DEFAULT_STYLE, "A ");
-        sheet.print(Stylesheet::DEFAULT_STYLE, "B");
-        // did not panick
-        assert!(true);
+impl<'de> Deserialize<'de> for StyleTransformation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&name), &"a transformation name"))
+    }
+}
+
+/// Colors are serialized by name, as in stylesheet files.
+impl Serialize for StyleColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for StyleColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| {
+            de::Error::invalid_value(de::Unexpected::Str(&name), &"a color name, rgb(R, G, B), fixed(N), hsl(H, S%, L%), #RRGGBB or #RGB")
+        })
+    }
+}
+
+impl fmt::Display for StyleColor {
+    /// Write the color name, as written in stylesheet files.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            StyleColor::DefaultColor => "default",
+            StyleColor::Black => "black",
+            StyleColor::White => "white",
+            StyleColor::Red => "red",
+            StyleColor::Green => "green",
+            StyleColor::Blue => "blue",
+            StyleColor::Cyan => "cyan",
+            StyleColor::Magenta => "magenta",
+            StyleColor::Yellow => "yellow",
+            StyleColor::BrightBlack => "bright_black",
+            StyleColor::BrightWhite => "bright_white",
+            StyleColor::BrightRed => "bright_red",
+            StyleColor::BrightGreen => "bright_green",
+            StyleColor::BrightBlue => "bright_blue",
+            StyleColor::BrightCyan => "bright_cyan",
+            StyleColor::BrightMagenta => "bright_magenta",
+            StyleColor::BrightYellow => "bright_yellow",
+            StyleColor::Orange => "orange",
+            StyleColor::Purple => "purple",
+            StyleColor::Pink => "pink",
+            StyleColor::Gray => "gray",
+            StyleColor::Teal => "teal",
+            StyleColor::Brown => "brown",
+            StyleColor::Olive => "olive",
+            StyleColor::Navy => "navy",
+            StyleColor::Maroon => "maroon",
+            StyleColor::Gold => "gold",
+            StyleColor::Rgb(r, g, b) => return write!(f, "rgb({}, {}, {})", r, g, b),
+            StyleColor::Fixed(index) => return write!(f, "fixed({})", index),
+            StyleColor::Hsl(h, s, l) => return write!(f, "hsl({}, {}%, {}%)", h, s, l),
+        };
+        f.write_str(name)
+    }
+}
+
+/// When a Stylesheet should print texts with their styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorMode {
+    /// Always print escape codes, even if output is piped.
+    Always,
+    /// Print escape codes if stdout is a terminal that interprets them
+    /// and NO_COLOR is not set.
+    #[default]
+    Auto,
+    /// Never print escape codes.
+    Never,
+}
+
+impl ColorMode {
+    /// Names accepted by from_str(), for example as values of a --color flag.
+    pub const NAMES: [&'static str; 3] = ["always", "auto", "never"];
+}
+
+impl FromStr for ColorMode {
+    type Err = ();
+
+    /// Parse a color mode, as written in a --color flag.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "always" => Ok(ColorMode::Always),
+            "auto" => Ok(ColorMode::Auto),
+            "never" => Ok(ColorMode::Never),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColorMode::Always => "always",
+            ColorMode::Auto => "auto",
+            ColorMode::Never => "never",
+        };
+        f.write_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&name), &"always, auto or never"))
+    }
+}
+
+/// Why texts are printed with or without styles, as returned by
+/// Stylesheet::color_decision(). The variants are in order of
+/// precedence: the first one that applies decides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDecision {
+    /// Plain mode is enabled: no styles.
+    Plain,
+    /// ColorMode is Always: styles.
+    Always,
+    /// ColorMode is Never: no styles.
+    Never,
+    /// CLICOLOR_FORCE is set to a value other than "0": styles,
+    /// even if the output is not a terminal.
+    CliColorForce,
+    /// NO_COLOR is set to a non-empty value: no styles.
+    NoColor,
+    /// CLICOLOR is "0": no styles.
+    CliColorDisabled,
+    /// The output is a terminal that interprets escape sequences, or
+    /// a writer set with set_writer_keeping_ansi(): styles.
+    Terminal,
+    /// The output is not a terminal, or it doesn't interpret escape
+    /// sequences: no styles.
+    NotTerminal,
+}
+
+impl ColorDecision {
+    /// Return true if texts are printed with their styles.
+    pub fn colors_enabled(self) -> bool {
+        matches!(self, ColorDecision::Always | ColorDecision::CliColorForce | ColorDecision::Terminal)
+    }
+}
+
+/// All properties that form a style.
+/// They can be written as a struct literal, or built with builder().
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct StyleProperties {
+    /// A vector of all the transformations to apply to texts.
+    /// Can be empty. Remember that it's a vector, not an array.
+    ///
+    /// # Examples
+    /// [].to_vec()
+    /// [StyleTransformation.Bold, StyleTransformation.Underlined].to_vec()
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transformation: Vec<StyleTransformation>,
+
+    /// The color to apply to the text. The type is Option<StyleColor>.
+    /// None means default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<StyleColor>,
+
+    /// The color to apply to the text background. The type is Option<StyleColor>.
+    /// None means default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<StyleColor>,
+
+    /// The color of the underline, if the text is underlined. Some
+    /// terminals ignore it. None means the text color.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub underline_color: Option<StyleColor>,
+}
+
+impl StyleProperties {
+    /// Return a builder, to create StyleProperties with a fluent syntax.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().bold().blink().color(Red).background(White).build());
+    /// ```
+    pub fn builder() -> StylePropertiesBuilder {
+        StylePropertiesBuilder { properties: StyleProperties::default() }
+    }
+
+    /// Return new properties combining these properties with other.
+    /// Transformations are added; colors of other replace these ones,
+    /// unless they are None. This is the same logic used
+    /// by styles extending other styles.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The properties to apply over these ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let bold = StyleProperties::builder().bold().build();
+    /// let red = StyleProperties::builder().color(Red).build();
+    /// let bold_red = bold.merge(&red);
+    /// assert_eq!(bold_red.color, Some(Red));
+    /// ```
+    pub fn merge(&self, other: &StyleProperties) -> StyleProperties {
+        let mut merged = self.clone();
+        for transformation in &other.transformation {
+            if !merged.transformation.contains(transformation) {
+                merged.transformation.push(transformation.clone());
+            }
+        }
+        if other.color.is_some() {
+            merged.color = other.color.clone();
+        }
+        if other.background.is_some() {
+            merged.background = other.background.clone();
+        }
+        if other.underline_color.is_some() {
+            merged.underline_color = other.underline_color.clone();
+        }
+        merged
+    }
+
+    /// Return the same properties, with Hsl colors converted to Rgb.
+    /// Styles are stored this way.
+    fn with_rgb_colors(mut self) -> StyleProperties {
+        self.color = self.color.map(|color| color.to_rgb());
+        self.background = self.background.map(|color| color.to_rgb());
+        self.underline_color = self.underline_color.map(|color| color.to_rgb());
+        self
+    }
+
+    /// Return an error if some properties can't be used together.
+    fn check_conflicts(&self) -> Result<(), StylesheetError> {
+        if self.transformation.contains(&StyleTransformation::Bright)
+                && self.transformation.contains(&StyleTransformation::Dim) {
+            return Err(StylesheetError::ConflictingProperties("bright and dim can't be used together".to_string()));
+        }
+        if let (Some(color), Some(background)) = (&self.color, &self.background) {
+            if color == background && *color != StyleColor::DefaultColor {
+                return Err(StylesheetError::ConflictingProperties(
+                    format!("text and background have the same color: {}", color)
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Builds StyleProperties. Returned by StyleProperties::builder().
+#[derive(Debug, Clone)]
+pub struct StylePropertiesBuilder {
+    properties: StyleProperties,
+}
+
+impl StylePropertiesBuilder {
+    /// Add a transformation. Adding the same transformation twice has no effect.
+    pub fn transformation(mut self, transformation: StyleTransformation) -> Self {
+        if !self.properties.transformation.contains(&transformation) {
+            self.properties.transformation.push(transformation);
+        }
+        self
+    }
+
+    /// Same as transformation(StyleTransformation::Blink).
+    pub fn blink(self) -> Self {
+        self.transformation(StyleTransformation::Blink)
+    }
+
+    /// Same as transformation(StyleTransformation::Bold).
+    pub fn bold(self) -> Self {
+        self.transformation(StyleTransformation::Bold)
+    }
+
+    /// Same as transformation(StyleTransformation::Bright).
+    pub fn bright(self) -> Self {
+        self.transformation(StyleTransformation::Bright)
+    }
+
+    /// Same as transformation(StyleTransformation::Dim).
+    pub fn dim(self) -> Self {
+        self.transformation(StyleTransformation::Dim)
+    }
+
+    /// Same as transformation(StyleTransformation::Italic).
+    pub fn italic(self) -> Self {
+        self.transformation(StyleTransformation::Italic)
+    }
+
+    /// Same as transformation(StyleTransformation::Underlined).
+    pub fn underlined(self) -> Self {
+        self.transformation(StyleTransformation::Underlined)
+    }
+
+    /// Same as transformation(StyleTransformation::Reverse).
+    pub fn reverse(self) -> Self {
+        self.transformation(StyleTransformation::Reverse)
+    }
+
+    /// Same as transformation(StyleTransformation::DoubleUnderlined).
+    pub fn double_underlined(self) -> Self {
+        self.transformation(StyleTransformation::DoubleUnderlined)
+    }
+
+    /// Same as transformation(StyleTransformation::CurlyUnderlined).
+    pub fn curly_underlined(self) -> Self {
+        self.transformation(StyleTransformation::CurlyUnderlined)
+    }
+
+    /// Same as transformation(StyleTransformation::Strikethrough).
+    pub fn strikethrough(self) -> Self {
+        self.transformation(StyleTransformation::Strikethrough)
+    }
+
+    /// Same as transformation(StyleTransformation::Hidden).
+    pub fn hidden(self) -> Self {
+        self.transformation(StyleTransformation::Hidden)
+    }
+
+    /// Set the text color.
+    pub fn color(mut self, color: StyleColor) -> Self {
+        self.properties.color = Some(color);
+        self
+    }
+
+    /// Set the background color.
+    pub fn background(mut self, color: StyleColor) -> Self {
+        self.properties.background = Some(color);
+        self
+    }
+
+    /// Set the underline color. It is only visible if the text is underlined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("typo", StyleProperties::builder().curly_underlined().underline_color(Red).build());
+    /// println!("Engage the {}", sheet.style("typo", "wrap drive"));
+    /// ```
+    pub fn underline_color(mut self, color: StyleColor) -> Self {
+        self.properties.underline_color = Some(color);
+        self
+    }
+
+    /// Return the StyleProperties, or an error if some of the
+    /// specified options conflict with each other:
+    /// bright and dim can't be used together, and the text can't
+    /// have the same color as the background.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let result = StyleProperties::builder().color(Red).background(Red).try_build();
+    /// assert!(result.is_err());
+    /// ```
+    pub fn try_build(self) -> Result<StyleProperties, StylesheetError> {
+        self.properties.check_conflicts()?;
+        Ok(self.properties)
+    }
+
+    /// Same as try_build(), but it panics if some options conflict.
+    pub fn build(self) -> StyleProperties {
+        match self.try_build() {
+            Ok(properties) => properties,
+            Err(err) => panic!("FATAL: {}", err),
+        }
+    }
+}
+
+
+/// Errors that can occur while building a Stylesheet.
+#[derive(Debug)]
+pub enum StylesheetError {
+    /// The Stylesheet is frozen, so it can't be modified.
+    Frozen,
+    /// The requested style doesn't exist.
+    UnknownStyle(String),
+    /// The default style can't be removed, because it is used as a fallback.
+    DefaultStyle,
+    /// A style extends itself, directly or through other styles.
+    /// Contains the style names that form the cycle.
+    InheritanceCycle(Vec<String>),
+    /// Some style properties can't be used together.
+    ConflictingProperties(String),
+    /// A timestamp format contains an unknown specifier.
+    InvalidTimestampFormat(String),
+    /// A style with the same name already exists.
+    DuplicateStyle(String),
+    /// A stylesheet file could not be read.
+    Io(io::Error),
+    /// The format of a stylesheet file can't be deduced from its extension.
+    UnknownFormat(String),
+    /// A stylesheet document is malformed.
+    /// The line is 1-based, and is None if the parser could not tell it.
+    Syntax { line: Option<usize>, message: String },
+    /// A style property contains a value we don't know.
+    /// The line is 1-based, and is None if the format doesn't tell it.
+    /// allowed contains the values that would be valid for that key.
+    InvalidValue {
+        line: Option<usize>,
+        style: String,
+        key: String,
+        value: String,
+        allowed: Vec<String>,
+    },
+}
+
+impl fmt::Display for StylesheetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StylesheetError::Frozen => write!(f, "Trying to modify a frozen Stylesheet"),
+            StylesheetError::UnknownStyle(name) => write!(f, "Style not found: \"{}\"", name),
+            StylesheetError::DefaultStyle => write!(f, "The default style can't be removed"),
+            StylesheetError::InheritanceCycle(names) => write!(f, "Inheritance cycle: {}", names.join(" -> ")),
+            StylesheetError::ConflictingProperties(reason) => write!(f, "Conflicting style properties: {}", reason),
+            StylesheetError::InvalidTimestampFormat(format) => write!(f, "Invalid timestamp format: \"{}\"", format),
+            StylesheetError::DuplicateStyle(name) => write!(f, "Style already exists: \"{}\"", name),
+            StylesheetError::Io(err) => write!(f, "Cannot read stylesheet: {}", err),
+            StylesheetError::UnknownFormat(path) =>
+                write!(f, "Unknown stylesheet format, expected .toml, .json, .yaml or .yml: \"{}\"", path),
+            StylesheetError::Syntax { line: Some(line), message } =>
+                write!(f, "Syntax error at line {}: {}", line, message),
+            StylesheetError::Syntax { line: None, message } =>
+                write!(f, "Syntax error: {}", message),
+            StylesheetError::InvalidValue { line, style, key, value, allowed } => {
+                match line {
+                    Some(line) => write!(f, "Invalid value at line {}: ", line)?,
+                    None => write!(f, "Invalid value: ")?,
+                }
+                write!(
+                    f, "style \"{}\", key \"{}\": \"{}\"; allowed values are: {}",
+                    style, key, value, allowed.join(", ")
+                )
+            },
+        }
+    }
+}
+
+impl Error for StylesheetError {}
+
+impl From<io::Error> for StylesheetError {
+    fn from(err: io::Error) -> Self {
+        StylesheetError::Io(err)
+    }
+}
+
+
+/// Errors found in the templates of Stylesheet::try_format_template().
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A '{' without the matching '}'.
+    UnterminatedPlaceholder,
+    /// A placeholder is neither "{}" nor "{:style}". Contains the text
+    /// between the braces.
+    InvalidPlaceholder(String),
+    /// A '}' that doesn't close a placeholder and is not doubled.
+    UnmatchedBrace,
+    /// There are more placeholders than arguments.
+    NotEnoughArguments,
+    /// There are more arguments than placeholders.
+    TooManyArguments,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::UnterminatedPlaceholder => write!(f, "Unterminated placeholder"),
+            TemplateError::InvalidPlaceholder(spec) => write!(f, "Invalid placeholder {{{}}}", spec),
+            TemplateError::UnmatchedBrace => write!(f, "Unmatched '}}'"),
+            TemplateError::NotEnoughArguments => write!(f, "Not enough arguments"),
+            TemplateError::TooManyArguments => write!(f, "Too many arguments"),
+        }
+    }
+}
+
+impl Error for TemplateError {}
+
+
+/// Split a compact style definition at commas, except for the commas
+/// between parenthesis, like in "bold,rgb(255, 136, 0)".
+/// Tokens are trimmed, and empty tokens are skipped.
+fn split_style_spec(spec: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                tokens.push(&spec[start..i]);
+                start = i + 1;
+            },
+            _ => (),
+        }
+    }
+    tokens.push(&spec[start..]);
+    tokens.into_iter().map(|token| token.trim()).filter(|token| !token.is_empty()).collect()
+}
+
+
+/// A value read from a stylesheet file, before we validate it.
+trait RawValue {
+    /// The value as written in the file.
+    fn text(&self) -> &str;
+
+    /// The 1-based line of the value in source, if the format tells it.
+    fn line(&self, source: &str) -> Option<usize>;
+}
+
+impl RawValue for String {
+    fn text(&self) -> &str {
+        self
+    }
+
+    fn line(&self, _source: &str) -> Option<usize> {
+        None
+    }
+}
+
+impl RawValue for Spanned<String> {
+    fn text(&self) -> &str {
+        self.get_ref()
+    }
+
+    fn line(&self, source: &str) -> Option<usize> {
+        Some(source[..self.start()].matches('\n').count() + 1)
+    }
+}
+
+/// A style as written in a stylesheet file.
+/// Values are kept as strings, so that we can validate them ourselves
+/// and report the invalid ones properly.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RawStyle<V> {
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    transformation: Vec<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    underline_color: Option<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extends: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    icon: Option<V>,
+    #[serde(default = "BTreeMap::new", skip_serializing_if = "BTreeMap::is_empty")]
+    alternates: BTreeMap<String, RawAlternate<V>>,
+}
+
+/// The properties of a style for a ColorLevel, as written in a
+/// stylesheet file.
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct RawAlternate<V> {
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    transformation: Vec<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    color: Option<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    background: Option<V>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    underline_color: Option<V>,
+}
+
+/// Validate the values of a style and return the corresponding StyleProperties.
+fn raw_properties<V: RawValue>(
+        transformation_values: &[V],
+        color_value: &Option<V>,
+        background_value: &Option<V>,
+        underline_color_value: &Option<V>,
+        style_name: &str,
+        source: &str,
+    ) -> Result<StyleProperties, StylesheetError> {
+    let invalid = |value: &V, key: &str, allowed: Vec<String>| {
+        StylesheetError::InvalidValue {
+            line: value.line(source),
+            style: style_name.to_string(),
+            key: key.to_string(),
+            value: value.text().to_string(),
+            allowed,
+        }
+    };
+    let transformations = || StyleTransformation::NAMES.iter().map(|name| name.to_string()).collect();
+
+    let mut transformation = Vec::new();
+    for value in transformation_values {
+        transformation.push(
+            value.text().parse().map_err(|_| invalid(value, "transformation", transformations()))?
+        );
+    }
+    let color = match color_value {
+        Some(value) => Some(value.text().parse().map_err(|_| invalid(value, "color", allowed_colors()))?),
+        None => None,
+    };
+    let background = match background_value {
+        Some(value) => Some(value.text().parse().map_err(|_| invalid(value, "background", allowed_colors()))?),
+        None => None,
+    };
+    let underline_color = match underline_color_value {
+        Some(value) => Some(value.text().parse().map_err(|_| invalid(value, "underline_color", allowed_colors()))?),
+        None => None,
+    };
+    Ok(StyleProperties { transformation, color, background, underline_color })
+}
+
+impl<V: RawValue> RawStyle<V> {
+    /// Validate the values and return the corresponding StyleProperties.
+    fn to_properties(&self, style_name: &str, source: &str) -> Result<StyleProperties, StylesheetError> {
+        raw_properties(&self.transformation, &self.color, &self.background, &self.underline_color, style_name, source)
+    }
+
+    /// Validate the alternates, if any, and return their properties.
+    fn to_alternates(
+            &self,
+            style_name: &str,
+            source: &str,
+        ) -> Result<BTreeMap<ColorLevel, StyleProperties>, StylesheetError> {
+        let mut alternates = BTreeMap::new();
+        for (level, alternate) in &self.alternates {
+            let level = level.parse().map_err(|_| StylesheetError::InvalidValue {
+                line: None,
+                style: style_name.to_string(),
+                key: "alternates".to_string(),
+                value: level.clone(),
+                allowed: ColorLevel::NAMES.iter().map(|name| name.to_string()).collect(),
+            })?;
+            let properties = raw_properties(
+                &alternate.transformation,
+                &alternate.color,
+                &alternate.background,
+                &alternate.underline_color,
+                style_name,
+                source,
+            )?;
+            alternates.insert(level, properties);
+        }
+        Ok(alternates)
+    }
+
+    /// Validate the icon name, if any, and return the corresponding Icon.
+    fn to_icon(&self, style_name: &str, source: &str) -> Result<Option<Icon>, StylesheetError> {
+        match &self.icon {
+            Some(value) => value.text().parse().map(Some).map_err(|_| StylesheetError::InvalidValue {
+                line: value.line(source),
+                style: style_name.to_string(),
+                key: "icon".to_string(),
+                value: value.text().to_string(),
+                allowed: Icon::NAMES.iter().map(|name| name.to_string()).collect(),
+            }),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Return the names of the transformations of properties, as written in
+/// stylesheet files.
+fn transformation_names(properties: &StyleProperties) -> Vec<String> {
+    properties.transformation.iter().map(|transformation| transformation.to_string()).collect()
+}
+
+impl<'a> From<&'a StyleEntry> for RawStyle<String> {
+    fn from(entry: &'a StyleEntry) -> Self {
+        let properties = &entry.properties;
+        RawStyle {
+            transformation: transformation_names(properties),
+            color: properties.color.as_ref().map(|c| c.to_string()),
+            background: properties.background.as_ref().map(|c| c.to_string()),
+            underline_color: properties.underline_color.as_ref().map(|c| c.to_string()),
+            extends: entry.parent.clone(),
+            prefix: entry.prefix.clone(),
+            icon: entry.icon.map(|icon| icon.to_string()),
+            alternates: entry.alternates.iter()
+                .map(|(level, properties)| (level.to_string(), RawAlternate {
+                    transformation: transformation_names(properties),
+                    color: properties.color.as_ref().map(|c| c.to_string()),
+                    background: properties.background.as_ref().map(|c| c.to_string()),
+                    underline_color: properties.underline_color.as_ref().map(|c| c.to_string()),
+                }))
+                .collect(),
+        }
+    }
+}
+
+
+/// A style in a Stylesheet: the properties it was defined with,
+/// and the escape sequence we use to print.
+#[derive(Clone)]
+struct StyleEntry {
+    /// If the style extends another style, these are only the overrides.
+    properties: StyleProperties,
+    /// The name of the style this style extends, if any.
+    parent: Option<String>,
+    /// The SGR escape sequence that turns the style on, built from the
+    /// properties of the style and of its ancestors. Empty for no style.
+    sgr: String,
+    /// The text printed before messages, if the style has its own.
+    prefix: Option<String>,
+    /// The prefix of the style or of its closest ancestor that has one.
+    /// Empty for no prefix.
+    full_prefix: String,
+    /// The icon printed before messages, if the style has its own.
+    icon: Option<Icon>,
+    /// The icon of the style or of its closest ancestor that has one.
+    full_icon: Option<Icon>,
+    /// Properties used instead of the main ones on terminals with
+    /// limited capabilities. See Stylesheet::set_alternate().
+    alternates: BTreeMap<ColorLevel, StyleProperties>,
+}
+
+impl StyleEntry {
+    /// Return the properties to use on terminals with the given color
+    /// level: the alternate for the lowest level that is not lower than
+    /// color_level, if any, or the main properties.
+    /// If color_level is None, return the main properties.
+    fn properties_for(&self, color_level: Option<ColorLevel>) -> &StyleProperties {
+        color_level
+            .and_then(|level| self.alternates.range(level..).next())
+            .map_or(&self.properties, |(_, properties)| properties)
+    }
+}
+
+
+/// A style as stored in a StylesheetView.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StyleDefinition {
+    /// If extends is not None, these are only the overrides.
+    #[serde(flatten)]
+    pub properties: StyleProperties,
+    /// The name of the style this style extends, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// The text printed before messages, if any. See Stylesheet::set_prefix().
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    /// The icon printed before messages, if any. See Stylesheet::set_icon().
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<Icon>,
+    /// Properties for terminals with limited capabilities.
+    /// See Stylesheet::set_alternate().
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub alternates: BTreeMap<ColorLevel, StyleProperties>,
+}
+
+/// The styles of a Stylesheet as plain data, that can be serialized,
+/// compared and modified. It is serialized as a map of style names
+/// and definitions, in the same format as stylesheet files.
+/// Other Stylesheet settings, like the ColorMode, are not included.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct StylesheetView {
+    pub styles: BTreeMap<String, StyleDefinition>,
+}
+
+
+/// What Stylesheet::merge() does when both stylesheets contain
+/// a style with the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Use the style from the merged stylesheet.
+    Override,
+    /// Keep the existing style.
+    Keep,
+    /// Fail with StylesheetError::DuplicateStyle.
+    Error,
+}
+
+/// What a Stylesheet does when a text is printed or formatted with
+/// a style that doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FallbackPolicy {
+    /// Use the default style, that doesn't change the text.
+    #[default]
+    Default,
+    /// Use the style with the given name.
+    Style(String),
+    /// Use the default style, and pass a warning to the handler set with
+    /// Stylesheet::set_warning_handler() the first time a style name
+    /// is not found.
+    Warn,
+    /// Use the default style. try_format(), try_println() and try_print()
+    /// fail with StylesheetError::UnknownStyle.
+    Error,
+}
+
+/// A function called with the warnings of a Stylesheet.
+/// See Stylesheet::set_warning_handler().
+type WarningHandler = Arc<dyn Fn(&StylesheetError) + Send + Sync>;
+
+
+/// Stylesheet struct creates and handles a stylesheet.
+/// A stylesheet is a library of named styles that can be applied to texts.
+/// A Stylesheet style will play in your code the same role that a named
+/// CSS style plays in an HTML document.
+pub struct Stylesheet {
+    styles: HashMap<String, StyleEntry>,
+    /// Alternative names of styles, and the names of those styles.
+    aliases: HashMap<String, String>,
+    is_frozen: bool,
+    /// What the terminal supports. With ColorLevel::Basic, Fixed colors
+    /// are shown as one of the 16 basic colors; without hyperlinks, links
+    /// are printed as "text (url)"; without unicode, we only print ASCII
+    /// decorations.
+    capabilities: TerminalCapabilities,
+    /// True if NO_COLOR is set and we honor it: texts are printed unstyled.
+    no_color: bool,
+    /// True if CLICOLOR_FORCE is set and we honor it: texts are printed
+    /// with styles, even if the output is not a terminal.
+    clicolor_force: bool,
+    /// True if CLICOLOR is "0" and we honor it: texts are printed unstyled.
+    clicolor_disabled: bool,
+    /// True if stdout was a terminal when the stylesheet was created.
+    is_tty: bool,
+    /// True if stderr was a terminal when the stylesheet was created.
+    stderr_is_tty: bool,
+    color_mode: ColorMode,
+    /// If true, texts are printed without styles, whatever the ColorMode.
+    plain: bool,
+    /// Format and style name of the timestamp printed before lines, if enabled.
+    timestamp: Option<(String, String)>,
+    /// Where println() and print() write. If None, they write to stdout.
+    writer: Option<Arc<Mutex<Box<dyn Write + Send>>>>,
+    /// True if the writer keeps escape codes with ColorMode::Auto,
+    /// as if it was a terminal.
+    keep_ansi: bool,
+    /// If true, the output is flushed after every print.
+    auto_flush: bool,
+    /// If true, red and green styles have icons. See set_red_green_icons().
+    red_green_icons: bool,
+    /// Rules used by format_highlighted() and println_highlighted().
+    highlight_rules: HighlightRules,
+    /// What to do when a style doesn't exist.
+    fallback: FallbackPolicy,
+    /// Unknown style names we already warned about, with FallbackPolicy::Warn.
+    warned: Mutex<HashSet<String>>,
+    /// Where warnings go. If None, they are discarded.
+    warning_handler: Option<WarningHandler>,
+}
+
+impl Stylesheet {
+    /// The style used for style names that don't exist. Every
+    /// stylesheet contains it.
+    pub const DEFAULT_STYLE: &'static str = "_default";
+
+    /// Prefix of the environment variables read by apply_env_overrides().
+    pub const ENV_PREFIX: &'static str = "CLI_EXP_STYLE_";
+
+    /// A built-in style that gives every character a different color.
+    /// It can be used like any other style, unless the stylesheet
+    /// defines a style with the same name.
+    pub const RAINBOW_STYLE: &'static str = "rainbow";
+
+    /// Colors used by RAINBOW_STYLE, in order.
+    const RAINBOW_COLORS: [StyleColor; 6] = [
+        StyleColor::BrightRed, StyleColor::BrightYellow, StyleColor::BrightGreen,
+        StyleColor::BrightCyan, StyleColor::BrightBlue, StyleColor::BrightMagenta,
+    ];
+
+
+    /// Return a new stylesheet. It only contains DEFAULT_STYLE,
+    /// that can be used explicitally and is used implicitally when
+    /// we try to use a non-existing style.
+    ///
+    /// If the NO_COLOR environment variable is set to a non-empty value,
+    /// texts will be printed without styles (see https://no-color.org/).
+    /// The same happens if stdout is not a terminal, or if CLICOLOR is
+    /// "0". If CLICOLOR_FORCE is set to a value other than "0", texts are
+    /// printed with styles even if stdout is not a terminal, and even if
+    /// NO_COLOR is set. The --color option of a program, if any, should
+    /// win over all these variables: see set_color_mode() and ColorDecision.
+    pub fn new() -> Stylesheet {
+        Stylesheet::new_honoring_no_color(true)
+    }
+
+    /// Same as new(), but if honor_no_color is false the NO_COLOR,
+    /// CLICOLOR and CLICOLOR_FORCE environment variables are ignored.
+    /// Use it only if your program needs colors to work properly.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new_honoring_no_color(false);
+    /// ```
+    pub fn new_honoring_no_color(honor_no_color: bool) -> Stylesheet {
+        let mut hash: HashMap<String, StyleEntry> = HashMap::new();
+        hash.insert(Stylesheet::DEFAULT_STYLE.to_string(), StyleEntry {
+            properties: StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None },
+            parent: None,
+            sgr: String::new(),
+            prefix: None,
+            full_prefix: String::new(),
+            icon: None,
+            full_icon: None,
+            alternates: BTreeMap::new(),
+        });
+
+        Stylesheet {
+            styles: hash,
+            aliases: HashMap::new(),
+            is_frozen: false,
+            capabilities: TerminalCapabilities::detect(),
+            no_color: honor_no_color && env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+            clicolor_force: honor_no_color
+                && env::var_os("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0"),
+            clicolor_disabled: honor_no_color && env::var_os("CLICOLOR").is_some_and(|value| value == "0"),
+            is_tty: stdout_is_tty(),
+            stderr_is_tty: stderr_is_tty(),
+            color_mode: ColorMode::Auto,
+            plain: false,
+            timestamp: None,
+            writer: None,
+            keep_ansi: false,
+            auto_flush: false,
+            red_green_icons: false,
+            highlight_rules: HighlightRules::new(),
+            fallback: FallbackPolicy::Default,
+            warned: Mutex::new(HashSet::new()),
+            warning_handler: None,
+        }
+    }
+
+    /// Same as new(), but println() and print() will write to writer
+    /// instead of stdout. The writer is not considered a terminal, so
+    /// styles are only applied with ColorMode::Always.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Any io::Write implementor: stderr, a file, a socket...
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::with_writer(std::io::stderr());
+    /// sheet.set_color_mode(ColorMode::Always);
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn with_writer<W>(writer: W) -> Stylesheet where W: Write + Send + 'static {
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer(writer);
+        sheet
+    }
+
+    /// Make println() and print() write to writer instead of stdout.
+    /// Like with_writer(), but it can be used with stylesheets loaded
+    /// from files.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Any io::Write implementor: stderr, a file, a socket...
+    pub fn set_writer<W>(&mut self, writer: W) where W: Write + Send + 'static {
+        self.writer = Some(Arc::new(Mutex::new(Box::new(writer))));
+        self.is_tty = false;
+        self.keep_ansi = false;
+    }
+
+    /// Same as set_writer(), but the writer keeps the escape codes with
+    /// ColorMode::Auto, as if it was a terminal. This is useful for log
+    /// files that will be read with "less -R". Setting another writer
+    /// with set_writer() disables this.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Any io::Write implementor: stderr, a file, a socket...
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_writer_keeping_ansi(std::fs::File::create("scan.log").unwrap());
+    /// sheet.println("danger", "Logged in red");
+    /// ```
+    pub fn set_writer_keeping_ansi<W>(&mut self, writer: W) where W: Write + Send + 'static {
+        self.set_writer(writer);
+        self.keep_ansi = true;
+    }
+
+    /// Return true if the writer keeps escape codes.
+    /// See set_writer_keeping_ansi().
+    pub fn keeps_ansi(&self) -> bool {
+        self.keep_ansi
+    }
+
+    /// Return true if stdout is a terminal. If it is not, texts are
+    /// printed without styles.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// if !sheet.is_tty() {
+    ///     eprintln!("Output is redirected");
+    /// }
+    /// ```
+    pub fn is_tty(&self) -> bool {
+        self.is_tty
+    }
+
+    /// Return true if the output is a terminal that interprets escape
+    /// sequences, or a writer set with set_writer_keeping_ansi(). Only
+    /// then texts can be redrawn, so animations like progress bars make
+    /// sense. This doesn't depend on the ColorMode.
+    pub fn is_interactive(&self) -> bool {
+        self.is_tty && self.capabilities.ansi || self.keep_ansi
+    }
+
+    /// Return the current ColorMode. The default is Auto.
+    pub fn color_mode(&self) -> ColorMode {
+        self.color_mode
+    }
+
+    /// Decide when texts are printed with their styles.
+    /// This doesn't modify the styles, so it is allowed for
+    /// frozen stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - Always, Auto or Never.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.set_color_mode("always".parse().unwrap());
+    /// assert!(sheet.colors_enabled());
+    /// ```
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.color_mode = mode;
+    }
+
+    /// Return true if texts are currently printed with their styles,
+    /// according to the ColorMode, the environment variables and TTY
+    /// detection. See color_decision().
+    pub fn colors_enabled(&self) -> bool {
+        self.color_decision().colors_enabled()
+    }
+
+    /// Same as colors_enabled(), but for eprintln() and eprint().
+    pub fn colors_enabled_stderr(&self) -> bool {
+        self.color_decision_stderr().colors_enabled()
+    }
+
+    /// Return whether texts are currently printed with their styles,
+    /// and why.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.set_color_mode(ColorMode::Never);
+    /// assert_eq!(sheet.color_decision(), ColorDecision::Never);
+    /// assert!(!sheet.color_decision().colors_enabled());
+    /// ```
+    pub fn color_decision(&self) -> ColorDecision {
+        self.color_decision_for(self.is_interactive())
+    }
+
+    /// Same as color_decision(), but for eprintln() and eprint().
+    /// Like stdout, stderr gets styles only if it is a terminal that
+    /// interprets escape sequences: consoles where virtual terminal
+    /// processing can't be enabled get plain texts.
+    pub fn color_decision_stderr(&self) -> ColorDecision {
+        self.color_decision_for(self.stderr_is_tty && self.capabilities.ansi)
+    }
+
+    /// Enable or disable plain mode. In plain mode all print and format
+    /// methods return the raw texts, without escape codes. Unlike
+    /// ColorMode::Never, disabling plain mode restores the previous
+    /// ColorMode. Like set_color_mode(), it is allowed for frozen stylesheets.
+    ///
+    /// # Arguments
+    ///
+    /// * `plain` - true to enable plain mode, false to disable it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// sheet.set_plain(true);
+    /// assert_eq!(sheet.format("danger", "Some text"), "Some text");
+    /// ```
+    pub fn set_plain(&mut self, plain: bool) {
+        self.plain = plain;
+    }
+
+    /// Return true if plain mode is enabled.
+    pub fn is_plain(&self) -> bool {
+        self.plain
+    }
+
+    /// Return whether texts are printed with their styles on an output,
+    /// and why. shows_ansi is true if the output can show them.
+    fn color_decision_for(&self, shows_ansi: bool) -> ColorDecision {
+        match self.color_mode {
+            _ if self.plain => ColorDecision::Plain,
+            ColorMode::Always => ColorDecision::Always,
+            ColorMode::Never => ColorDecision::Never,
+            ColorMode::Auto if self.clicolor_force => ColorDecision::CliColorForce,
+            ColorMode::Auto if self.no_color => ColorDecision::NoColor,
+            ColorMode::Auto if self.clicolor_disabled => ColorDecision::CliColorDisabled,
+            ColorMode::Auto if shows_ansi => ColorDecision::Terminal,
+            ColorMode::Auto => ColorDecision::NotTerminal,
+        }
+    }
+
+    /// Return a new stylesheet containing the styles defined in a TOML file.
+    /// Every table in the file is a style, and the table name is the style
+    /// name. All keys are optional.
+    ///
+    /// ```toml
+    /// [danger]
+    /// transformation = ["bold", "blink"]
+    /// color = "red"
+    /// background = "white"
+    /// prefix = "ERROR: "
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the TOML file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_toml_file("styles.toml").unwrap();
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn from_toml_file<P: AsRef<Path>>(path: P) -> Result<Stylesheet, StylesheetError> {
+        let source = fs::read_to_string(path)?;
+        Stylesheet::from_toml_str(&source)
+    }
+
+    /// Return a new stylesheet, reading styles from a TOML, JSON or YAML
+    /// file. The format is deduced from the file extension: ".toml",
+    /// ".json", ".yaml" or ".yml".
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_file("styles.yaml").unwrap();
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Stylesheet, StylesheetError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str()).map(|extension| extension.to_lowercase());
+        match extension.as_deref() {
+            Some("toml") => Stylesheet::from_toml_file(path),
+            Some("json") => Stylesheet::from_json_file(path),
+            Some("yaml") | Some("yml") => Stylesheet::from_yaml_file(path),
+            _ => Err(StylesheetError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    /// Same as from_toml_file(), but the TOML document is passed as a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The TOML document (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_toml_str("[info]\ncolor = \"green\"\n").unwrap();
+    /// sheet.println("info", "Some text");
+    /// ```
+    pub fn from_toml_str(source: &str) -> Result<Stylesheet, StylesheetError> {
+        let document: BTreeMap<String, RawStyle<Spanned<String>>> = toml::from_str(source).map_err(|err| {
+            StylesheetError::Syntax {
+                line: err.line_col().map(|(line, _)| line + 1),
+                message: err.to_string(),
+            }
+        })?;
+        Stylesheet::from_raw_styles(&document, source)
+    }
+
+    /// Return a new stylesheet containing the styles defined in a JSON file.
+    /// The document is an object of styles, and every key is a style name.
+    /// All style keys are optional.
+    ///
+    /// ```json
+    /// {
+    ///   "danger": {
+    ///     "transformation": ["bold", "blink"],
+    ///     "color": "red",
+    ///     "background": "white"
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the JSON file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_json_file("styles.json").unwrap();
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Stylesheet, StylesheetError> {
+        let source = fs::read_to_string(path)?;
+        Stylesheet::from_json(&source)
+    }
+
+    /// Same as from_json_file(), but the JSON document is passed as a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The JSON document (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_json(r#"{ "info": { "color": "green" } }"#).unwrap();
+    /// sheet.println("info", "Some text");
+    /// ```
+    pub fn from_json(source: &str) -> Result<Stylesheet, StylesheetError> {
+        let document: BTreeMap<String, RawStyle<String>> = serde_json::from_str(source).map_err(|err| {
+            StylesheetError::Syntax {
+                line: Some(err.line()),
+                message: err.to_string(),
+            }
+        })?;
+        Stylesheet::from_raw_styles(&document, source)
+    }
+
+    /// Return a new stylesheet containing the styles defined in a YAML file.
+    /// The document is a mapping of styles, and every key is a style name.
+    /// All style keys are optional.
+    ///
+    /// ```yaml
+    /// danger:
+    ///   transformation: [bold, blink]
+    ///   color: red
+    ///   background: white
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the YAML file.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_yaml_file("styles.yaml").unwrap();
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn from_yaml_file<P: AsRef<Path>>(path: P) -> Result<Stylesheet, StylesheetError> {
+        let source = fs::read_to_string(path)?;
+        Stylesheet::from_yaml_str(&source)
+    }
+
+    /// Same as from_yaml_file(), but the YAML document is passed as a string.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The YAML document (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::from_yaml_str("info:\n  color: green\n").unwrap();
+    /// sheet.println("info", "Some text");
+    /// ```
+    pub fn from_yaml_str(source: &str) -> Result<Stylesheet, StylesheetError> {
+        let document: BTreeMap<String, RawStyle<String>> = serde_yaml::from_str(source).map_err(|err| {
+            StylesheetError::Syntax {
+                line: err.location().map(|location| location.line()),
+                message: err.to_string(),
+            }
+        })?;
+        Stylesheet::from_raw_styles(&document, source)
+    }
+
+    /// Return the stylesheet as a JSON document that from_json() accepts.
+    /// DEFAULT_STYLE is not included, because every stylesheet has it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("info", StyleProperties {
+    ///     transformation: [].to_vec(), color: Some(Green), background: None, underline_color: None
+    /// });
+    /// let copy = Stylesheet::from_json(&sheet.to_json()).unwrap();
+    /// ```
+    pub fn to_json(&self) -> String {
+        let document: BTreeMap<&str, RawStyle<String>> = self.styles.iter()
+            .filter(|(name, _)| name.as_str() != Stylesheet::DEFAULT_STYLE)
+            .map(|(name, entry)| (name.as_str(), RawStyle::from(entry)))
+            .collect();
+        // serializing a map of strings and vectors of strings can't fail
+        serde_json::to_string_pretty(&document).unwrap()
+    }
+
+    /// Return the styles as a StylesheetView.
+    /// DEFAULT_STYLE is not included, because every stylesheet has it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("info", StyleProperties::builder().color(StyleColor::Green).build());
+    /// let view = sheet.view();
+    /// assert_eq!(view.styles["info"].properties.color, Some(StyleColor::Green));
+    /// ```
+    pub fn view(&self) -> StylesheetView {
+        let styles = self.styles.iter()
+            .filter(|(name, _)| name.as_str() != Stylesheet::DEFAULT_STYLE)
+            .map(|(name, entry)| {
+                (name.clone(), StyleDefinition {
+                    properties: entry.properties.clone(),
+                    extends: entry.parent.clone(),
+                    prefix: entry.prefix.clone(),
+                    icon: entry.icon,
+                    alternates: entry.alternates.clone(),
+                })
+            })
+            .collect();
+        StylesheetView { styles }
+    }
+
+    /// Return a new stylesheet containing the styles of a StylesheetView.
+    ///
+    /// # Arguments
+    ///
+    /// * `view` - The styles to add.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut view = StylesheetView::default();
+    /// view.styles.insert("info".to_string(), StyleDefinition {
+    ///     properties: StyleProperties::builder().color(StyleColor::Green).build(),
+    ///     extends: None,
+    ///     prefix: None,
+    ///     icon: None,
+    ///     alternates: Default::default(),
+    /// });
+    /// let sheet = Stylesheet::from_view(&view).unwrap();
+    /// ```
+    pub fn from_view(view: &StylesheetView) -> Result<Stylesheet, StylesheetError> {
+        let mut sheet = Stylesheet::new();
+        for (style_name, definition) in &view.styles {
+            sheet.insert_entry(style_name.clone(), definition.properties.clone(), definition.extends.clone());
+            let entry = sheet.styles.get_mut(style_name).unwrap();
+            entry.prefix = definition.prefix.clone();
+            entry.icon = definition.icon;
+            entry.alternates = definition.alternates.iter()
+                .map(|(level, properties)| (*level, properties.clone().with_rgb_colors()))
+                .collect();
+        }
+        for style_name in view.styles.keys() {
+            sheet.inheritance_chain(style_name)?;
+        }
+        sheet.resolve();
+        Ok(sheet)
+    }
+
+    /// Build a stylesheet from the styles read from a file.
+    fn from_raw_styles<V: RawValue>(
+            document: &BTreeMap<String, RawStyle<V>>,
+            source: &str,
+        ) -> Result<Stylesheet, StylesheetError> {
+        let mut sheet = Stylesheet::new();
+        for (style_name, definition) in document {
+            let properties = definition.to_properties(style_name, source)?;
+            let icon = definition.to_icon(style_name, source)?;
+            let alternates = definition.to_alternates(style_name, source)?;
+            sheet.insert_entry(style_name.clone(), properties, definition.extends.clone());
+            let entry = sheet.styles.get_mut(style_name).unwrap();
+            entry.prefix = definition.prefix.clone();
+            entry.icon = icon;
+            entry.alternates = alternates.into_iter()
+                .map(|(level, properties)| (level, properties.with_rgb_colors()))
+                .collect();
+        }
+        // styles can extend styles that appear later in the document,
+        // so we can only validate inheritance when we have all of them
+        for style_name in document.keys() {
+            sheet.inheritance_chain(style_name)?;
+        }
+        sheet.resolve();
+        Ok(sheet)
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.styles.len()
+    }
+
+    /// Return true if the stylesheet contains a style with this name.
+    pub fn contains(&self, style_name: &str) -> bool {
+        self.styles.contains_key(style_name) || self.aliases.contains_key(style_name)
+    }
+
+    /// Return the names of the styles, in alphabetical order.
+    /// The default style and aliases are not included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// for style_name in sheet.style_names() {
+    ///     sheet.println(style_name, style_name);
+    /// }
+    /// ```
+    pub fn style_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.styles.keys()
+            .map(|name| name.as_str())
+            .filter(|name| *name != Stylesheet::DEFAULT_STYLE)
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Return the properties a style was defined with, or None if
+    /// the style doesn't exist. If the style extends another style,
+    /// these are only the overrides.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style (&str).
+    pub fn get_properties(&self, style_name: &str) -> Option<&StyleProperties> {
+        self.styles.get(self.real_name(style_name)).map(|entry| &entry.properties)
+    }
+
+    /// Return an iterator over the style names and their properties,
+    /// in alphabetical order. The default style is not included.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// for (style_name, properties) in sheet.iter() {
+    ///     println!("{}: {:?}", style_name, properties.color);
+    /// }
+    /// ```
+    pub fn iter(&self) -> ::std::vec::IntoIter<(&str, &StyleProperties)> {
+        self.style_names().into_iter()
+            .map(|name| (name, &self.styles[name].properties))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Add a style to an existing stylesheet.
+    /// Panics if the stylesheet is frozen; see try_add_style() for
+    /// a version that returns an error instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to be added to the stylesheet (&str or String).
+    /// * `style_definition` - The definition of the style to be added to the stylesheet (StyleProperties).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::stylesheet::StyleTransformation::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties {
+    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White), underline_color: None
+    /// });
+    /// ```
+    pub fn add_style<S>(
+            &mut self,
+            style_name: S,
+            style_definition: StyleProperties,
+        ) where S: Into<String> {
+        if let Err(err) = self.try_add_style(style_name, style_definition) {
+            panic!("FATAL: {}", err);
+        }
+    }
+
+    /// Same as add_style(), but if the stylesheet is frozen it returns
+    /// StylesheetError::Frozen instead of panicking.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to be added to the stylesheet (&str or String).
+    /// * `style_definition` - The definition of the style to be added to the stylesheet (StyleProperties).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.freeze();
+    /// let result = sheet.try_add_style("info", StyleProperties {
+    ///     transformation: [].to_vec(), color: Some(Green), background: None, underline_color: None
+    /// });
+    /// assert!(result.is_err());
+    /// ```
+    pub fn try_add_style<S>(
+            &mut self,
+            style_name: S,
+            style_definition: StyleProperties,
+        ) -> Result<(), StylesheetError> where S: Into<String> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+
+        self.insert_style(style_name.into(), style_definition);
+        Ok(())
+    }
+
+    /// Add several styles at once, for example a whole theme.
+    /// This is faster than calling add_style() for every style.
+    /// Panics if the stylesheet is frozen; see try_add_styles() for
+    /// a version that returns an error instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `styles` - Pairs of style names (&str or String) and definitions (StyleProperties).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_styles(vec![
+    ///     ("danger", StyleProperties::builder().bold().color(Red).build()),
+    ///     ("info", StyleProperties::builder().color(Green).build()),
+    /// ]);
+    /// ```
+    pub fn add_styles<I, S>(&mut self, styles: I) where I: IntoIterator<Item = (S, StyleProperties)>, S: Into<String> {
+        if let Err(err) = self.try_add_styles(styles) {
+            panic!("FATAL: {}", err);
+        }
+    }
+
+    /// Same as add_styles(), but if the stylesheet is frozen it returns
+    /// StylesheetError::Frozen instead of panicking.
+    pub fn try_add_styles<I, S>(
+            &mut self,
+            styles: I,
+        ) -> Result<(), StylesheetError> where I: IntoIterator<Item = (S, StyleProperties)>, S: Into<String> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+
+        for (style_name, style_definition) in styles {
+            self.insert_entry(style_name.into(), style_definition, None);
+        }
+        self.resolve();
+        Ok(())
+    }
+
+    /// Add a style that extends an existing style. The new style has
+    /// the properties of its parent, with the following overrides:
+    /// transformations are added to the parent's transformations;
+    /// color and background replace the parent's, unless they are None.
+    /// Changes to the parent will also affect the new style.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to be added to the stylesheet (&str or String).
+    /// * `parent_name` - The name of the style to extend (&str).
+    /// * `overrides` - The properties that differ from the parent (StyleProperties).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::stylesheet::StyleTransformation::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties {
+    ///     transformation: [Bold].to_vec(), color: Some(Red), background: None, underline_color: None
+    /// });
+    /// // bold, blinking, red on white
+    /// sheet.add_style_extending("critical", "danger", StyleProperties {
+    ///     transformation: [Blink].to_vec(), color: None, background: Some(White), underline_color: None
+    /// }).unwrap();
+    /// ```
+    pub fn add_style_extending<S>(
+            &mut self,
+            style_name: S,
+            parent_name: &str,
+            overrides: StyleProperties,
+        ) -> Result<(), StylesheetError> where S: Into<String> {
+        let style_name: String = style_name.into();
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        if !self.contains(parent_name) {
+            return Err(StylesheetError::UnknownStyle(parent_name.to_string()));
+        }
+        let parent_name = self.real_name(parent_name).to_string();
+
+        // redefining an existing style could create a cycle:
+        // in that case, put the old definition back
+        let old_aliases = self.aliases.clone();
+        let old_entry = self.insert_entry(style_name.clone(), overrides, Some(parent_name));
+        if let Err(err) = self.inheritance_chain(&style_name) {
+            match old_entry {
+                Some(entry) => self.styles.insert(style_name, entry),
+                None => self.styles.remove(&style_name),
+            };
+            self.aliases = old_aliases;
+            return Err(err);
+        }
+        self.resolve();
+        Ok(())
+    }
+
+    /// Add an alternative name for a style. Printing with the alias is the
+    /// same as printing with the style, and changes to the style affect
+    /// the alias. Methods that modify a style, like update_style(), modify
+    /// the aliased style. remove_style() only removes the alias.
+    /// Aliases are not included in style_names(), iter() and view().
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` - The new name (&str or String).
+    /// * `style_name` - The name of the existing style, or of another alias (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// // danger was called error in the old versions
+    /// sheet.add_alias("error", "danger").unwrap();
+    /// sheet.println("error", "Borg cube approaching!");
+    /// ```
+    pub fn add_alias<S>(&mut self, alias: S, style_name: &str) -> Result<(), StylesheetError> where S: Into<String> {
+        let alias: String = alias.into();
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        if self.styles.contains_key(&alias) {
+            return Err(StylesheetError::DuplicateStyle(alias));
+        }
+        if !self.contains(style_name) {
+            return Err(StylesheetError::UnknownStyle(style_name.to_string()));
+        }
+        let target = self.real_name(style_name).to_string();
+        self.aliases.insert(alias, target);
+        Ok(())
+    }
+
+    /// If style_name is an alias, return the name of the aliased style.
+    pub fn alias_target(&self, style_name: &str) -> Option<&str> {
+        self.aliases.get(style_name).map(|target| target.as_str())
+    }
+
+    /// Return the name of the style that style_name refers to:
+    /// the aliased style if it is an alias, or style_name itself.
+    fn real_name<'a>(&'a self, style_name: &'a str) -> &'a str {
+        self.alias_target(style_name).unwrap_or(style_name)
+    }
+
+    /// Add a style that combines the properties of existing styles,
+    /// merged in the given order with StyleProperties::merge().
+    /// The new style doesn't extend the original styles: changing them
+    /// later won't affect it.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to be added to the stylesheet (&str or String).
+    /// * `style_names` - The names of the styles to combine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("bold", StyleProperties::builder().bold().build());
+    /// sheet.add_style("red", StyleProperties::builder().color(Red).build());
+    /// sheet.compose("bold_red", &["bold", "red"]).unwrap();
+    /// ```
+    pub fn compose<S>(
+            &mut self,
+            style_name: S,
+            style_names: &[&str],
+        ) -> Result<(), StylesheetError> where S: Into<String> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        let mut properties = StyleProperties::default();
+        for name in style_names {
+            if !self.contains(name) {
+                return Err(StylesheetError::UnknownStyle(name.to_string()));
+            }
+            properties = properties.merge(&self.resolved_properties(self.real_name(name)));
+        }
+        properties.check_conflicts()?;
+        self.insert_style(style_name.into(), properties);
+        Ok(())
+    }
+
+    /// Copy all the styles of other into this stylesheet.
+    /// Styles that extend other styles keep doing so. Other settings,
+    /// like the ColorMode, are not copied.
+    /// If an error occurs, this stylesheet is not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The stylesheet to copy styles from.
+    /// * `policy` - What to do if a style exists in both stylesheets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+    /// sheet.add_style("info", StyleProperties::builder().color(Green).build());
+    /// // the user theme redefines danger
+    /// let user_theme = Stylesheet::from_toml_str("[danger]\ncolor = \"magenta\"").unwrap();
+    /// sheet.merge(&user_theme, ConflictPolicy::Override).unwrap();
+    /// ```
+    pub fn merge(&mut self, other: &Stylesheet, policy: ConflictPolicy) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+
+        let old_styles = self.styles.clone();
+        let old_aliases = self.aliases.clone();
+        for (style_name, entry) in &other.styles {
+            if style_name == Stylesheet::DEFAULT_STYLE {
+                continue;
+            }
+            if self.contains(style_name) {
+                match policy {
+                    ConflictPolicy::Override => (),
+                    ConflictPolicy::Keep => continue,
+                    ConflictPolicy::Error => {
+                        self.styles = old_styles;
+                        self.aliases = old_aliases;
+                        return Err(StylesheetError::DuplicateStyle(style_name.clone()));
+                    },
+                }
+            }
+            self.aliases.remove(style_name);
+            self.styles.insert(style_name.clone(), entry.clone());
+        }
+        // an overridden style could extend a style that extends it
+        let style_names: Vec<String> = self.styles.keys().cloned().collect();
+        for style_name in style_names {
+            if let Err(err) = self.inheritance_chain(&style_name) {
+                self.styles = old_styles;
+                self.aliases = old_aliases;
+                return Err(err);
+            }
+        }
+        // aliases of removed styles are removed
+        let styles = &self.styles;
+        self.aliases.retain(|_, target| styles.contains_key(target));
+        self.resolve();
+        Ok(())
+    }
+
+    /// Replace all the styles and aliases with the ones of other.
+    /// Other settings, like the ColorMode or the writer, are kept.
+    /// This is how a stylesheet file is reloaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The stylesheet to copy styles from.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// let edited = Stylesheet::from_toml_str("[danger]\ncolor = \"magenta\"").unwrap();
+    /// sheet.replace_styles(&edited).unwrap();
+    /// assert!(!sheet.contains("info"));
+    /// ```
+    pub fn replace_styles(&mut self, other: &Stylesheet) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        self.styles = other.styles.clone();
+        self.aliases = other.aliases.clone();
+        self.resolve();
+        Ok(())
+    }
+
+    /// Add a style without checking if the stylesheet is frozen.
+    fn insert_style(&mut self, style_name: String, style_definition: StyleProperties) {
+        self.insert_entry(style_name, style_definition, None);
+        self.resolve();
+    }
+
+    /// Add a style, without building its escape sequence. If an alias
+    /// has the same name, it is removed.
+    /// resolve() must be called after this. Return the replaced entry, if any.
+    fn insert_entry(
+            &mut self,
+            style_name: String,
+            style_definition: StyleProperties,
+            parent: Option<String>,
+        ) -> Option<StyleEntry> {
+        self.aliases.remove(&style_name);
+        self.styles.insert(style_name, StyleEntry {
+            properties: style_definition.with_rgb_colors(),
+            parent,
+            sgr: String::new(),
+            prefix: None,
+            full_prefix: String::new(),
+            icon: None,
+            full_icon: None,
+            alternates: BTreeMap::new(),
+        })
+    }
+
+    /// Return the names of a style and of its ancestors, starting from the style itself.
+    /// Fail if an ancestor doesn't exist or the style extends itself.
+    fn inheritance_chain(&self, style_name: &str) -> Result<Vec<String>, StylesheetError> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut name = Some(style_name.to_string());
+        while let Some(current) = name {
+            if chain.contains(&current) {
+                chain.push(current);
+                return Err(StylesheetError::InheritanceCycle(chain));
+            }
+            let entry = match self.styles.get(&current) {
+                Some(entry) => entry,
+                None => return Err(StylesheetError::UnknownStyle(current)),
+            };
+            name = entry.parent.clone();
+            chain.push(current);
+        }
+        Ok(chain)
+    }
+
+    /// Return the properties of a style, including the inherited ones.
+    fn resolved_properties(&self, style_name: &str) -> StyleProperties {
+        self.resolved_properties_for(style_name, None)
+    }
+
+    /// Same as resolved_properties(), but for every style in the chain
+    /// use the alternate for color_level, if any. See StyleEntry::properties_for().
+    fn resolved_properties_for(&self, style_name: &str, color_level: Option<ColorLevel>) -> StyleProperties {
+        let mut properties = StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None };
+        // inheritance is validated when styles are added, so this can't fail
+        let chain = self.inheritance_chain(style_name).unwrap_or_default();
+        for name in chain.iter().rev() {
+            properties = properties.merge(self.styles[name].properties_for(color_level));
+        }
+        properties
+    }
+
+    /// Build the escape sequence of every style.
+    /// Must be called after any change, because styles depend on their ancestors.
+    fn resolve(&mut self) {
+        let sequences: Vec<(String, String, String, Option<Icon>)> = self.styles.keys()
+            .map(|name| (
+                name.clone(),
+                self.build_sgr(&self.resolved_properties_for(name, Some(self.capabilities.color_level))),
+                self.resolved_prefix(name),
+                self.resolved_icon(name),
+            ))
+            .collect();
+        for (name, sgr, prefix, icon) in sequences {
+            if let Some(entry) = self.styles.get_mut(&name) {
+                entry.sgr = sgr;
+                entry.full_prefix = prefix;
+                entry.full_icon = icon;
+            }
+        }
+    }
+
+    /// Return the prefix of a style, or the one of its closest ancestor.
+    fn resolved_prefix(&self, style_name: &str) -> String {
+        let chain = self.inheritance_chain(style_name).unwrap_or_default();
+        chain.iter()
+            .find_map(|name| self.styles[name].prefix.clone())
+            .unwrap_or_default()
+    }
+
+    /// Return the icon of a style, or the one of its closest ancestor.
+    fn resolved_icon(&self, style_name: &str) -> Option<Icon> {
+        let chain = self.inheritance_chain(style_name).unwrap_or_default();
+        chain.iter().find_map(|name| self.styles[name].icon)
+    }
+
+    /// Return the color depth used to build escape sequences, that is
+    /// the color level of the terminal. Colors that need more colors
+    /// are replaced by the most similar supported ones.
+    /// See StyleColor::downsample().
+    pub fn color_depth(&self) -> ColorLevel {
+        self.capabilities.color_level
+    }
+
+    /// Return the SGR escape sequence that corresponds to style_definition,
+    /// or an empty string if it doesn't change the text appearance.
+    fn build_sgr(&self, style_definition: &StyleProperties) -> String {
+        let codes = self.sgr_parameters(style_definition);
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+
+    /// Return the SGR parameters that correspond to style_definition.
+    /// See build_sgr().
+    fn sgr_parameters(&self, style_definition: &StyleProperties) -> Vec<String> {
+        // Based on the contents of style_definition, collect the SGR
+        // parameters to join in a single escape sequence.
+        let mut codes: Vec<String> = Vec::new();
+        // bright is not a separate attribute: it changes the text color
+        let mut bright = false;
+        // apply all specified transformations, if any
+        for s in &style_definition.transformation {
+            match s {
+                StyleTransformation::Blink => codes.push("5".to_string()),
+                StyleTransformation::Bold => codes.push("1".to_string()),
+                StyleTransformation::Bright => bright = true,
+                StyleTransformation::Dim => codes.push("2".to_string()),
+                StyleTransformation::Italic => codes.push("3".to_string()),
+                StyleTransformation::Underlined => codes.push("4".to_string()),
+                StyleTransformation::DoubleUnderlined => codes.push("4:2".to_string()),
+                StyleTransformation::CurlyUnderlined => codes.push("4:3".to_string()),
+                StyleTransformation::Reverse => codes.push("7".to_string()),
+                StyleTransformation::Strikethrough => codes.push("9".to_string()),
+                StyleTransformation::Hidden => codes.push("8".to_string()),
+            }
+        }
+        // apply specified text color, unless it is None
+        if let Some(color) = &style_definition.color {
+            codes.extend(color.sgr_codes(false, bright, self.color_depth()));
+        }
+        // apply specified background color, unless it is None
+        if let Some(color) = &style_definition.background {
+            codes.extend(color.sgr_codes(true, false, self.color_depth()));
+        }
+        // apply specified underline color, unless it is None
+        if let Some(color) = &style_definition.underline_color {
+            codes.extend(color.underline_sgr_codes(self.color_depth()));
+        }
+        codes
+    }
+
+    /// Return message with the style applied. If the requested style
+    /// doesn't exist, we fall back to default.
+    fn paint(&self, style_name: &str, message: &str) -> String {
+        self.paint_for(style_name, message, self.colors_enabled())
+    }
+
+    /// Same as paint(), but the caller decides if colors are enabled.
+    fn paint_for(&self, style_name: &str, message: &str, colors_enabled: bool) -> String {
+        let entry = match self.lookup(style_name) {
+            Some(entry) => entry,
+            None => return self.rainbow(message, colors_enabled),
+        };
+        if entry.sgr.is_empty() || !colors_enabled {
+            message.to_string()
+        } else {
+            format!("{}{}\x1b[0m", entry.sgr, message)
+        }
+    }
+
+    /// Return the style to use for style_name, applying the FallbackPolicy
+    /// if it doesn't exist, or None for RAINBOW_STYLE.
+    fn lookup(&self, style_name: &str) -> Option<&StyleEntry> {
+        match self.styles.get(self.real_name(style_name)) {
+            Some(entry) => Some(entry),
+            None if style_name == Stylesheet::RAINBOW_STYLE => None,
+            None => Some(self.fallback_entry(style_name)),
+        }
+    }
+
+    /// Return the style to use instead of a style that doesn't exist,
+    /// according to the FallbackPolicy.
+    fn fallback_entry(&self, style_name: &str) -> &StyleEntry {
+        &self.styles[self.fallback_name(style_name)]
+    }
+
+    /// Return the name of the style returned by fallback_entry().
+    fn fallback_name(&self, style_name: &str) -> &str {
+        match self.fallback {
+            FallbackPolicy::Style(ref fallback) if self.styles.contains_key(self.real_name(fallback)) =>
+                self.real_name(fallback),
+            FallbackPolicy::Warn => {
+                let first_time = self.warned.lock()
+                    .unwrap_or_else(|err| err.into_inner())
+                    .insert(style_name.to_string());
+                // the lock is released, so the handler can use the stylesheet
+                if let (true, Some(handler)) = (first_time, &self.warning_handler) {
+                    handler(&StylesheetError::UnknownStyle(style_name.to_string()));
+                }
+                Stylesheet::DEFAULT_STYLE
+            },
+            _ => Stylesheet::DEFAULT_STYLE,
+        }
+    }
+
+    /// Return the properties of nested styles, from the outermost to
+    /// the innermost, merged with StyleProperties::merge(). Styles that
+    /// don't exist are replaced according to the FallbackPolicy.
+    /// No styles means the default style.
+    fn nested_properties(&self, style_names: &[String]) -> StyleProperties {
+        if style_names.is_empty() {
+            return self.resolved_properties_for(Stylesheet::DEFAULT_STYLE, Some(self.color_depth()));
+        }
+        style_names.iter().fold(StyleProperties::default(), |properties, style_name| {
+            let name = match self.real_name(style_name) {
+                name if self.styles.contains_key(name) => name,
+                _ => self.fallback_name(style_name),
+            };
+            properties.merge(&self.resolved_properties_for(name, Some(self.color_depth())))
+        })
+    }
+
+    /// Return the SGR escape sequence that changes the style of the
+    /// following texts from one set of properties to another, without
+    /// a full reset: only the differences are written. This allows
+    /// to restore an outer style when a nested style ends.
+    /// If to has no properties, a reset is returned.
+    fn transition_sgr(&self, from: &StyleProperties, to: &StyleProperties) -> String {
+        if from == to {
+            return String::new();
+        }
+        if self.build_sgr(to).is_empty() {
+            return "\x1b[0m".to_string();
+        }
+        let removed = |transformations: &[StyleTransformation]| transformations.iter()
+            .any(|transformation| from.transformation.contains(transformation) && !to.transformation.contains(transformation));
+        let underlines = [
+            StyleTransformation::Underlined, StyleTransformation::DoubleUnderlined, StyleTransformation::CurlyUnderlined,
+        ];
+        // the same code turns off bold and dim, and all kinds of underlines
+        let mut codes: Vec<String> = Vec::new();
+        let mut turned_off: Vec<StyleTransformation> = Vec::new();
+        let off_codes: [(&[StyleTransformation], &str); 7] = [
+            (&[StyleTransformation::Bold, StyleTransformation::Dim], "22"),
+            (&[StyleTransformation::Italic], "23"),
+            (&underlines, "24"),
+            (&[StyleTransformation::Blink], "25"),
+            (&[StyleTransformation::Reverse], "27"),
+            (&[StyleTransformation::Hidden], "28"),
+            (&[StyleTransformation::Strikethrough], "29"),
+        ];
+        for (transformations, code) in off_codes.iter() {
+            if removed(transformations) {
+                codes.push(code.to_string());
+                turned_off.extend(transformations.iter().cloned());
+            }
+        }
+
+        // the properties to turn on: new transformations, the ones
+        // turned off by mistake, and colors that changed
+        let mut changes = StyleProperties {
+            transformation: to.transformation.iter()
+                .filter(|transformation| !from.transformation.contains(transformation) || turned_off.contains(transformation))
+                .cloned()
+                .collect(),
+            ..StyleProperties::default()
+        };
+        // bright is part of the text color, so they are written together
+        let bright = StyleTransformation::Bright;
+        if from.transformation.contains(&bright) != to.transformation.contains(&bright) || from.color != to.color {
+            match to.color {
+                Some(ref color) => {
+                    changes.color = Some(color.clone());
+                    if to.transformation.contains(&bright) && !changes.transformation.contains(&bright) {
+                        changes.transformation.push(bright);
+                    }
+                },
+                None => codes.push("39".to_string()),
+            }
+        }
+        if from.background != to.background {
+            match to.background {
+                Some(ref color) => changes.background = Some(color.clone()),
+                None => codes.push("49".to_string()),
+            }
+        }
+        if from.underline_color != to.underline_color {
+            match to.underline_color {
+                Some(ref color) => changes.underline_color = Some(color.clone()),
+                None => codes.push("59".to_string()),
+            }
+        }
+        codes.extend(self.sgr_parameters(&changes));
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+
+    /// Decide what happens when a text is printed or formatted with a
+    /// style that doesn't exist. By default the text is not styled;
+    /// during development, FallbackPolicy::Warn or FallbackPolicy::Error
+    /// help to find typos in style names. This doesn't modify the styles,
+    /// so it is allowed for frozen stylesheets too.
+    /// Fail if the policy is FallbackPolicy::Style with a style that
+    /// doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - What to do with unknown styles.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_fallback(FallbackPolicy::Style("info".to_string())).unwrap();
+    /// // shown as info
+    /// sheet.println("infp", "Typo in the style name");
+    /// ```
+    pub fn set_fallback(&mut self, policy: FallbackPolicy) -> Result<(), StylesheetError> {
+        if let FallbackPolicy::Style(ref style_name) = policy {
+            if !self.contains(style_name) {
+                return Err(StylesheetError::UnknownStyle(style_name.clone()));
+            }
+        }
+        self.fallback = policy;
+        Ok(())
+    }
+
+    /// Return the current FallbackPolicy.
+    pub fn fallback(&self) -> &FallbackPolicy {
+        &self.fallback
+    }
+
+    /// Decide where warnings go, like the ones of FallbackPolicy::Warn.
+    /// By default they are discarded: the library doesn't write to
+    /// stderr on its own. Like set_fallback(), it is allowed for frozen
+    /// stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `handler` - Called with every warning.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_fallback(FallbackPolicy::Warn).unwrap();
+    /// sheet.set_warning_handler(|warning| eprintln!("WARNING: {}", warning));
+    /// sheet.println("infp", "Typo in the style name");
+    /// ```
+    pub fn set_warning_handler<F>(&mut self, handler: F) where F: Fn(&StylesheetError) + Send + Sync + 'static {
+        self.warning_handler = Some(Arc::new(handler));
+    }
+
+    /// Return an error if style_name doesn't exist and the policy is
+    /// FallbackPolicy::Error.
+    fn check_fallback(&self, style_name: &str) -> Result<(), StylesheetError> {
+        if self.fallback == FallbackPolicy::Error
+                && !self.contains(style_name)
+                && style_name != Stylesheet::RAINBOW_STYLE {
+            return Err(StylesheetError::UnknownStyle(style_name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Replace the definition of an existing style.
+    /// If the style extended another style, it won't anymore.
+    /// Styles that extend this style will reflect the change.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to be modified (&str).
+    /// * `style_definition` - The new definition of the style (StyleProperties).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::stylesheet::StyleTransformation::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties {
+    ///     transformation: [Bold].to_vec(), color: Some(Red), background: None, underline_color: None
+    /// });
+    /// // output was redirected: switch to monochrome
+    /// sheet.update_style("danger", StyleProperties {
+    ///     transformation: [Bold].to_vec(), color: None, background: None, underline_color: None
+    /// }).unwrap();
+    /// ```
+    pub fn update_style(
+            &mut self,
+            style_name: &str,
+            style_definition: StyleProperties,
+        ) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        if !self.contains(style_name) {
+            return Err(StylesheetError::UnknownStyle(style_name.to_string()));
+        }
+
+        let style_name = self.real_name(style_name).to_string();
+        let replaced = self.insert_entry(style_name.clone(), style_definition, None).unwrap();
+        let entry = self.styles.get_mut(&style_name).unwrap();
+        entry.prefix = replaced.prefix;
+        entry.icon = replaced.icon;
+        entry.alternates = replaced.alternates;
+        self.resolve();
+        Ok(())
+    }
+
+    /// Set a text that println() and similar methods print before every
+    /// message that uses this style, like "ERROR: ". The prefix has the
+    /// same style as the message. Styles that extend this style inherit
+    /// the prefix, unless they have their own.
+    /// format() and the methods that style parts of a line don't add prefixes.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style (&str).
+    /// * `prefix` - The text to print before messages (&str or String).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_prefix("danger", "ERROR: ").unwrap();
+    /// sheet.println("danger", "Borg cube approaching!");
+    /// ```
+    pub fn set_prefix<S>(&mut self, style_name: &str, prefix: S) -> Result<(), StylesheetError> where S: Into<String> {
+        self.replace_prefix(style_name, Some(prefix.into()))
+    }
+
+    /// Remove the prefix of a style. If it extends a style that has
+    /// a prefix, it will use that prefix.
+    pub fn clear_prefix(&mut self, style_name: &str) -> Result<(), StylesheetError> {
+        self.replace_prefix(style_name, None)
+    }
+
+    /// Return the prefix that is printed before messages that use
+    /// this style, if any, including an inherited prefix.
+    pub fn get_prefix(&self, style_name: &str) -> Option<&str> {
+        match self.styles.get(self.real_name(style_name)) {
+            Some(entry) if !entry.full_prefix.is_empty() => Some(&entry.full_prefix),
+            _ => None,
+        }
+    }
+
+    fn replace_prefix(&mut self, style_name: &str, prefix: Option<String>) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        let real_name = self.real_name(style_name).to_string();
+        match self.styles.get_mut(&real_name) {
+            Some(entry) => entry.prefix = prefix,
+            None => return Err(StylesheetError::UnknownStyle(style_name.to_string())),
+        }
+        self.resolve();
+        Ok(())
+    }
+
+    /// Set an icon that println() and similar methods print before every
+    /// message that uses this style, followed by a space and the prefix,
+    /// if any. If Unicode is not enabled, an ASCII character is printed
+    /// instead of the glyph. Styles that extend this style inherit
+    /// the icon, unless they have their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style (&str).
+    /// * `icon` - The icon to print before messages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::icons::Icon;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_icon("success", Icon::Success).unwrap();
+    /// sheet.println("success", "All systems nominal");
+    /// ```
+    pub fn set_icon(&mut self, style_name: &str, icon: Icon) -> Result<(), StylesheetError> {
+        self.replace_icon(style_name, Some(icon))
+    }
+
+    /// Remove the icon of a style. If it extends a style that has
+    /// an icon, it will use that icon.
+    pub fn clear_icon(&mut self, style_name: &str) -> Result<(), StylesheetError> {
+        self.replace_icon(style_name, None)
+    }
+
+    /// Return the icon that is printed before messages that use
+    /// this style, if any, including an inherited icon.
+    pub fn get_icon(&self, style_name: &str) -> Option<Icon> {
+        self.styles.get(self.real_name(style_name)).and_then(|entry| entry.full_icon)
+    }
+
+    fn replace_icon(&mut self, style_name: &str, icon: Option<Icon>) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        let real_name = self.real_name(style_name).to_string();
+        match self.styles.get_mut(&real_name) {
+            Some(entry) => entry.icon = icon,
+            None => return Err(StylesheetError::UnknownStyle(style_name.to_string())),
+        }
+        self.resolve();
+        Ok(())
+    }
+
+    /// Make red and green styles distinguishable without colors, for
+    /// users who can't tell those colors apart. When enabled, println()
+    /// and similar methods print an icon before the messages of styles
+    /// that have a reddish text color (Icon::Error) or a greenish one
+    /// (Icon::Success), unless the styles have their own icon.
+    /// This doesn't modify the styles, so it is allowed for frozen
+    /// stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - true to print icons before red and green messages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_red_green_icons(true);
+    /// sheet.println("danger", "Shields down");
+    /// sheet.println("success", "Shields up");
+    /// ```
+    pub fn set_red_green_icons(&mut self, enabled: bool) {
+        self.red_green_icons = enabled;
+    }
+
+    /// Return true if red and green styles have icons.
+    /// See set_red_green_icons().
+    pub fn red_green_icons(&self) -> bool {
+        self.red_green_icons
+    }
+
+    /// Return the icon that set_red_green_icons() gives to a style,
+    /// if the option is enabled and the style is red or green.
+    fn red_green_icon(&self, style_name: &str) -> Option<Icon> {
+        if !self.red_green_icons {
+            return None;
+        }
+        let (r, g, b) = self.resolved_properties(self.real_name(style_name)).color?.rgb_value()?;
+        let (r, g, b) = (u16::from(r), u16::from(g), u16::from(b));
+        if r > 100 && r > 2 * g && r > 2 * b {
+            Some(Icon::Error)
+        } else if g > 100 && g > 2 * r && g > 2 * b {
+            Some(Icon::Success)
+        } else {
+            None
+        }
+    }
+
+    /// Set the properties that a style has on terminals that only support
+    /// color_level or fewer colors, instead of its main properties.
+    /// For example, a style can use an Rgb color, and a basic color on
+    /// terminals that don't support truecolor. If a style has alternates
+    /// for several levels, the one for the lowest level that the terminal
+    /// supports is used. The main properties are used on terminals that
+    /// support more colors than all the alternates.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style (&str).
+    /// * `color_level` - The highest color level the alternate is meant for.
+    /// * `style_definition` - The properties to use on those terminals (StyleProperties).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::terminal::ColorLevel;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("brand", StyleProperties::builder().color(Rgb(255, 136, 0)).build());
+    /// sheet.set_alternate("brand", ColorLevel::Ansi256, StyleProperties::builder().color(Fixed(208)).build()).unwrap();
+    /// sheet.set_alternate("brand", ColorLevel::Basic, StyleProperties::builder().bold().color(Yellow).build()).unwrap();
+    /// sheet.println("brand", "Orange everywhere");
+    /// ```
+    pub fn set_alternate(
+            &mut self,
+            style_name: &str,
+            color_level: ColorLevel,
+            style_definition: StyleProperties,
+        ) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        style_definition.check_conflicts()?;
+        let real_name = self.real_name(style_name).to_string();
+        match self.styles.get_mut(&real_name) {
+            Some(entry) => entry.alternates.insert(color_level, style_definition.with_rgb_colors()),
+            None => return Err(StylesheetError::UnknownStyle(style_name.to_string())),
+        };
+        self.resolve();
+        Ok(())
+    }
+
+    /// Remove the alternate of a style for a color level, if any.
+    pub fn remove_alternate(&mut self, style_name: &str, color_level: ColorLevel) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        let real_name = self.real_name(style_name).to_string();
+        match self.styles.get_mut(&real_name) {
+            Some(entry) => entry.alternates.remove(&color_level),
+            None => return Err(StylesheetError::UnknownStyle(style_name.to_string())),
+        };
+        self.resolve();
+        Ok(())
+    }
+
+    /// Return the capabilities of the terminal, as detected when the
+    /// stylesheet was created or set with set_capabilities().
+    pub fn capabilities(&self) -> TerminalCapabilities {
+        self.capabilities
+    }
+
+    /// Override the detected capabilities of the terminal, for example
+    /// to honor a command line option. This also decides which alternates
+    /// are used, see set_alternate(). This doesn't modify the styles, so
+    /// it is allowed for frozen stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `capabilities` - What the terminal supports.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::terminal::TerminalCapabilities;
+    /// let mut sheet = Stylesheet::theme_solarized();
+    /// // safe output for any terminal
+    /// sheet.set_capabilities(TerminalCapabilities::minimal());
+    /// ```
+    pub fn set_capabilities(&mut self, capabilities: TerminalCapabilities) {
+        self.capabilities = capabilities;
+        self.resolve();
+    }
+
+    /// Print the current time before every line printed by println(),
+    /// eprintln(), writeln() and the other methods that print whole lines.
+    /// print(), eprint() and write() don't print timestamps, because they
+    /// can be called multiple times for a single line.
+    /// This doesn't modify the styles, so it is allowed for frozen
+    /// stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The strftime-like format of the timestamp, for example "%H:%M:%S".
+    /// * `style_name` - The style of the timestamp.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.enable_timestamps("%Y-%m-%d %H:%M:%S", "info").unwrap();
+    /// sheet.println("danger", "Borg cube approaching!");
+    /// ```
+    pub fn enable_timestamps<F, S>(&mut self, format: F, style_name: S) -> Result<(), StylesheetError>
+            where F: Into<String>, S: Into<String> {
+        let format: String = format.into();
+        if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+            return Err(StylesheetError::InvalidTimestampFormat(format));
+        }
+        self.timestamp = Some((format, style_name.into()));
+        Ok(())
+    }
+
+    /// Stop printing timestamps.
+    pub fn disable_timestamps(&mut self) {
+        self.timestamp = None;
+    }
+
+    /// Return the styled timestamp followed by a space, or an empty
+    /// string if timestamps are disabled.
+    fn timestamp(&self, colors_enabled: bool) -> String {
+        match self.timestamp {
+            Some((ref format, ref style_name)) => {
+                let now = chrono::Local::now().format(format).to_string();
+                format!("{} ", self.paint_for(style_name, &now, colors_enabled))
+            },
+            None => String::new(),
+        }
+    }
+
+    /// Return message preceded by the icon and the prefix of the style, if any.
+    fn prefixed<'a>(&self, style_name: &str, message: &'a str) -> Cow<'a, str> {
+        let icon = self.get_icon(style_name).or_else(|| self.red_green_icon(style_name));
+        match (icon, self.get_prefix(style_name)) {
+            (Some(icon), prefix) => Cow::Owned(format!("{} {}{}", icon.glyph(self.capabilities.unicode), prefix.unwrap_or(""), message)),
+            (None, Some(prefix)) => Cow::Owned(format!("{}{}", prefix, message)),
+            (None, None) => Cow::Borrowed(message),
+        }
+    }
+
+    /// Remove a style from the stylesheet. After that, printing with
+    /// that style name will use the default style.
+    /// The default style itself can't be removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to be removed (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("info", StyleProperties {
+    ///     transformation: [].to_vec(), color: Some(Green), background: None, underline_color: None
+    /// });
+    /// sheet.remove_style("info").unwrap();
+    /// ```
+    pub fn remove_style(&mut self, style_name: &str) -> Result<(), StylesheetError> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+        if style_name == Stylesheet::DEFAULT_STYLE {
+            return Err(StylesheetError::DefaultStyle);
+        }
+
+        if !self.contains(style_name) {
+            return Err(StylesheetError::UnknownStyle(style_name.to_string()));
+        }
+        if self.aliases.remove(style_name).is_some() {
+            return Ok(());
+        }
+        self.aliases.retain(|_, target| target != style_name);
+
+        // styles that extend the removed one keep their current look
+        let children: Vec<String> = self.styles.iter()
+            .filter(|(_, entry)| entry.parent.as_deref() == Some(style_name))
+            .map(|(name, _)| name.clone())
+            .collect();
+        for child in children {
+            let properties = self.resolved_properties(&child);
+            let levels: Vec<ColorLevel> = self.inheritance_chain(&child).unwrap_or_default().iter()
+                .flat_map(|name| self.styles[name].alternates.keys().cloned())
+                .collect();
+            let alternates: BTreeMap<ColorLevel, StyleProperties> = levels.into_iter()
+                .map(|level| (level, self.resolved_properties_for(&child, Some(level))))
+                .collect();
+            let entry = self.styles.get_mut(&child).unwrap();
+            entry.properties = properties;
+            entry.alternates = alternates;
+            entry.parent = None;
+            if entry.prefix.is_none() && !entry.full_prefix.is_empty() {
+                entry.prefix = Some(entry.full_prefix.clone());
+            }
+            if entry.icon.is_none() {
+                entry.icon = entry.full_icon;
+            }
+        }
+        self.styles.remove(style_name);
+        self.resolve();
+        Ok(())
+    }
+
+    /// Override or add styles based on environment variables, so users
+    /// can recolor the output without any configuration file.
+    /// Every variable whose name starts with ENV_PREFIX defines a style.
+    /// The rest of the name, lowercased, is the style name. The value is a
+    /// comma-separated list of transformations, a text color and a background
+    /// color prefixed with "on_". For example:
+    ///
+    /// ```sh
+    /// CLI_EXP_STYLE_DANGER="bold,red,on_white"
+    /// ```
+    ///
+    /// If any variable is invalid, or the Stylesheet is frozen, an error is
+    /// returned and no style is changed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// if let Err(err) = sheet.apply_env_overrides() {
+    ///     eprintln!("{}", err);
+    /// }
+    /// ```
+    pub fn apply_env_overrides(&mut self) -> Result<(), StylesheetError> {
+        self.apply_overrides(env::vars())
+    }
+
+    /// Apply overrides from (variable, value) pairs, as described for
+    /// apply_env_overrides(). Variables without ENV_PREFIX are ignored.
+    fn apply_overrides<I>(&mut self, vars: I) -> Result<(), StylesheetError>
+        where I: IntoIterator<Item = (String, String)> {
+        if self.is_frozen {
+            return Err(StylesheetError::Frozen);
+        }
+
+        let mut overrides = Vec::new();
+        for (var, value) in vars {
+            let style_name = match var.strip_prefix(Stylesheet::ENV_PREFIX) {
+                Some(suffix) if !suffix.is_empty() => suffix.to_lowercase(),
+                _ => continue,
+            };
+            let properties = Stylesheet::parse_style_spec(&style_name, &var, &value)?;
+            overrides.push((style_name, properties));
+        }
+        for (style_name, properties) in overrides {
+            self.insert_style(style_name, properties);
+        }
+        Ok(())
+    }
+
+    /// Parse a compact style definition like "bold,red,on_white".
+    /// key is only used to report errors.
+    fn parse_style_spec(style_name: &str, key: &str, spec: &str) -> Result<StyleProperties, StylesheetError> {
+        let mut properties = StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None };
+        for token in split_style_spec(spec) {
+            if let Some(background) = token.strip_prefix("on_") {
+                if let Ok(color) = background.parse() {
+                    properties.background = Some(color);
+                    continue;
+                }
+            } else if let Ok(transformation) = token.parse() {
+                properties.transformation.push(transformation);
+                continue;
+            } else if let Some(color) = token.strip_prefix("underline_color(").and_then(|color| color.strip_suffix(')')) {
+                if let Ok(color) = color.parse() {
+                    properties.underline_color = Some(color);
+                    continue;
+                }
+            } else if let Ok(color) = token.parse() {
+                properties.color = Some(color);
+                continue;
+            }
+
+            let mut allowed: Vec<String> = StyleTransformation::NAMES.iter().map(|name| name.to_string()).collect();
+            allowed.extend(allowed_colors());
+            allowed.extend(allowed_colors().iter().map(|name| format!("on_{}", name)));
+            return Err(StylesheetError::InvalidValue {
+                line: None,
+                style: style_name.to_string(),
+                key: key.to_string(),
+                value: token.to_string(),
+                allowed,
+            });
+        }
+        Ok(properties)
+    }
+
+    /// Freeze the Stylesheet. It will not be possible to modify it again.
+    /// However, trying to freeze it will not cause any complain.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::stylesheet::StyleTransformation::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties {
+    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White), underline_color: None
+    /// });
+    /// sheet.freeze();
+    /// ```
+    pub fn freeze(&mut self) {
+        self.is_frozen = true;
+    }
+
+    /// Return true if the stylesheet is frozen.
+    pub fn is_frozen(&self) -> bool {
+        self.is_frozen
+    }
+
+    /// Freeze the Stylesheet and return it as a FrozenStylesheet.
+    /// Unlike freeze(), trying to modify the styles won't compile.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// let sheet = sheet.into_frozen();
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn into_frozen(mut self) -> FrozenStylesheet {
+        self.freeze();
+        FrozenStylesheet { sheet: self }
+    }
+
+    /// Return a copy of the stylesheet that can be modified, even if
+    /// this stylesheet is frozen. Like clone(), the copy has the same
+    /// styles and settings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark().into_frozen();
+    /// let mut log_sheet = sheet.clone_unfrozen();
+    /// log_sheet.set_prefix("danger", "ERROR: ").unwrap();
+    /// ```
+    pub fn clone_unfrozen(&self) -> Stylesheet {
+        let mut sheet = self.clone();
+        sheet.is_frozen = false;
+        sheet
+    }
+
+    /// Return a string with a style applied, instead of printing it.
+    /// Useful to compose lines with more styles, or to pass styled
+    /// texts to other libraries. Like println(), format() respects the
+    /// ColorMode.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// let line = format!("Status: {}", sheet.format("danger", "down"));
+    /// println!("{}", line);
+    /// ```
+    pub fn format<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) -> String where S: AsRef<str> {
+        self.paint(style_name, message.as_ref())
+    }
+
+    /// Return a text with a style applied, that implements Display.
+    /// Unlike format(), the styled text is only built when it is
+    /// written, so it can be passed to format!(), write!() and similar
+    /// macros without allocating a String. Like format(), it respects
+    /// the ColorMode.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The text to style (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// println!("Status: {}, shields: {}", sheet.style("danger", "down"), sheet.style("info", "100%"));
+    /// ```
+    pub fn style<'a>(&'a self, style_name: &'a str, text: &'a str) -> StyledText<'a> {
+        StyledText { sheet: self, style_name, text }
+    }
+
+    /// Same as format(), but with FallbackPolicy::Error it fails
+    /// if the style doesn't exist, instead of using the default style.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_fallback(FallbackPolicy::Error).unwrap();
+    /// assert!(sheet.try_format("infp", "Typo in the style name").is_err());
+    /// ```
+    pub fn try_format<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) -> Result<String, StylesheetError> where S: AsRef<str> {
+        self.check_fallback(style_name)?;
+        Ok(self.format(style_name, message))
+    }
+
+    /// Return a string with styles applied to substrings, using
+    /// the markup syntax described in markup::parse().
+    /// Nested tags combine their styles: in
+    /// "<title>Engines <danger>down</danger></title>" the word "down"
+    /// has the transformations of title and the color of danger.
+    /// When the inner tag is closed, the outer style is restored.
+    /// Texts outside of tags use the default style.
+    ///
+    /// # Arguments
+    ///
+    /// * `markup` - The text to format, containing style tags.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// let line = sheet.format_markup("Shields at <danger>12%</danger>").unwrap();
+    /// ```
+    pub fn format_markup(&self, markup: &str) -> Result<String, MarkupError> {
+        let colors_enabled = self.colors_enabled();
+        let mut formatted = String::new();
+        // the properties of the last text, to only write the changes
+        let mut current = StyleProperties::default();
+        for (style_names, text) in markup::parse_nested(markup)? {
+            if let Some(style_name) = style_names.last() {
+                if style_name == Stylesheet::RAINBOW_STYLE && self.lookup(style_name).is_none() {
+                    // rainbow ends with a reset
+                    formatted.push_str(&self.rainbow(&text, colors_enabled));
+                    current = StyleProperties::default();
+                    continue;
+                }
+            }
+            let properties = self.nested_properties(&style_names);
+            if colors_enabled {
+                formatted.push_str(&self.transition_sgr(&current, &properties));
+            }
+            current = properties;
+            formatted.push_str(&text);
+        }
+        if colors_enabled && !self.build_sgr(&current).is_empty() {
+            formatted.push_str("\x1b[0m");
+        }
+        Ok(formatted)
+    }
+
+    /// Print a line containing style tags. See format_markup().
+    /// If the markup is malformed, nothing is printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `markup` - The text to print, containing style tags.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// sheet.add_style("info", StyleProperties::builder().color(StyleColor::Green).build());
+    /// sheet.println_markup("Shields at <danger>12%</danger>, engines <info>nominal</info>").unwrap();
+    /// ```
+    pub fn println_markup(&self, markup: &str) -> Result<(), MarkupError> {
+        self.output(&self.format_markup(markup)?, true);
+        Ok(())
+    }
+
+    /// Similar to println_markup(), but no newline character is appended.
+    pub fn print_markup(&self, markup: &str) -> Result<(), MarkupError> {
+        self.output(&self.format_markup(markup)?, false);
+        Ok(())
+    }
+
+    /// Return a styled text, with spaces added on the left so that it
+    /// occupies width columns. Escape codes are not counted, so styled
+    /// texts are aligned properly, while format!("{:>10}", ...) wouldn't.
+    /// Texts longer than width are returned without padding.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The string struct or string reference to align.
+    /// * `width` - The number of columns to fill.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// println!("[{}]", sheet.pad_left("danger", "12%", 6));
+    /// ```
+    pub fn pad_left<S>(&self, style_name: &str, text: S, width: usize) -> String where S: AsRef<str> {
+        self.align(style_name, text.as_ref(), width, console::Alignment::Right)
+    }
+
+    /// Same as pad_left(), but spaces are added on the right.
+    pub fn pad_right<S>(&self, style_name: &str, text: S, width: usize) -> String where S: AsRef<str> {
+        self.align(style_name, text.as_ref(), width, console::Alignment::Left)
+    }
+
+    /// Same as pad_left(), but spaces are added on both sides.
+    /// If the spaces can't be split evenly, the extra space goes on the right.
+    pub fn center<S>(&self, style_name: &str, text: S, width: usize) -> String where S: AsRef<str> {
+        self.align(style_name, text.as_ref(), width, console::Alignment::Center)
+    }
+
+    /// Print a styled line, centered in the terminal.
+    /// If stdout is not a terminal, or a writer is set, the line is
+    /// centered in 80 columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.println_centered("info", "Long range scanner report");
+    /// ```
+    pub fn println_centered<S>(&self, style_name: &str, message: S) where S: AsRef<str> {
+        let message = self.prefixed(style_name, message.as_ref());
+        self.output(self.center(style_name, message, self.output_width()).trim_end(), true);
+    }
+
+    /// Same as println_centered(), but the line is aligned to the right.
+    pub fn println_right<S>(&self, style_name: &str, message: S) where S: AsRef<str> {
+        let message = self.prefixed(style_name, message.as_ref());
+        self.output(&self.pad_left(style_name, message, self.output_width()), true);
+    }
+
+    /// Apply a style and pad the result. Spaces are not styled.
+    fn align(&self, style_name: &str, text: &str, width: usize, alignment: console::Alignment) -> String {
+        console::pad_str(&self.paint(style_name, text), width, alignment, None).into_owned()
+    }
+
+    /// Return a styled text, wrapped at width columns. Lines are broken
+    /// between words; words longer than a line are broken too.
+    /// Lines after the first one start with indent spaces, that are not
+    /// styled. Newline characters in text are preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The string struct or string reference to wrap.
+    /// * `width` - The maximum number of columns of a line.
+    /// * `indent` - The hanging indent of lines after the first one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let report = sheet.format_wrapped("info", "Vulcan ship detected in sector 7", 20, 2);
+    /// ```
+    pub fn format_wrapped<S>(
+            &self,
+            style_name: &str,
+            text: S,
+            width: usize,
+            indent: usize,
+        ) -> String where S: AsRef<str> {
+        let indent = " ".repeat(indent);
+        let wrapper = textwrap::Wrapper::new(width.max(indent.len() + 1)).subsequent_indent(&indent);
+        let mut lines: Vec<String> = Vec::new();
+        for paragraph in text.as_ref().split('\n') {
+            let wrapped = wrapper.wrap(paragraph);
+            if wrapped.is_empty() {
+                lines.push(String::new());
+            }
+            for (line_number, line) in wrapped.iter().enumerate() {
+                match line.strip_prefix(indent.as_str()) {
+                    Some(content) if line_number > 0 =>
+                        lines.push(format!("{}{}", indent, self.paint(style_name, content))),
+                    _ => lines.push(self.paint(style_name, line)),
+                }
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Print a styled text, wrapped at the terminal width.
+    /// If stdout is not a terminal, or a writer is set, lines are
+    /// wrapped at 80 columns. See format_wrapped().
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.println_wrapped("info", "Vulcan ship detected. It is not responding to our calls.");
+    /// ```
+    pub fn println_wrapped<S>(&self, style_name: &str, message: S) where S: AsRef<str> {
+        self.println_wrapped_indented(style_name, message, 0);
+    }
+
+    /// Same as println_wrapped(), but lines after the first one
+    /// start with indent spaces.
+    pub fn println_wrapped_indented<S>(&self, style_name: &str, message: S, indent: usize) where S: AsRef<str> {
+        let message = self.prefixed(style_name, message.as_ref());
+        self.output(&self.format_wrapped(style_name, message, self.output_width(), indent), true);
+    }
+
+    /// Return the number of columns of the output: the width of the
+    /// terminal, or 80 if the output is not a terminal or its width
+    /// can't be detected. Printing methods that fill the line, like hr()
+    /// and println_wrapped(), use it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::with_writer(Vec::new());
+    /// assert_eq!(sheet.output_width(), 80);
+    /// ```
+    pub fn output_width(&self) -> usize {
+        const DEFAULT_WIDTH: usize = 80;
+        if self.writer.is_some() {
+            return DEFAULT_WIDTH;
+        }
+        terminal::terminal_width_or(DEFAULT_WIDTH)
+    }
+
+    /// Return a large header, useful to separate sections of the output:
+    /// the text is uppercased and spaced out, between two rules.
+    /// Every line has the style, so that backgrounds form a block.
+    ///
+    /// ```text
+    /// ===================
+    ///   S C A N   L O G
+    /// ===================
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The text of the header.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// println!("{}", sheet.banner("info", "Scan log"));
+    /// ```
+    pub fn banner(&self, style_name: &str, text: &str) -> String {
+        let spaced: Vec<String> = text.to_uppercase()
+            .split_whitespace()
+            .map(|word| word.chars().map(|c| c.to_string()).collect::<Vec<String>>().join(" "))
+            .collect();
+        let title = format!("  {}  ", spaced.join("   "));
+        let rule = "=".repeat(console::measure_text_width(&title));
+        [&rule, &title, &rule].iter()
+            .map(|line| self.paint(style_name, line))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Print a large header. See banner().
+    pub fn println_banner(&self, style_name: &str, text: &str) {
+        self.output(&self.banner(style_name, text), true);
+    }
+
+    /// Return text colored with a gradient: the first character has the
+    /// from color, the last one has the to color, and the others have
+    /// intermediate colors. Terminals that don't support truecolor show
+    /// the most similar colors they support.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - The (red, green, blue) color of the first character.
+    /// * `to` - The (red, green, blue) color of the last character.
+    /// * `text` - The text to color.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// println!("{}", sheet.format_gradient((255, 0, 0), (0, 0, 255), "Scan log"));
+    /// ```
+    pub fn format_gradient(&self, from: (u8, u8, u8), to: (u8, u8, u8), text: &str) -> String {
+        if !self.colors_enabled() || text.is_empty() {
+            return text.to_string();
+        }
+        let steps = text.chars().count().max(2) - 1;
+        let mix = |from: u8, to: u8, step: usize| {
+            (i32::from(from) + (i32::from(to) - i32::from(from)) * step as i32 / steps as i32) as u8
+        };
+        let mut gradient = String::new();
+        for (step, c) in text.chars().enumerate() {
+            // spaces have no color to show
+            if !c.is_whitespace() {
+                let color = StyleColor::Rgb(mix(from.0, to.0, step), mix(from.1, to.1, step), mix(from.2, to.2, step));
+                gradient.push_str(&format!("\x1b[{}m", color.sgr_codes(false, false, self.color_depth()).join(";")));
+            }
+            gradient.push(c);
+        }
+        gradient.push_str("\x1b[0m");
+        gradient
+    }
+
+    /// Print text colored with a gradient. See format_gradient().
+    pub fn println_gradient(&self, from: (u8, u8, u8), to: (u8, u8, u8), text: &str) {
+        self.output(&self.format_gradient(from, to, text), true);
+    }
+
+    /// Return text with the colors of the rainbow, that change at every
+    /// character. This is what RAINBOW_STYLE does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// println!("{}", sheet.format_rainbow("All systems nominal"));
+    /// // same as:
+    /// sheet.println(Stylesheet::RAINBOW_STYLE, "All systems nominal");
+    /// ```
+    pub fn format_rainbow(&self, text: &str) -> String {
+        self.rainbow(text, self.colors_enabled())
+    }
+
+    /// Same as format_rainbow(), but the caller decides if colors are enabled.
+    fn rainbow(&self, text: &str, colors_enabled: bool) -> String {
+        if !colors_enabled || text.is_empty() {
+            return text.to_string();
+        }
+        let mut colors = Stylesheet::RAINBOW_COLORS.iter().cycle();
+        let mut rainbow = String::new();
+        for c in text.chars() {
+            // spaces don't consume colors
+            if !c.is_whitespace() {
+                let color = colors.next().unwrap();
+                rainbow.push_str(&format!("\x1b[{}m", color.sgr_codes(false, false, self.color_depth()).join(";")));
+            }
+            rainbow.push(c);
+        }
+        rainbow.push_str("\x1b[0m");
+        rainbow
+    }
+
+    /// Decide whether decorations like boxes use Unicode characters.
+    /// By default this is enabled if the locale uses UTF-8.
+    /// If disabled, decorations only use ASCII characters.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - true to use Unicode characters.
+    pub fn set_unicode(&mut self, enabled: bool) {
+        self.capabilities.unicode = enabled;
+    }
+
+    /// Return true if decorations use Unicode characters.
+    pub fn unicode_enabled(&self) -> bool {
+        self.capabilities.unicode
+    }
+
+    /// Return lines inside a box, drawn with Unicode box-drawing
+    /// characters or, if they are not enabled, with ASCII characters.
+    /// The box and the lines have the style. Lines can contain escape
+    /// sequences, which are not counted to compute the box width.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `lines` - The lines to put inside the box.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_unicode(false);
+    /// let alert = sheet.format_boxed("danger", &["Borg cube approaching!", "Shields up"]);
+    /// ```
+    pub fn format_boxed<S>(&self, style_name: &str, lines: &[S]) -> String where S: AsRef<str> {
+        let (horizontal, vertical, top_left, top_right, bottom_left, bottom_right) = if self.capabilities.unicode {
+            ("─", "│", "┌", "┐", "└", "┘")
+        } else {
+            ("-", "|", "+", "+", "+", "+")
+        };
+        let width = lines.iter()
+            .map(|line| text::visible_width(line.as_ref()))
+            .max()
+            .unwrap_or(0);
+        let rule = horizontal.repeat(width + 2);
+
+        let mut boxed: Vec<String> = Vec::new();
+        boxed.push(self.paint(style_name, &format!("{}{}{}", top_left, rule, top_right)));
+        for line in lines {
+            let padding = " ".repeat(width - text::visible_width(line.as_ref()));
+            boxed.push(self.paint(style_name, &format!("{} {}{} {}", vertical, line.as_ref(), padding, vertical)));
+        }
+        boxed.push(self.paint(style_name, &format!("{}{}{}", bottom_left, rule, bottom_right)));
+        boxed.join("\n")
+    }
+
+    /// Print lines inside a box. See format_boxed().
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `lines` - The lines to put inside the box.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.print_boxed("danger", &["Borg cube approaching!", "Shields up"]);
+    /// ```
+    pub fn print_boxed<S>(&self, style_name: &str, lines: &[S]) where S: AsRef<str> {
+        self.output(&self.format_boxed(style_name, lines), true);
+    }
+
+    /// Return style_name if it exists, or the default style. Decorations
+    /// use it for optional styles, that must not trigger the FallbackPolicy.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of an optional style.
+    pub fn existing_style<'a>(&self, style_name: &'a str) -> &'a str {
+        if self.contains(style_name) { style_name } else { Stylesheet::DEFAULT_STYLE }
+    }
+
+    /// Return a tree, one node per line, with branches drawn with
+    /// Unicode box-drawing characters or, if they are not enabled, with
+    /// ASCII characters. Labels have the style of their node or, if
+    /// they have none, the style of their depth; branches have
+    /// tree::BRANCH_STYLE. Styles that don't exist are not applied.
+    /// Labels that span several lines are indented under their branch.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root of the tree, printed on the first line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::tree::TreeNode;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.set_unicode(false);
+    /// let root = TreeNode::new("Sector 001")
+    ///     .child(TreeNode::new("Sol").child(TreeNode::new("Borg cube")))
+    ///     .child(TreeNode::new("Wolf 359"));
+    /// assert_eq!(sheet.format_tree(&root), "Sector 001\n|-- Sol\n|   `-- Borg cube\n`-- Wolf 359");
+    /// ```
+    pub fn format_tree(&self, root: &TreeNode) -> String {
+        tree::format_tree(self, root)
+    }
+
+    /// Print a tree. See format_tree().
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root of the tree, printed on the first line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::tree::TreeNode;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let root = TreeNode::new("Sector 001")
+    ///     .child(TreeNode::new("Sol").child(TreeNode::new("Borg cube").with_style("danger")));
+    /// sheet.print_tree(&root);
+    /// ```
+    pub fn print_tree(&self, root: &TreeNode) {
+        self.output(&self.format_tree(root), true);
+    }
+
+    /// Return a list, one item per line. Markers have list::MARKER_STYLE
+    /// and items have their own style, if they exist. Nested lists and
+    /// the lines after the first one of each item are indented under the
+    /// text of the item. See List.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - The list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::list::List;
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// let list = List::numbered().with_start(9).item("Sol").item("Wolf 359");
+    /// assert_eq!(sheet.format_list(&list), " 9. Sol\n10. Wolf 359");
+    /// ```
+    pub fn format_list(&self, list: &List) -> String {
+        list::format_list(self, list)
+    }
+
+    /// Print a list. See format_list().
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - The list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::list::List;
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.print_list(&List::bullets().styled_item("danger", "Borg cube").item("Whormhole"));
+    /// ```
+    pub fn print_list(&self, list: &List) {
+        self.output(&self.format_list(list), true);
+    }
+
+    /// Return the differences between two texts, line by line. Added
+    /// lines start with "+" and have diff::ADDED_STYLE, removed lines
+    /// start with "-" and have diff::REMOVED_STYLE, changed lines start
+    /// with "~", show the old and the new line and have
+    /// diff::CHANGED_STYLE. Lines that are in both texts are not styled.
+    /// See diff::diff_lines().
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The original text.
+    /// * `new` - The modified text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.set_unicode(false);
+    /// assert_eq!(
+    ///     sheet.format_diff("color = \"never\"\nmin_threat = 10", "color = \"never\"\nmin_threat = 50\n"),
+    ///     "  color = \"never\"\n~ min_threat = 10 -> min_threat = 50"
+    /// );
+    /// ```
+    pub fn format_diff(&self, old: &str, new: &str) -> String {
+        diff::format_diff(self, old, new)
+    }
+
+    /// Print the differences between two texts. See format_diff().
+    ///
+    /// # Arguments
+    ///
+    /// * `old` - The original text.
+    /// * `new` - The modified text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.print_diff("Sol: clear\nWolf 359: clear", "Sol: clear\nWolf 359: Borg cube");
+    /// ```
+    pub fn print_diff(&self, old: &str, new: &str) {
+        self.output(&self.format_diff(old, new), true);
+    }
+
+    /// Return a Markdown text rendered for the terminal: headings,
+    /// emphasis, code and links have the styles of the markdown module,
+    /// if they exist, paragraphs are wrapped to width, lists are indented
+    /// like format_list() does and quotes are preceded by a bar.
+    /// Code blocks are indented; if their language is supported by the
+    /// syntax module, they are highlighted. See markdown::parse() for
+    /// the supported syntax.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The Markdown text.
+    /// * `width` - The maximum width of paragraphs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::markdown;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// markdown::add_markdown_styles(&mut sheet);
+    /// println!("{}", sheet.format_markdown("# Scan report\n\n- **Borg cube** in sector *001*", 80));
+    /// ```
+    pub fn format_markdown(&self, source: &str, width: usize) -> String {
+        markdown::format_markdown(self, source, width)
+    }
+
+    /// Print a Markdown text, with paragraphs wrapped to the width of the
+    /// terminal. See format_markdown().
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The Markdown text.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.print_markdown("## Threats\n\n1. Borg cube\n2. Romulan warbird");
+    /// ```
+    pub fn print_markdown(&self, source: &str) {
+        self.output(&self.format_markdown(source, self.output_width()), true);
+    }
+
+    /// Return short texts laid out in as many columns as fit in width,
+    /// like ls does: the texts go down the first column, then down the
+    /// second one, and so on. Columns are as wide as their widest text,
+    /// and separated by two spaces. Texts wider than width get a line
+    /// of their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - A list of (style name, text) pairs.
+    /// * `width` - The maximum width of the lines.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// let sectors = [("info", "001"), ("info", "002"), ("danger", "359"), ("info", "4")];
+    /// assert_eq!(sheet.format_columns(&sectors, 10), "001  359\n002  4");
+    /// ```
+    pub fn format_columns(&self, items: &[(&str, &str)], width: usize) -> String {
+        columns::format_columns(self, items, width)
+    }
+
+    /// Print short texts in as many columns as fit in the terminal.
+    /// See format_columns().
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - A list of (style name, text) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let names: Vec<(&str, &str)> = sheet.style_names().into_iter().map(|name| (name, name)).collect();
+    /// sheet.print_columns(&names);
+    /// ```
+    pub fn print_columns(&self, items: &[(&str, &str)]) {
+        if !items.is_empty() {
+            self.output(&self.format_columns(items, self.output_width()), true);
+        }
+    }
+
+    /// Return a heatmap: one line per row, with its label aligned to the
+    /// left and its cells. Cells are heatmap::CELL_WIDTH characters wide,
+    /// with the background color of their value in the ramp. If colors
+    /// are not enabled, cells are filled with heatmap::SHADES, or with
+    /// heatmap::ASCII_SHADES if Unicode is not enabled either.
+    ///
+    /// # Arguments
+    ///
+    /// * `heatmap` - The Heatmap to format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::heatmap::Heatmap;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.set_color_mode(ColorMode::Never);
+    /// sheet.set_unicode(false);
+    /// let heatmap = Heatmap::new().row("Sol", vec![0.0, 2.0]).row("Vulcan", vec![4.0, f64::NAN]);
+    /// assert_eq!(sheet.format_heatmap(&heatmap), "Sol      ::\nVulcan ##  ");
+    /// ```
+    pub fn format_heatmap(&self, heatmap: &Heatmap) -> String {
+        heatmap::format_heatmap(self, heatmap)
+    }
+
+    /// Print a heatmap. See format_heatmap().
+    ///
+    /// # Arguments
+    ///
+    /// * `heatmap` - The Heatmap to print.
+    pub fn print_heatmap(&self, heatmap: &Heatmap) {
+        if let Err(err) = self.try_print_heatmap(heatmap) {
+            panic!("FATAL: {}", err);
+        }
+    }
+
+    /// Same as print_heatmap(), but write errors are returned.
+    pub fn try_print_heatmap(&self, heatmap: &Heatmap) -> io::Result<()> {
+        if heatmap.rows.is_empty() {
+            return Ok(());
+        }
+        self.try_output(&self.format_heatmap(heatmap), true)
+    }
+
+    /// Return a horizontal rule that occupies width columns, made of
+    /// a repeated character. If the character is wide, the rule can
+    /// be one column shorter.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `character` - The character that forms the rule.
+    /// * `width` - The number of columns.
+    pub fn format_hr(&self, style_name: &str, character: char, width: usize) -> String {
+        let character_width = console::measure_text_width(&character.to_string()).max(1);
+        self.paint(style_name, &character.to_string().repeat(width / character_width))
+    }
+
+    /// Print a horizontal rule as wide as the terminal, to separate
+    /// parts of the output. The rule is made of "─", or "-" if Unicode
+    /// is not enabled. If stdout is not a terminal, or a writer is set,
+    /// the rule is 80 columns wide.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.hr("info");
+    /// sheet.hr_with("info", '=');
+    /// ```
+    pub fn hr(&self, style_name: &str) {
+        self.hr_with(style_name, if self.capabilities.unicode { '─' } else { '-' });
+    }
+
+    /// Same as hr(), but the rule is made of the given character.
+    pub fn hr_with(&self, style_name: &str, character: char) {
+        self.output(&self.format_hr(style_name, character, self.output_width()), true);
+    }
+
+    /// Decide whether links are printed as clickable OSC 8 hyperlinks.
+    /// By default this is enabled for terminals known to support them.
+    /// Hyperlinks are only printed when colors are enabled; otherwise,
+    /// or if this is disabled, links are printed as "text (url)".
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - true to print hyperlinks.
+    pub fn set_hyperlinks(&mut self, enabled: bool) {
+        self.capabilities.hyperlinks = enabled;
+    }
+
+    /// Return a styled text that links to url. See set_hyperlinks().
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The text to show.
+    /// * `url` - The link destination.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let link = sheet.format_link("info", "Star chart", "https://example.com/chart");
+    /// ```
+    pub fn format_link(&self, style_name: &str, text: &str, url: &str) -> String {
+        if self.capabilities.hyperlinks && self.colors_enabled() {
+            format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, self.paint(style_name, text))
+        } else {
+            self.paint(style_name, &format!("{} ({})", text, url))
+        }
+    }
+
+    /// Print a line containing a link. See format_link().
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `text` - The text to show.
+    /// * `url` - The link destination.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.println_link("info", "Star chart", "https://example.com/chart");
+    /// ```
+    pub fn println_link(&self, style_name: &str, text: &str, url: &str) {
+        self.output(&self.format_link(style_name, text, url), true);
+    }
+
+    /// Return a string made of several texts, each one with its style.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments` - A list of (style name, text) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let line = sheet.format_segments(&[("success", "[ OK ]"), ("info", " service started")]);
+    /// ```
+    pub fn format_segments(&self, segments: &[(&str, &str)]) -> String {
+        segments.iter()
+            .map(|(style_name, text)| self.paint(style_name, text))
+            .collect()
+    }
+
+    /// Print several texts, each one with its style, on a single line.
+    ///
+    /// # Arguments
+    ///
+    /// * `segments` - A list of (style name, text) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.print_segments(&[("success", "[ OK ]"), ("info", " service started")]);
+    /// ```
+    pub fn print_segments(&self, segments: &[(&str, &str)]) {
+        self.output(&self.format_segments(segments), true);
+    }
+
+    /// Return a StyledString with the escape sequences of its styles.
+    /// Texts without a style get the default style.
+    ///
+    /// # Arguments
+    ///
+    /// * `string` - The StyledString to format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::styled_string::StyledString;
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let line = StyledString::styled("danger", "Shields") + " down";
+    /// let text = sheet.format_styled(&line);
+    /// ```
+    pub fn format_styled(&self, string: &StyledString) -> String {
+        string.iter()
+            .map(|(style_name, text)| {
+                let style_name = style_name.as_deref().unwrap_or(Stylesheet::DEFAULT_STYLE);
+                self.paint(style_name, text)
+            })
+            .collect()
+    }
+
+    /// Print a StyledString on a single line. See format_styled().
+    ///
+    /// # Arguments
+    ///
+    /// * `string` - The StyledString to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::styled_string::StyledString;
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.println_styled(&(StyledString::styled("success", "[ OK ]") + " service started"));
+    /// ```
+    pub fn println_styled(&self, string: &StyledString) {
+        self.output(&self.format_styled(string), true);
+    }
+
+    /// Set the rules used by format_highlighted() and println_highlighted().
+    /// This doesn't modify the styles, so it is allowed for frozen
+    /// stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `rules` - The rules that associate patterns to style names.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::highlight::HighlightRules;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// let mut rules = HighlightRules::new();
+    /// rules.add_rule(r"(?i)error", "danger").unwrap();
+    /// sheet.set_highlight_rules(rules);
+    /// ```
+    pub fn set_highlight_rules(&mut self, rules: HighlightRules) {
+        self.highlight_rules = rules;
+    }
+
+    /// Return the rules set with set_highlight_rules().
+    pub fn highlight_rules(&self) -> &HighlightRules {
+        &self.highlight_rules
+    }
+
+    /// Return text with the styles of the highlight rules applied to
+    /// the parts that match them. Other parts use the default style.
+    /// See set_highlight_rules().
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to highlight.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::highlight::HighlightRules;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// let mut rules = HighlightRules::new();
+    /// rules.add_rule(r"\d+ ms", "info").unwrap();
+    /// sheet.set_highlight_rules(rules);
+    /// let line = sheet.format_highlighted("Reply received in 42 ms");
+    /// ```
+    pub fn format_highlighted(&self, text: &str) -> String {
+        self.highlight_rules.segments(text).into_iter()
+            .map(|(style_name, text)| self.paint(style_name.unwrap_or(Stylesheet::DEFAULT_STYLE), text))
+            .collect()
+    }
+
+    /// Print a line with the styles of the highlight rules applied to
+    /// the parts that match them. See format_highlighted().
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::highlight::HighlightRules;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// let mut rules = HighlightRules::new();
+    /// rules.add_rule(r"(?i)error", "danger").unwrap();
+    /// sheet.set_highlight_rules(rules);
+    /// sheet.println_highlighted("Error: warp core breach");
+    /// ```
+    pub fn println_highlighted(&self, text: &str) {
+        self.output(&self.format_highlighted(text), true);
+    }
+
+    /// Read lines from input until its end, and print them highlighted
+    /// with the highlight rules, like println_highlighted() does. This
+    /// allows to use a program as a colorizing filter in a pipeline.
+    /// Lines are read and printed one at a time, so the used memory
+    /// doesn't depend on the input size, and a slow output makes the
+    /// input be read more slowly. Escape sequences already present
+    /// in the input are removed; invalid UTF-8 sequences are replaced
+    /// with U+FFFD.
+    ///
+    /// Return the number of printed lines. Stop without an error if
+    /// the output is closed, like when piping into head.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - Where lines are read, like a locked stdin.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::io;
+    /// use common::highlight::HighlightRules;
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// let mut rules = HighlightRules::new();
+    /// rules.add_rule(r"(?i)error", "danger").unwrap();
+    /// sheet.set_highlight_rules(rules);
+    /// let stdin = io::stdin();
+    /// sheet.highlight_lines(stdin.lock()).unwrap();
+    /// ```
+    pub fn highlight_lines<R>(&self, mut input: R) -> io::Result<usize> where R: BufRead {
+        let is_broken_pipe = |err: &io::Error| err.kind() == io::ErrorKind::BrokenPipe;
+        // the same buffer is reused for all lines
+        let mut line: Vec<u8> = Vec::new();
+        let mut count = 0;
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            let text = String::from_utf8_lossy(&line);
+            let text = text.strip_suffix('\n').unwrap_or(&text);
+            let text = text.strip_suffix('\r').unwrap_or(text);
+            match self.try_output(&self.format_highlighted(&ansi::strip(text)), true) {
+                Ok(()) => count += 1,
+                Err(ref err) if is_broken_pipe(err) => return Ok(count),
+                Err(err) => return Err(err),
+            }
+        }
+        match self.flush() {
+            Err(err) if !is_broken_pipe(&err) => Err(err),
+            _ => Ok(count),
+        }
+    }
+
+    /// Return the colored output of another program, restyled with this
+    /// stylesheet. Texts whose color appears in colors get the style
+    /// associated to it, so that the red of another tool becomes our
+    /// danger style. Other texts keep their original style, adapted to
+    /// the terminal color depth. Escape sequences that don't change
+    /// styles, like cursor movements, are removed.
+    /// If colors are disabled, the plain text is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The output to restyle, with its escape sequences.
+    /// * `colors` - A list of (text color, style name) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let output = "\x1b[31mFAILED\x1b[0m 3 tests";
+    /// let line = sheet.format_remapped(output, &[(StyleColor::Red, "danger"), (StyleColor::Green, "success")]);
+    /// ```
+    pub fn format_remapped(&self, text: &str, colors: &[(StyleColor, &str)]) -> String {
+        let colors_enabled = self.colors_enabled();
+        let mut properties = StyleProperties::default();
+        let mut result = String::new();
+        for segment in ansi::segments(text) {
+            match segment {
+                ansi::Segment::Escape(escape) => {
+                    if let Some(parameters) = ansi::sgr_parameters(escape) {
+                        ansi::apply_sgr(parameters, &mut properties);
+                    }
+                },
+                ansi::Segment::Text(text) => {
+                    let mapped = colors.iter().find(|(color, _)| properties.color.as_ref() == Some(color));
+                    if let Some((_, style_name)) = mapped {
+                        result.push_str(&self.paint_for(style_name, text, colors_enabled));
+                        continue;
+                    }
+                    let sgr = self.build_sgr(&properties);
+                    if sgr.is_empty() || !colors_enabled {
+                        result.push_str(text);
+                    } else {
+                        result.push_str(&format!("{}{}\x1b[0m", sgr, text));
+                    }
+                },
+            }
+        }
+        result
+    }
+
+    /// Print the colored output of another program, restyled with this
+    /// stylesheet. See format_remapped().
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The output to restyle, with its escape sequences.
+    /// * `colors` - A list of (text color, style name) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.println_remapped("\x1b[1;32mPASSED\x1b[0m", &[(StyleColor::Green, "success")]);
+    /// ```
+    pub fn println_remapped(&self, text: &str, colors: &[(StyleColor, &str)]) {
+        self.output(&self.format_remapped(text, colors), true);
+    }
+
+    /// Return a string built from a template, like format!() does,
+    /// where every placeholder can specify a style:
+    /// "{}" inserts an argument without style, "{:danger}" inserts
+    /// it with the danger style. "{{" and "}}" are literal braces.
+    /// Usually this is called by the styled!() and sprintln!() macros.
+    ///
+    /// Panics if the template is malformed or the number of arguments
+    /// doesn't match the placeholders, as format!() wouldn't compile.
+    /// For templates that are not written in the code, like the ones
+    /// read from configuration files, use try_format_template().
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The text to format, containing placeholders.
+    /// * `args` - The values to insert, in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// let line = sheet.format_template("Found {:danger} in sector {}", &[&"Borg cube", &7]);
+    /// ```
+    pub fn format_template(&self, template: &str, args: &[&dyn fmt::Display]) -> String {
+        self.try_format_template(template, args)
+            .unwrap_or_else(|err| panic!("FATAL: {} in template: {}", err, template))
+    }
+
+    /// Same as format_template(), but a malformed template, or a number
+    /// of arguments that doesn't match the placeholders, returns an error
+    /// instead of causing a panic. Usually this is called by the
+    /// try_styled!() macro.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// assert_eq!(sheet.try_format_template("{} ships", &[&2]).unwrap(), "2 ships");
+    /// assert_eq!(sheet.try_format_template("{} ships", &[]), Err(TemplateError::NotEnoughArguments));
+    /// ```
+    pub fn try_format_template(&self, template: &str, args: &[&dyn fmt::Display]) -> Result<String, TemplateError> {
+        let mut formatted = String::new();
+        let mut args = args.iter();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    formatted.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    formatted.push('}');
+                },
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return Err(TemplateError::UnterminatedPlaceholder),
+                        }
+                    }
+                    let style_name = if spec.is_empty() {
+                        Stylesheet::DEFAULT_STYLE
+                    } else {
+                        match spec.strip_prefix(':') {
+                            Some(style_name) => style_name,
+                            None => return Err(TemplateError::InvalidPlaceholder(spec)),
+                        }
+                    };
+                    let arg = args.next().ok_or(TemplateError::NotEnoughArguments)?;
+                    formatted.push_str(&self.paint(style_name, &arg.to_string()));
+                },
+                '}' => return Err(TemplateError::UnmatchedBrace),
+                _ => formatted.push(c),
+            }
+        }
+        if args.next().is_some() {
+            return Err(TemplateError::TooManyArguments);
+        }
+        Ok(formatted)
+    }
+
+    /// Print a line built with format_template().
+    /// Usually this is called by the sprintln!() macro.
+    pub fn println_template(&self, template: &str, args: &[&dyn fmt::Display]) {
+        self.output(&self.format_template(template, args), true);
+    }
+
+    /// Write a message to out, applying to it a single style.
+    /// No newline character is appended. This ignores the writer
+    /// set with with_writer().
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to write.
+    /// * `out` - Any io::Write implementor.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::new();
+    /// let mut buffer: Vec<u8> = Vec::new();
+    /// sheet.write("danger", "Some text", &mut buffer).unwrap();
+    /// ```
+    pub fn write<S, W>(
+            &self,
+            style_name: &str,
+            message: S,
+            out: &mut W,
+        ) -> io::Result<()> where S: AsRef<str>, W: Write + ?Sized {
+        out.write_all(self.paint(style_name, &self.prefixed(style_name, message.as_ref())).as_bytes())
+    }
+
+    /// Same as write(), but a newline character is appended.
+    pub fn writeln<S, W>(
+            &self,
+            style_name: &str,
+            message: S,
+            out: &mut W,
+        ) -> io::Result<()> where S: AsRef<str>, W: Write + ?Sized {
+        writeln!(
+            out, "{}{}",
+            self.timestamp(self.colors_enabled()),
+            self.paint(style_name, &self.prefixed(style_name, message.as_ref()))
+        )
+    }
+
+    /// Write an already styled text to the writer passed to with_writer(),
+    /// or to stdout. Write errors make us panic, like println!() does.
+    fn output(&self, text: &str, newline: bool) {
+        if let Err(err) = self.try_output(text, newline) {
+            panic!("FATAL: {}", err);
+        }
+    }
+
+    /// Same as output(), but write errors are returned.
+    fn try_output(&self, text: &str, newline: bool) -> io::Result<()> {
+        let text = if newline {
+            Cow::Owned(format!("{}{}\n", self.timestamp(self.colors_enabled()), text))
+        } else {
+            Cow::Borrowed(text)
+        };
+        match self.writer {
+            Some(ref writer) => {
+                let mut out = writer.lock().unwrap_or_else(|err| err.into_inner());
+                out.write_all(text.as_bytes())?;
+                if self.auto_flush {
+                    out.flush()?;
+                }
+            },
+            None => {
+                let stdout = io::stdout();
+                let mut out = stdout.lock();
+                out.write_all(text.as_bytes())?;
+                if self.auto_flush {
+                    out.flush()?;
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Print a line (string reference), applying to it a single style.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::stylesheet::StyleTransformation::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties {
+    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White), underline_color: None
+    /// });
+    /// sheet.println("danger", "Some text");
+    /// ```
+    pub fn println<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) where S: AsRef<str> {
+        self.output(&self.paint(style_name, &self.prefixed(style_name, message.as_ref())), true);
+    }
+
+    /// Same as println(), but write errors are returned instead of causing
+    /// a panic. For example, writing to a closed pipe returns an error of
+    /// kind io::ErrorKind::BrokenPipe. With FallbackPolicy::Error, a style
+    /// that doesn't exist returns an error of kind
+    /// io::ErrorKind::InvalidInput, that contains StylesheetError::UnknownStyle.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io;
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// match sheet.try_println("info", "Some text") {
+    ///     Err(ref err) if err.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+    ///     result => result.unwrap(),
+    /// }
+    /// ```
+    pub fn try_println<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) -> io::Result<()> where S: AsRef<str> {
+        self.check_fallback(style_name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.try_output(&self.paint(style_name, &self.prefixed(style_name, message.as_ref())), true)
+    }
+
+    /// Same as try_println(), but no newline character is appended.
+    pub fn try_print<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) -> io::Result<()> where S: AsRef<str> {
+        self.check_fallback(style_name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        self.try_output(&self.paint(style_name, &self.prefixed(style_name, message.as_ref())), false)
+    }
+
+    /// Print text as it is: no style, no prefix, no timestamp and no
+    /// newline character. Useful for texts that are already formatted,
+    /// like the lines of progress bars, and for escape sequences that
+    /// move the cursor.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let line = sheet.format("info", "Scanning... ");
+    /// sheet.try_print_raw(&line).unwrap();
+    /// ```
+    pub fn try_print_raw(&self, text: &str) -> io::Result<()> {
+        self.try_output(text, false)
+    }
+
+    /// Same as try_print_raw(), but it panics if the text can't be written.
+    pub fn print_raw(&self, text: &str) {
+        self.output(text, false);
+    }
+
+    /// Print content through a pager, so that long texts can be scrolled.
+    /// The pager is the command in the PAGER environment variable, run
+    /// by the shell like git does, or "less -R". If LESS is not set, it is
+    /// set to "FRX": less keeps the colors, exits immediately if content
+    /// fits in the screen, and doesn't clear the screen when it exits.
+    /// The content is printed directly if the output is not a terminal or
+    /// is a writer, if PAGER is empty or "cat", or if the pager can't be
+    /// started or is not found. Quitting the pager before the end is not
+    /// an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The text to print, possibly containing escape sequences.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// let log: Vec<String> = (1..=500).map(|n| sheet.format("info", format!("Event {}", n))).collect();
+    /// sheet.print_paged(&log.join("\n")).unwrap();
+    /// ```
+    pub fn print_paged(&self, content: &str) -> io::Result<()> {
+        let command = match pager_command(env::var("PAGER").ok()) {
+            Some(command) if self.writer.is_none() && self.is_tty => command,
+            _ => return self.try_print_raw(content),
+        };
+        // what was printed before must appear before the pager starts
+        self.flush()?;
+        self.page(&command, content)
+    }
+
+    /// Pipe content to a pager command, returned by pager_command().
+    /// The content is printed directly if the pager can't be started,
+    /// or if the shell can't find or run it.
+    fn page(&self, command: &[String], content: &str) -> io::Result<()> {
+        let mut pager = process::Command::new(&command[0]);
+        pager.args(&command[1..]).stdin(process::Stdio::piped());
+        if env::var_os("LESS").is_none() {
+            pager.env("LESS", "FRX");
+        }
+        let mut child = match pager.spawn() {
+            Ok(child) => child,
+            Err(_) => return self.try_print_raw(content),
+        };
+        if let Some(mut input) = child.stdin.take() {
+            match input.write_all(content.as_bytes()) {
+                Err(ref err) if err.kind() == io::ErrorKind::BrokenPipe => (),
+                result => result?,
+            }
+            // input is closed here, so the pager sees the end of the content
+        }
+        let status = child.wait()?;
+        if status.code().is_some_and(|code| PAGER_NOT_FOUND_CODES.contains(&code)) {
+            return self.try_print_raw(content);
+        }
+        Ok(())
+    }
+
+    /// Show a countdown, like "Self-destruct in 00:10", updated in place
+    /// once per second, and return when it reaches 00:00. Useful for
+    /// retries with backoff, too. If the output is not a terminal, the
+    /// line is printed once, at the beginning; the function still waits.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style of the line.
+    /// * `seconds` - The duration of the countdown.
+    /// * `label` - The text before the remaining time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.count_down("danger", 1, "Self-destruct in").unwrap();
+    /// sheet.println("danger", "Boom");
+    /// ```
+    pub fn count_down(&self, style_name: &str, seconds: u64, label: &str) -> io::Result<()> {
+        let start = Instant::now();
+        let interactive = self.is_interactive();
+        if !interactive {
+            self.try_print_raw(&format!("{}\n", self.countdown_line(style_name, label, seconds)))?;
+        }
+        for remaining in (0..=seconds).rev() {
+            if interactive {
+                self.try_print_raw(&term::replace_line(&self.countdown_line(style_name, label, remaining)))?;
+                self.flush()?;
+            }
+            if remaining > 0 {
+                // sleep until the next second, so that the delays don't add up
+                let next = Duration::from_secs(seconds - remaining + 1);
+                thread::sleep(next.checked_sub(start.elapsed()).unwrap_or_default());
+            }
+        }
+        if interactive {
+            self.try_print_raw("\n")?;
+        }
+        self.flush()
+    }
+
+    /// Return the line of a countdown, with remaining seconds left.
+    fn countdown_line(&self, style_name: &str, label: &str, remaining: u64) -> String {
+        self.format(style_name, format!("{} {}", label, progress::format_duration(Duration::from_secs(remaining))))
+    }
+
+    /// Similar to println(), but print() doesn't append a newline character.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::stylesheet::StyleColor::*;
+    /// use common::stylesheet::StyleTransformation::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties {
+    ///     transformation: [Bold, Blink,].to_vec(), color: Some(Red), background: Some(White), underline_color: None
+    /// });
+    /// sheet.print("danger", "Some text");
+    /// ```
+    pub fn print<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) where S: AsRef<str> {
+        self.output(&self.paint(style_name, &self.prefixed(style_name, message.as_ref())), false);
+    }
+
+    /// Make sure that everything printed so far is shown. Texts without a
+    /// newline character could otherwise stay in a buffer. This is not
+    /// needed if auto-flush is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.print("info", "Scanning... ");
+    /// sheet.flush().unwrap();
+    /// ```
+    pub fn flush(&self) -> io::Result<()> {
+        match self.writer {
+            Some(ref writer) => writer.lock().unwrap_or_else(|err| err.into_inner()).flush(),
+            None => io::stdout().flush(),
+        }
+    }
+
+    /// Decide whether the output is flushed after every print, so that
+    /// texts without a newline character are shown immediately. This is
+    /// useful for spinners and progress indicators. Disabled by default.
+    /// This doesn't modify the styles, so it is allowed for frozen
+    /// stylesheets too.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - true to flush after every print.
+    pub fn set_auto_flush(&mut self, enabled: bool) {
+        self.auto_flush = enabled;
+    }
+
+    /// Return true if the output is flushed after every print.
+    pub fn auto_flush(&self) -> bool {
+        self.auto_flush
+    }
+
+    /// Same as println(), but it always writes to stderr.
+    /// Styles are applied if stderr is a terminal, regardless of stdout.
+    ///
+    /// # Arguments
+    ///
+    /// * `style_name` - The name of the style to use (&str).
+    /// * `message` - The string struct or string reference to print.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let mut sheet = Stylesheet::new();
+    /// sheet.add_style("danger", StyleProperties::builder().color(StyleColor::Red).build());
+    /// sheet.eprintln("danger", "Something went wrong");
+    /// ```
+    pub fn eprintln<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) where S: AsRef<str> {
+        let colors_enabled = self.colors_enabled_stderr();
+        eprintln!(
+            "{}{}",
+            self.timestamp(colors_enabled),
+            self.paint_for(style_name, &self.prefixed(style_name, message.as_ref()), colors_enabled)
+        );
+    }
+
+    /// Similar to eprintln(), but eprint() doesn't append a newline character.
+    pub fn eprint<S>(
+            &self,
+            style_name: &str,
+            message: S,
+        ) where S: AsRef<str> {
+        eprint!("{}", self.paint_for(style_name, &self.prefixed(style_name, message.as_ref()), self.colors_enabled_stderr()));
+    }
+}
+
+impl Default for Stylesheet {
+    fn default() -> Self {
+        Stylesheet::new()
+    }
+}
+
+/// A clone has the same styles and settings, and it is frozen if the
+/// original is frozen (see Stylesheet::clone_unfrozen()). It shares the
+/// writer, if any: use set_writer() to give it a different output.
+impl Clone for Stylesheet {
+    fn clone(&self) -> Self {
+        let warned = self.warned.lock().unwrap_or_else(|err| err.into_inner()).clone();
+        Stylesheet {
+            styles: self.styles.clone(),
+            aliases: self.aliases.clone(),
+            is_frozen: self.is_frozen,
+            capabilities: self.capabilities,
+            no_color: self.no_color,
+            clicolor_force: self.clicolor_force,
+            clicolor_disabled: self.clicolor_disabled,
+            is_tty: self.is_tty,
+            stderr_is_tty: self.stderr_is_tty,
+            color_mode: self.color_mode,
+            plain: self.plain,
+            timestamp: self.timestamp.clone(),
+            writer: self.writer.clone(),
+            keep_ansi: self.keep_ansi,
+            auto_flush: self.auto_flush,
+            red_green_icons: self.red_green_icons,
+            highlight_rules: self.highlight_rules.clone(),
+            fallback: self.fallback.clone(),
+            warned: Mutex::new(warned),
+            warning_handler: self.warning_handler.clone(),
+        }
+    }
+}
+
+/// Styles are shown as in view(), followed by the settings.
+impl fmt::Debug for Stylesheet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let aliases: BTreeMap<&String, &String> = self.aliases.iter().collect();
+        f.debug_struct("Stylesheet")
+            .field("styles", &self.view().styles)
+            .field("aliases", &aliases)
+            .field("is_frozen", &self.is_frozen)
+            .field("capabilities", &self.capabilities)
+            .field("no_color", &self.no_color)
+            .field("clicolor_force", &self.clicolor_force)
+            .field("clicolor_disabled", &self.clicolor_disabled)
+            .field("is_tty", &self.is_tty)
+            .field("stderr_is_tty", &self.stderr_is_tty)
+            .field("color_mode", &self.color_mode)
+            .field("plain", &self.plain)
+            .field("timestamp", &self.timestamp)
+            .field("writer", &self.writer.as_ref().map(|_| "..."))
+            .field("keep_ansi", &self.keep_ansi)
+            .field("auto_flush", &self.auto_flush)
+            .field("red_green_icons", &self.red_green_icons)
+            .field("highlight_rules", &self.highlight_rules)
+            .field("fallback", &self.fallback)
+            .field("warning_handler", &self.warning_handler.as_ref().map(|_| "..."))
+            .finish()
+    }
+}
+
+/// A text with a style, returned by Stylesheet::style().
+/// The escape sequences are written when it is displayed.
+#[derive(Clone, Copy)]
+pub struct StyledText<'a> {
+    sheet: &'a Stylesheet,
+    style_name: &'a str,
+    text: &'a str,
+}
+
+impl<'a> fmt::Display for StyledText<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let colors_enabled = self.sheet.colors_enabled();
+        match self.sheet.lookup(self.style_name) {
+            None => f.write_str(&self.sheet.rainbow(self.text, colors_enabled)),
+            Some(entry) if entry.sgr.is_empty() || !colors_enabled => f.write_str(self.text),
+            Some(entry) => write!(f, "{}{}\x1b[0m", entry.sgr, self.text),
+        }
+    }
+}
+
+/// A frozen Stylesheet, returned by Stylesheet::into_frozen().
+/// It can be used as a &Stylesheet, so all the print and format
+/// methods are available, but the styles can't be modified.
+#[derive(Clone, Debug)]
+pub struct FrozenStylesheet {
+    sheet: Stylesheet,
+}
+
+impl FrozenStylesheet {
+    /// Same as Stylesheet::set_color_mode().
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.sheet.set_color_mode(mode);
+    }
+
+    /// Same as Stylesheet::set_plain().
+    pub fn set_plain(&mut self, plain: bool) {
+        self.sheet.set_plain(plain);
+    }
+
+    /// Same as Stylesheet::set_auto_flush().
+    pub fn set_auto_flush(&mut self, enabled: bool) {
+        self.sheet.set_auto_flush(enabled);
+    }
+
+    /// Same as Stylesheet::set_highlight_rules().
+    pub fn set_highlight_rules(&mut self, rules: HighlightRules) {
+        self.sheet.set_highlight_rules(rules);
+    }
+
+    /// Same as Stylesheet::set_red_green_icons().
+    pub fn set_red_green_icons(&mut self, enabled: bool) {
+        self.sheet.set_red_green_icons(enabled);
+    }
+}
+
+impl ::std::ops::Deref for FrozenStylesheet {
+    type Target = Stylesheet;
+
+    fn deref(&self) -> &Stylesheet {
+        &self.sheet
+    }
+}
+
+impl<'a> IntoIterator for &'a Stylesheet {
+    type Item = (&'a str, &'a StyleProperties);
+    type IntoIter = ::std::vec::IntoIter<(&'a str, &'a StyleProperties)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A Stylesheet is serialized as its StylesheetView.
+impl Serialize for Stylesheet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.view().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Stylesheet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let view = StylesheetView::deserialize(deserializer)?;
+        Stylesheet::from_view(&view).map_err(de::Error::custom)
+    }
+}
+
+
+#[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
+mod tests {
+    use super::*;
+    use super::StyleColor::*;
+    use super::StyleTransformation::*;
+    use test_support::SharedBuffer;
+
+
+    #[test]
+    fn create_stylesheet() {
+        let sheet: Stylesheet = Stylesheet::new();
+        // must be a HashMap of Style's and only contain DEFAULT_STYLE key
+        assert!(sheet.contains(Stylesheet::DEFAULT_STYLE));
+        assert_eq!(sheet.len(), 1);
+    }
+
+    #[test]
+    fn add_empty_style() {
+        let mut sheet = Stylesheet::new();
+        // must be able to retrieve an empty style and retrieve it
+        // there are more ways to achieve this
+
+        let style_name = "empty_guy";
+        sheet.add_style(style_name,
+            StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, }
+        );
+        assert!(sheet.contains(style_name));
+
+        let style_name = "empty_lady";
+        sheet.add_style(style_name,
+            StyleProperties { transformation: [].to_vec(), color: Some(DefaultColor), background: Some(DefaultColor), underline_color: None, }
+        );
+        assert!(sheet.contains(style_name));
+    }
+
+    #[test]
+    fn add_style() {
+        let mut sheet = Stylesheet::new();
+        // must be able to retrieve a regular style and retrieve it
+        let style_name = "new_guy";
+        sheet.add_style(
+            style_name,
+            StyleProperties {
+                transformation: [ Bold, Underlined, ].to_vec(),
+                color: Some(Blue),
+                background: Some(White), underline_color: None,
+            }
+        );
+        assert!(sheet.contains(style_name));
+    }
+
+    #[test]
+    fn builder() {
+        let properties = StyleProperties::builder().bold().blink().bold().color(Red).background(White).build();
+        // transformations must not be repeated
+        assert_eq!(properties.transformation, [ Bold, Blink, ]);
+        assert_eq!(properties.color, Some(Red));
+        assert_eq!(properties.background, Some(White));
+
+        let properties = StyleProperties::builder().build();
+        assert!(properties.transformation.is_empty());
+        assert!(properties.color.is_none());
+    }
+
+    #[test]
+    fn builder_conflicts() {
+        assert!(StyleProperties::builder().bright().dim().try_build().is_err());
+        assert!(StyleProperties::builder().color(Blue).background(Blue).try_build().is_err());
+        assert!(StyleProperties::builder().color(DefaultColor).background(DefaultColor).try_build().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "FATAL:")]
+    fn builder_conflicts_panic() {
+        StyleProperties::builder().bright().dim().build();
+    }
+
+    #[test]
+    fn add_style_with_runtime_name() {
+        let mut sheet = Stylesheet::new();
+        // names don't need to be known at compile time
+        for i in 0..3 {
+            let style_name = format!("level_{}", i);
+            sheet.add_style(style_name, StyleProperties::builder().build());
+        }
+        let parent = String::from("level_0");
+        sheet.add_style_extending(format!("{}_child", parent), &parent, StyleProperties::builder().bold().build())
+            .unwrap();
+        assert!(sheet.contains("level_2"));
+        assert!(sheet.contains("level_0_child"));
+    }
+
+    #[test]
+    fn rgb_color() {
+        let mut sheet = Stylesheet::new();
+        sheet.capabilities.color_level = ColorLevel::TrueColor;
+        sheet.add_style("brand", StyleProperties::builder().bold().color(Rgb(255, 136, 0)).background(Rgb(0, 0, 32)).build());
+        assert_eq!(sheet.styles["brand"].sgr, "\x1b[1;38;2;255;136;0;48;2;0;0;32m");
+        // basic colors must still use the basic codes
+        sheet.add_style("info", StyleProperties::builder().bright().color(Green).background(Black).build());
+        assert_eq!(sheet.styles["info"].sgr, "\x1b[92;40m");
+        sheet.add_style("plain", StyleProperties::builder().color(DefaultColor).build());
+        assert_eq!(sheet.styles["plain"].sgr, "");
+    }
+
+    #[test]
+    fn rgb_color_from_text() {
+        assert_eq!("rgb(255, 136, 0)".parse(), Ok(Rgb(255, 136, 0)));
+        assert_eq!("rgb(1,2,3)".parse(), Ok(Rgb(1, 2, 3)));
+        assert_eq!("rgb(1, 2)".parse::<StyleColor>(), Err(()));
+        assert_eq!("rgb(256, 0, 0)".parse::<StyleColor>(), Err(()));
+        assert_eq!(Rgb(1, 2, 3).to_string(), "rgb(1, 2, 3)");
+
+        let mut sheet = Stylesheet::new();
+        let vars = vec![("CLI_EXP_STYLE_BRAND".to_string(), "bold,rgb(255, 136, 0),on_rgb(0,0,0)".to_string())];
+        sheet.apply_overrides(vars).unwrap();
+        assert_eq!(sheet.styles["brand"].properties.color, Some(Rgb(255, 136, 0)));
+        assert_eq!(sheet.styles["brand"].properties.background, Some(Rgb(0, 0, 0)));
+    }
+
+    #[test]
+    fn hex_color() {
+        assert_eq!("#FF8800".parse(), Ok(Rgb(255, 136, 0)));
+        assert_eq!("#fff".parse(), Ok(Rgb(255, 255, 255)));
+        assert!("#ff880".parse::<StyleColor>().is_err());
+        assert!("#ggg".parse::<StyleColor>().is_err());
+        assert!("ff8800".parse::<StyleColor>().is_err());
+        // non-ASCII characters must not make slicing panic
+        assert!("#ÿÿÿ".parse::<StyleColor>().is_err());
+        // errors must list the short form too
+        assert!(allowed_colors().contains(&"#RGB".to_string()));
+
+        let sheet = Stylesheet::from_toml_str("[brand]\ncolor = \"#ff8800\"\nbackground = \"#000\"\n").unwrap();
+        assert_eq!(sheet.styles["brand"].properties.color, Some(Rgb(255, 136, 0)));
+        assert_eq!(sheet.styles["brand"].properties.background, Some(Rgb(0, 0, 0)));
+        let mut sheet = Stylesheet::new();
+        let vars = vec![("CLI_EXP_STYLE_BRAND".to_string(), "bold,#ff8800".to_string())];
+        sheet.apply_overrides(vars).unwrap();
+        assert_eq!(sheet.styles["brand"].properties.color, Some(Rgb(255, 136, 0)));
+    }
+
+    #[test]
+    fn fixed_color() {
+        let mut sheet = Stylesheet::new();
+        sheet.capabilities.color_level = ColorLevel::Ansi256;
+        sheet.add_style("orange", StyleProperties::builder().color(Fixed(208)).background(Fixed(16)).build());
+        assert_eq!(sheet.styles["orange"].sgr, "\x1b[38;5;208;48;5;16m");
+        assert_eq!("fixed(208)".parse(), Ok(Fixed(208)));
+        assert_eq!(Fixed(208).to_string(), "fixed(208)");
+    }
+
+    #[test]
+    fn extended_colors() {
+        let mut sheet = Stylesheet::new();
+        sheet.capabilities.color_level = ColorLevel::Ansi256;
+        sheet.add_style("warm", StyleProperties::builder().color(Orange).background(Navy).build());
+        assert_eq!(sheet.styles["warm"].sgr, "\x1b[38;5;208;48;5;18m");
+        assert_eq!("teal".parse(), Ok(Teal));
+        assert_eq!(Gray.to_string(), "gray");
+        // must degrade like the equivalent Fixed colors
+        sheet.capabilities.color_level = ColorLevel::Basic;
+        sheet.add_style("warm", StyleProperties::builder().color(Orange).background(Navy).build());
+        sheet.add_style("fixed", StyleProperties::builder().color(Fixed(208)).background(Fixed(18)).build());
+        assert_eq!(sheet.styles["warm"].sgr, sheet.styles["fixed"].sgr);
+    }
+
+    #[test]
+    fn hsl_color() {
+        assert_eq!(Hsl(0, 100, 50).to_rgb(), Rgb(255, 0, 0));
+        assert_eq!(Hsl(240, 100, 25).to_rgb(), Rgb(0, 0, 128));
+        assert_eq!(Hsl(210, 0, 100).to_rgb(), Rgb(255, 255, 255));
+        assert_eq!(Hsl(30, 100, 50).to_rgb(), Rgb(255, 128, 0));
+        assert_eq!("hsl(30, 100%, 50%)".parse(), Ok(Hsl(30, 100, 50)));
+        assert_eq!("hsl(30,100,50)".parse(), Ok(Hsl(30, 100, 50)));
+        assert_eq!(Hsl(30, 100, 50).to_string(), "hsl(30, 100%, 50%)");
+        assert!("hsl(360, 100%, 50%)".parse::<StyleColor>().is_err());
+        assert!("hsl(30, 101%, 50%)".parse::<StyleColor>().is_err());
+
+        // must be stored as Rgb when the style is registered
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("brand", StyleProperties::builder().color(Hsl(30, 100, 50)).build());
+        sheet.set_alternate("brand", ColorLevel::Ansi256, StyleProperties::builder().color(Hsl(0, 100, 50)).build())
+            .unwrap();
+        assert_eq!(sheet.styles["brand"].properties.color, Some(Rgb(255, 128, 0)));
+        assert_eq!(sheet.styles["brand"].alternates[&ColorLevel::Ansi256].color, Some(Rgb(255, 0, 0)));
+        let sheet = Stylesheet::from_toml_str("[brand]\ncolor = \"hsl(30, 100%, 50%)\"\n").unwrap();
+        assert_eq!(sheet.styles["brand"].properties.color, Some(Rgb(255, 128, 0)));
+        // the error of an invalid color must mention the syntax
+        let err = serde_json::from_str::<StyleColor>("\"hsl(30, 100%)\"").unwrap_err();
+        assert!(err.to_string().contains("hsl(H, S%, L%)"));
+    }
+
+    #[test]
+    fn rgb_downsampling() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::Ansi256, ..TerminalCapabilities::minimal() });
+        assert_eq!(sheet.color_depth(), ColorLevel::Ansi256);
+        sheet.add_style("brand", StyleProperties::builder().color(Rgb(255, 136, 0)).background(Hsl(0, 0, 50)).build());
+        // must not emit truecolor sequences
+        assert_eq!(sheet.styles["brand"].sgr, "\x1b[38;5;208;48;5;244m");
+        sheet.set_capabilities(TerminalCapabilities::minimal());
+        assert_eq!(sheet.color_depth(), ColorLevel::Basic);
+        assert_eq!(sheet.styles["brand"].sgr, "\x1b[33;100m");
+
+        assert_eq!(Rgb(0, 0, 0).downsample(ColorLevel::Basic), Black);
+        assert_eq!(Fixed(231).downsample(ColorLevel::Basic), BrightWhite);
+        assert_eq!(Fixed(231).downsample(ColorLevel::Ansi256), Fixed(231));
+        assert_eq!(Rgb(1, 2, 3).downsample(ColorLevel::TrueColor), Rgb(1, 2, 3));
+        assert_eq!(Orange.downsample(ColorLevel::Ansi256), Fixed(208));
+        assert_eq!(Orange.downsample(ColorLevel::Basic), Fixed(208).downsample(ColorLevel::Basic));
+        assert_eq!(Hsl(0, 100, 50).downsample(ColorLevel::TrueColor), Rgb(255, 0, 0));
+        assert_eq!(Red.downsample(ColorLevel::Basic), Red);
+    }
+
+    #[test]
+    fn blend_colors() {
+        assert_eq!(StyleColor::blend(&Black, &Rgb(255, 255, 255), 0.0), Rgb(0, 0, 0));
+        assert_eq!(StyleColor::blend(&Black, &Rgb(255, 255, 255), 1.0), Rgb(255, 255, 255));
+        // t must be clamped
+        assert_eq!(StyleColor::blend(&Rgb(10, 20, 30), &Rgb(20, 40, 60), 2.0), Rgb(20, 40, 60));
+        assert_eq!(StyleColor::blend(&Rgb(10, 20, 30), &Rgb(20, 40, 60), -1.0), Rgb(10, 20, 30));
+        assert_eq!(StyleColor::blend(&Fixed(196), &Hsl(240, 100, 50), 0.5), Rgb(128, 0, 128));
+        assert_eq!(StyleColor::blend(&DefaultColor, &Red, 0.4), DefaultColor);
+        assert_eq!(StyleColor::blend(&DefaultColor, &Red, 0.6), Red);
+        assert_eq!(Orange.rgb_value(), Fixed(208).rgb_value());
+        assert_eq!(BrightRed.rgb_value(), Some((255, 0, 0)));
+    }
+
+    #[test]
+    fn color_ramp() {
+        let stops = [Rgb(0, 0, 0), Rgb(100, 100, 100), Rgb(100, 0, 0)];
+        assert_eq!(StyleColor::ramp(&stops, 0.0), Rgb(0, 0, 0));
+        assert_eq!(StyleColor::ramp(&stops, 0.25), Rgb(50, 50, 50));
+        assert_eq!(StyleColor::ramp(&stops, 0.5), Rgb(100, 100, 100));
+        assert_eq!(StyleColor::ramp(&stops, 1.0), Rgb(100, 0, 0));
+        assert_eq!(StyleColor::ramp(&[], 0.5), DefaultColor);
+        assert_eq!(StyleColor::ramp(&[Red], 0.5), Red);
+        assert_eq!(StyleColor::palette(&stops, 5)[3], Rgb(100, 50, 50));
+        assert_eq!(StyleColor::palette(&stops, 1), vec![Rgb(0, 0, 0)]);
+        assert!(StyleColor::palette(&stops, 0).is_empty());
+    }
+
+    #[test]
+    fn fixed_color_degradation() {
+        let mut sheet = Stylesheet::new();
+        sheet.capabilities.color_level = ColorLevel::Basic;
+        // 196 is pure red, 21 is pure blue, 231 is white, 232 is nearly black
+        sheet.add_style("red", StyleProperties::builder().color(Fixed(196)).background(Fixed(21)).build());
+        sheet.add_style("gray", StyleProperties::builder().color(Fixed(231)).background(Fixed(232)).build());
+        sheet.add_style("basic", StyleProperties::builder().color(Fixed(3)).background(Fixed(11)).build());
+        assert_eq!(sheet.styles["red"].sgr, "\x1b[91;44m");
+        assert_eq!(sheet.styles["gray"].sgr, "\x1b[97;40m");
+        assert_eq!(sheet.styles["basic"].sgr, "\x1b[33;103m");
+    }
+
+    #[test]
+    fn bright_colors() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("warning", StyleProperties::builder().color(BrightYellow).background(BrightBlue).build());
+        assert_eq!(sheet.styles["warning"].sgr, "\x1b[93;104m");
+        // the bright transformation doesn't change bright colors
+        sheet.add_style("bright", StyleProperties::builder().bright().color(BrightRed).build());
+        assert_eq!(sheet.styles["bright"].sgr, "\x1b[91m");
+        // every name must be parsed back to its color
+        for name in StyleColor::NAMES {
+            assert_eq!(name.parse::<StyleColor>().unwrap().to_string(), *name);
+        }
+    }
+
+    #[test]
+    fn underline_styles() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::TrueColor, ..TerminalCapabilities::minimal() });
+        sheet.add_style("typo", StyleProperties::builder().curly_underlined().color(White).underline_color(Red).build());
+        sheet.add_style("link", StyleProperties::builder().double_underlined().underline_color(Rgb(0, 0, 255)).build());
+        assert_eq!(sheet.styles["typo"].sgr, "\x1b[4:3;37;58;5;1m");
+        assert_eq!(sheet.styles["link"].sgr, "\x1b[4:2;58;2;0;0;255m");
+        // underline colors must be downsampled too
+        sheet.set_capabilities(TerminalCapabilities::minimal());
+        assert_eq!(sheet.styles["link"].sgr, "\x1b[4:2;58;5;4m");
+        // children inherit the underline color
+        sheet.add_style_extending("bold_typo", "typo", StyleProperties::builder().bold().build()).unwrap();
+        assert_eq!(sheet.styles["bold_typo"].sgr, "\x1b[4:3;1;37;58;5;1m");
+        // a style has one underline color: the merged one replaces it
+        let blue = StyleProperties::builder().underline_color(Blue).build();
+        assert_eq!(sheet.styles["typo"].properties.merge(&blue).underline_color, Some(Blue));
+        let spec = Stylesheet::parse_style_spec("typo", "CLI_EXP_STYLE_TYPO", "curly_underlined,underline_color(red)").unwrap();
+        assert_eq!(spec, StyleProperties::builder().curly_underlined().underline_color(Red).build());
+
+        let sheet = Stylesheet::from_toml_str(
+            "[typo]\ntransformation = [\"curly_underlined\"]\nunderline_color = \"red\"\n"
+        ).unwrap();
+        assert_eq!(sheet.styles["typo"].properties.underline_color, Some(Red));
+        let copy = Stylesheet::from_json(&sheet.to_json()).unwrap();
+        assert_eq!(copy.view(), sheet.view());
+        assert!(Stylesheet::from_toml_str("[typo]\nunderline_color = \"plaid\"\n").is_err());
+    }
+
+    #[test]
+    fn remap_foreign_colors() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_capabilities(TerminalCapabilities::minimal());
+        sheet.add_style("danger", StyleProperties::builder().bold().color(Red).build());
+        let colors = [(Red, "danger")];
+        // mapped colors get the style, also when selected from the palette
+        assert_eq!(sheet.format_remapped("\x1b[31mfail\x1b[0m ok", &colors), "\x1b[1;31mfail\x1b[0m ok");
+        assert_eq!(sheet.format_remapped("\x1b[38;5;1mfail", &colors), "\x1b[1;31mfail\x1b[0m");
+        // other styles are kept, but downsampled
+        assert_eq!(sheet.format_remapped("\x1b[4;38;2;0;205;0mok\x1b[2K", &colors), "\x1b[4;32mok\x1b[0m");
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.format_remapped("\x1b[31mfail\x1b[0m ok", &colors), "fail ok");
+    }
+
+    #[test]
+    fn more_transformations() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("obsolete", StyleProperties::builder().dim().strikethrough().build());
+        sheet.add_style("selected", StyleProperties::builder().reverse().build());
+        sheet.add_style("redacted", StyleProperties::builder().hidden().build());
+        assert_eq!(sheet.styles["obsolete"].sgr, "\x1b[2;9m");
+        assert_eq!(sheet.styles["selected"].sgr, "\x1b[7m");
+        assert_eq!(sheet.styles["redacted"].sgr, "\x1b[8m");
+        for name in StyleTransformation::NAMES {
+            assert_eq!(name.parse::<StyleTransformation>().unwrap().to_string(), *name);
+        }
+    }
+
+    #[test]
+    fn no_color() {
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        assert!(!sheet.no_color);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        // NO_COLOR makes every style plain, but doesn't change definitions
+        sheet.no_color = true;
+        assert_eq!(sheet.paint("danger", "text"), "text");
+        assert_eq!(sheet.styles["danger"].sgr, "\x1b[31m");
+    }
+
+    #[test]
+    fn clicolor() {
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        assert!(!sheet.clicolor_force && !sheet.clicolor_disabled);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.is_tty = false;
+        assert_eq!(sheet.color_decision(), ColorDecision::NotTerminal);
+        // CLICOLOR_FORCE must win over TTY detection and NO_COLOR
+        sheet.clicolor_force = true;
+        sheet.no_color = true;
+        assert_eq!(sheet.color_decision(), ColorDecision::CliColorForce);
+        assert_eq!(sheet.paint("danger", "text"), "\x1b[31mtext\x1b[0m");
+        sheet.clicolor_force = false;
+        assert_eq!(sheet.color_decision(), ColorDecision::NoColor);
+        // CLICOLOR=0 must disable styles on a terminal
+        sheet.no_color = false;
+        sheet.clicolor_disabled = true;
+        sheet.is_tty = true;
+        sheet.capabilities.ansi = true;
+        assert_eq!(sheet.color_decision(), ColorDecision::CliColorDisabled);
+        assert_eq!(sheet.paint("danger", "text"), "text");
+        // explicit choices win over the environment
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(sheet.color_decision(), ColorDecision::Always);
+        sheet.clicolor_force = true;
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.color_decision(), ColorDecision::Never);
+        sheet.set_plain(true);
+        assert_eq!(sheet.color_decision(), ColorDecision::Plain);
+        sheet.set_plain(false);
+        sheet.set_color_mode(ColorMode::Auto);
+        sheet.clicolor_force = false;
+        sheet.clicolor_disabled = false;
+        assert_eq!(sheet.color_decision(), ColorDecision::Terminal);
+    }
+
+    #[test]
+    fn not_a_tty() {
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.capabilities.ansi = true;
+        sheet.is_tty = true;
+        assert_eq!(sheet.paint("danger", "text"), "\x1b[31mtext\x1b[0m");
+        // must not print escape codes when output is piped
+        sheet.is_tty = false;
+        assert!(!sheet.is_tty());
+        assert_eq!(sheet.paint("danger", "text"), "text");
+    }
+
+    #[test]
+    fn no_ansi_support() {
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.is_tty = true;
+        // must not print escape codes that the console would show as text
+        sheet.capabilities.ansi = false;
+        assert_eq!(sheet.paint("danger", "text"), "text");
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(sheet.paint("danger", "text"), "\x1b[31mtext\x1b[0m");
+    }
+
+    #[test]
+    fn color_mode() {
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.is_tty = false;
+        assert_eq!(sheet.color_mode(), ColorMode::Auto);
+        assert_eq!(sheet.paint("danger", "text"), "text");
+        // Always must print escape codes even if output is piped
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(sheet.paint("danger", "text"), "\x1b[31mtext\x1b[0m");
+        sheet.no_color = true;
+        assert_eq!(sheet.paint("danger", "text"), "\x1b[31mtext\x1b[0m");
+        // Never must win on a terminal
+        sheet.is_tty = true;
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.paint("danger", "text"), "text");
+    }
+
+    #[test]
+    fn plain() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_plain(true);
+        assert!(sheet.is_plain());
+        assert!(!sheet.colors_enabled());
+        assert!(!sheet.colors_enabled_stderr());
+        assert_eq!(sheet.format("danger", "text"), "text");
+        assert_eq!(sheet.format_markup("<danger>text</danger>").unwrap(), "text");
+        // the ColorMode must be restored
+        sheet.set_plain(false);
+        assert_eq!(sheet.format("danger", "text"), "\x1b[31mtext\x1b[0m");
+    }
+
+    #[test]
+    fn color_mode_from_str() {
+        for name in ColorMode::NAMES.iter() {
+            let mode: ColorMode = name.parse().unwrap();
+            assert_eq!(mode.to_string(), *name);
+        }
+        assert!("sometimes".parse::<ColorMode>().is_err());
+    }
+
+    #[test]
+    fn merge_properties() {
+        let first = StyleProperties::builder().bold().color(Red).background(White).build();
+        let second = StyleProperties::builder().bold().underlined().color(Blue).build();
+        let merged = first.merge(&second);
+        // transformations must not be duplicated
+        assert_eq!(merged.transformation, vec![Bold, Underlined]);
+        assert_eq!(merged.color, Some(Blue));
+        assert_eq!(merged.background, Some(White));
+    }
+
+    #[test]
+    fn compose() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("bold", StyleProperties::builder().bold().build());
+        sheet.add_style("red", StyleProperties::builder().color(Red).build());
+        sheet.compose("bold_red", &["bold", "red"]).unwrap();
+        assert_eq!(sheet.styles["bold_red"].sgr, "\x1b[1;31m");
+        // composed styles don't follow changes to their components
+        sheet.update_style("red", StyleProperties::builder().color(Blue).build()).unwrap();
+        assert_eq!(sheet.styles["bold_red"].sgr, "\x1b[1;31m");
+        assert!(sheet.compose("bold_green", &["bold", "green"]).is_err());
+        assert!(!sheet.contains("bold_green"));
+    }
+
+    #[test]
+    fn compose_conflicts() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("bright", StyleProperties::builder().bright().build());
+        sheet.add_style("dim", StyleProperties::builder().dim().build());
+        match sheet.compose("both", &["bright", "dim"]) {
+            Err(StylesheetError::ConflictingProperties(_)) => (),
+            _ => panic!("bright and dim must conflict"),
+        }
+    }
+
+    #[test]
+    fn merge_stylesheets() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        let mut other = Stylesheet::new();
+        other.add_style("danger", StyleProperties::builder().color(Magenta).build());
+        other.add_style_extending("critical", "danger", StyleProperties::builder().bold().build()).unwrap();
+
+        let mut kept = Stylesheet::new();
+        kept.merge(&sheet, ConflictPolicy::Keep).unwrap();
+        kept.merge(&other, ConflictPolicy::Keep).unwrap();
+        assert_eq!(kept.len(), 4);
+        assert_eq!(kept.styles["danger"].sgr, "\x1b[31m");
+        // critical extends the danger style of the destination
+        assert_eq!(kept.styles["critical"].sgr, "\x1b[1;31m");
+
+        sheet.merge(&other, ConflictPolicy::Override).unwrap();
+        assert_eq!(sheet.styles["danger"].sgr, "\x1b[35m");
+        assert_eq!(sheet.styles["critical"].sgr, "\x1b[1;35m");
+        assert_eq!(sheet.styles["info"].sgr, "\x1b[32m");
+    }
+
+    #[test]
+    fn merge_stylesheets_errors() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let mut other = Stylesheet::new();
+        other.add_style("danger", StyleProperties::builder().color(Magenta).build());
+        other.add_style("info", StyleProperties::builder().color(Green).build());
+        match sheet.merge(&other, ConflictPolicy::Error) {
+            Err(StylesheetError::DuplicateStyle(name)) => assert_eq!(name, "danger"),
+            _ => panic!("danger must be a duplicate"),
+        }
+        // a failed merge must not modify the stylesheet
+        assert!(!sheet.contains("info"));
+        assert_eq!(sheet.styles["danger"].sgr, "\x1b[31m");
+        sheet.freeze();
+        assert!(sheet.merge(&other, ConflictPolicy::Override).is_err());
+    }
+
+    #[test]
+    fn inspect_styles() {
+        let mut sheet = Stylesheet::new();
+        assert!(sheet.style_names().is_empty());
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        assert_eq!(sheet.style_names(), vec!["danger", "info"]);
+        assert_eq!(sheet.get_properties("info").unwrap().color, Some(Green));
+        assert!(sheet.get_properties("warning").is_none());
+        let colors: Vec<(&str, Option<StyleColor>)> = (&sheet).into_iter()
+            .map(|(name, properties)| (name, properties.color.clone()))
+            .collect();
+        assert_eq!(colors, vec![("danger", Some(Red)), ("info", Some(Green))]);
+    }
+
+    #[test]
+    fn stylesheet_macro() {
+        let sheet = stylesheet! {
+            danger: bold + blink, red, on_white;
+            info: bright_green;
+            empty: ;
+        };
+        assert_eq!(sheet.style_names(), vec!["danger", "empty", "info"]);
+        assert_eq!(sheet.styles["danger"].sgr, "\x1b[1;5;31;47m");
+        assert_eq!(sheet.styles["info"].sgr, "\x1b[92m");
+        assert_eq!(stylesheet! {}.len(), 1);
+    }
+
+    #[test]
+    fn prefix() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.add_style_extending("critical", "danger", StyleProperties::builder().bold().build()).unwrap();
+        sheet.set_prefix("danger", "ERROR: ").unwrap();
+        assert_eq!(sheet.get_prefix("critical"), Some("ERROR: "));
+        sheet.println("danger", "a");
+        sheet.println("critical", "b");
+        // format() doesn't add prefixes
+        assert_eq!(sheet.format("danger", "c"), "c");
+        // update_style() keeps the prefix
+        sheet.update_style("danger", StyleProperties::builder().color(Magenta).build()).unwrap();
+        assert_eq!(sheet.get_prefix("danger"), Some("ERROR: "));
+        // children keep the prefix when the parent is removed
+        sheet.remove_style("danger").unwrap();
+        assert_eq!(sheet.get_prefix("critical"), Some("ERROR: "));
+        sheet.clear_prefix("critical").unwrap();
+        sheet.println("critical", "d");
+        assert_eq!(buffer.contents(), "ERROR: a\nERROR: b\nd\n");
+        assert!(sheet.set_prefix("unknown", "x").is_err());
+    }
+
+    #[test]
+    fn prefix_from_toml_str() {
+        let sheet = Stylesheet::from_toml_str("[danger]\ncolor = \"red\"\nprefix = \"ERROR: \"\n").unwrap();
+        assert_eq!(sheet.get_prefix("danger"), Some("ERROR: "));
+        let copy = Stylesheet::from_json(&sheet.to_json()).unwrap();
+        assert_eq!(copy.get_prefix("danger"), Some("ERROR: "));
+        assert_eq!(copy.view(), sheet.view());
+    }
+
+    #[test]
+    fn icons() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(true);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.add_style_extending("critical", "danger", StyleProperties::builder().bold().build()).unwrap();
+        sheet.set_icon("danger", Icon::Error).unwrap();
+        sheet.set_prefix("critical", "CRITICAL: ").unwrap();
+        assert_eq!(sheet.get_icon("critical"), Some(Icon::Error));
+        sheet.println("danger", "a");
+        sheet.println("critical", "b");
+        // ASCII fallback
+        sheet.set_unicode(false);
+        sheet.println("danger", "c");
+        // update_style() keeps the icon, children keep it when the parent is removed
+        sheet.update_style("danger", StyleProperties::builder().color(Magenta).build()).unwrap();
+        assert_eq!(sheet.get_icon("danger"), Some(Icon::Error));
+        sheet.remove_style("danger").unwrap();
+        assert_eq!(sheet.get_icon("critical"), Some(Icon::Error));
+        sheet.clear_icon("critical").unwrap();
+        sheet.println("critical", "d");
+        assert_eq!(buffer.contents(), "✖ a\n✖ CRITICAL: b\nx c\nCRITICAL: d\n");
+        assert!(sheet.set_icon("unknown", Icon::Info).is_err());
+    }
+
+    #[test]
+    fn red_green_icons() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.set_unicode(false);
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().color(BrightRed).build()),
+            ("info", StyleProperties::builder().color(Green).build()),
+            ("warning", StyleProperties::builder().color(Orange).build()),
+            ("custom", StyleProperties::builder().color(Rgb(200, 0, 0)).build()),
+        ]);
+        sheet.add_style_extending("critical", "danger", StyleProperties::builder().bold().build()).unwrap();
+        sheet.set_icon("custom", Icon::Warning).unwrap();
+        sheet.println("danger", "a");
+        sheet.set_red_green_icons(true);
+        sheet.println("danger", "b");
+        sheet.println("critical", "c");
+        sheet.println("info", "d");
+        // orange is not red, and explicit icons win
+        sheet.println("warning", "e");
+        sheet.println("custom", "f");
+        assert_eq!(buffer.contents(), "a
+x b
+x c
++ d
+e
+! f
+");
+    }
+
+    #[test]
+    fn icons_from_toml_str() {
+        let sheet = Stylesheet::from_toml_str("[danger]\ncolor = \"red\"\nicon = \"error\"\n").unwrap();
+        assert_eq!(sheet.get_icon("danger"), Some(Icon::Error));
+        let copy = Stylesheet::from_json(&sheet.to_json()).unwrap();
+        assert_eq!(copy.view(), sheet.view());
+        match Stylesheet::from_toml_str("[danger]\nicon = \"skull\"\n") {
+            Err(StylesheetError::InvalidValue { line, key, .. }) => {
+                assert_eq!(line, Some(2));
+                assert_eq!(key, "icon");
+            },
+            _ => panic!("invalid icon must be rejected"),
+        }
+    }
+
+    #[test]
+    fn alternates() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::TrueColor, ..TerminalCapabilities::minimal() });
+        sheet.add_style("brand", StyleProperties::builder().color(Rgb(255, 136, 0)).build());
+        sheet.add_style_extending("title", "brand", StyleProperties::builder().bold().build()).unwrap();
+        sheet.set_alternate("brand", ColorLevel::Basic, StyleProperties::builder().color(Yellow).build()).unwrap();
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;38;2;255;136;0mx\x1b[0m");
+        // the lowest alternate that the terminal supports is used
+        sheet.set_capabilities(TerminalCapabilities::minimal());
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;33mx\x1b[0m");
+        sheet.set_alternate("brand", ColorLevel::Ansi256, StyleProperties::builder().color(Fixed(208)).build()).unwrap();
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;33mx\x1b[0m");
+        sheet.set_capabilities(TerminalCapabilities { color_level: ColorLevel::Ansi256, ..TerminalCapabilities::minimal() });
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;38;5;208mx\x1b[0m");
+        // children keep their alternates when the parent is removed
+        sheet.remove_style("brand").unwrap();
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;38;5;208mx\x1b[0m");
+        sheet.set_capabilities(TerminalCapabilities::minimal());
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;33mx\x1b[0m");
+        sheet.remove_alternate("title", ColorLevel::Basic).unwrap();
+        assert_eq!(sheet.format("title", "x"), "\x1b[1;33mx\x1b[0m");
+        assert!(sheet.set_alternate("unknown", ColorLevel::Basic, StyleProperties::default()).is_err());
+    }
+
+    #[test]
+    fn alternates_from_toml_str() {
+        let source = "[brand]\ncolor = \"rgb(255, 136, 0)\"\n[brand.alternates.basic]\ncolor = \"yellow\"\n";
+        let sheet = Stylesheet::from_toml_str(source).unwrap();
+        assert_eq!(sheet.view().styles["brand"].alternates[&ColorLevel::Basic].color, Some(Yellow));
+        let copy = Stylesheet::from_json(&sheet.to_json()).unwrap();
+        assert_eq!(copy.view(), sheet.view());
+        match Stylesheet::from_toml_str("[brand.alternates.sepia]\ncolor = \"yellow\"\n") {
+            Err(StylesheetError::InvalidValue { key, .. }) => assert_eq!(key, "alternates"),
+            _ => panic!("invalid color level must be rejected"),
+        }
+    }
+
+    #[test]
+    fn timestamps() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.add_style("time", StyleProperties::builder().dim().build());
+        sheet.set_color_mode(ColorMode::Always);
+        // a format without specifiers is printed as is
+        sheet.enable_timestamps("[now]", "time").unwrap();
+        sheet.println(Stylesheet::DEFAULT_STYLE, "a");
+        // print() doesn't add timestamps
+        sheet.print(Stylesheet::DEFAULT_STYLE, "b");
+        sheet.disable_timestamps();
+        sheet.println(Stylesheet::DEFAULT_STYLE, "c");
+        assert_eq!(buffer.contents(), "\x1b[2m[now]\x1b[0m a\nbc\n");
+
+        let mut out: Vec<u8> = Vec::new();
+        sheet.enable_timestamps("%Y", "time").unwrap();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.writeln(Stylesheet::DEFAULT_STYLE, "d", &mut out).unwrap();
+        let line = String::from_utf8(out).unwrap();
+        assert_eq!(line.len(), "2020 d\n".len());
+
+        match sheet.enable_timestamps("%J", "time") {
+            Err(StylesheetError::InvalidTimestampFormat(format)) => assert_eq!(format, "%J"),
+            _ => panic!("%J must be invalid"),
+        }
+    }
+
+    #[test]
+    fn freeze() {
+        let mut sheet = Stylesheet::new();
+        // should be able to freeze (become immutable)
+        sheet.freeze();
+        // did not panick
+        assert!(true);
+    }
+
+    #[test]
+    fn into_frozen() {
+        let mut sheet = Stylesheet::new();
+        assert!(!sheet.is_frozen());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let mut frozen = sheet.into_frozen();
+        assert!(frozen.is_frozen());
+        // settings can still be changed
+        frozen.set_color_mode(ColorMode::Always);
+        assert_eq!(frozen.format("danger", "text"), "\x1b[31mtext\x1b[0m");
+    }
+
+    #[test]
+    fn freeze_twice() {
+        let mut sheet = Stylesheet::new();
+        // should be able to freeze (become immutable)
+        sheet.freeze();
+        sheet.freeze();
+        // did not panick
+        assert!(true);
+    }
+
+    #[test]
+    #[should_panic(expected = "FATAL:")]
+    fn freeze_and_try_to_modify() {
+        let mut sheet = Stylesheet::new();
+        sheet.freeze();
+        sheet.add_style(
+            "test",
+            StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, }
+        );
+    }
+
+    #[test]
+    fn freeze_and_try_to_modify_without_panic() {
+        let mut sheet = Stylesheet::new();
+        sheet.freeze();
+        let result = sheet.try_add_style(
+            "test",
+            StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, }
+        );
+        match result {
+            Err(StylesheetError::Frozen) => (),
+            _ => panic!("Expected a Frozen error"),
+        }
+        assert!(!sheet.contains("test"));
+    }
+
+    #[test]
+    fn update_style() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("test", StyleProperties { transformation: [].to_vec(), color: Some(Red), background: None, underline_color: None, });
+        sheet.update_style("test", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, })
+            .unwrap();
+        assert!(sheet.styles.get("test").unwrap().properties.color.is_none());
+        // only existing styles can be updated
+        match sheet.update_style("missing", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, }) {
+            Err(StylesheetError::UnknownStyle(name)) => assert_eq!(name, "missing"),
+            _ => panic!("Expected an UnknownStyle error"),
+        }
+    }
+
+    #[test]
+    fn remove_style() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("test", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, });
+        sheet.remove_style("test").unwrap();
+        assert!(!sheet.contains("test"));
+        assert!(sheet.remove_style("test").is_err());
+        // the default style is always there
+        assert!(sheet.remove_style(Stylesheet::DEFAULT_STYLE).is_err());
+        assert!(sheet.contains(Stylesheet::DEFAULT_STYLE));
+    }
+
+    #[test]
+    fn frozen_update_and_remove() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("test", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, });
+        sheet.freeze();
+        assert!(sheet.update_style("test", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, })
+            .is_err());
+        assert!(sheet.remove_style("test").is_err());
+        assert!(sheet.contains("test"));
+    }
+
+    #[test]
+    fn add_style_extending() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties { transformation: [ Bold, ].to_vec(), color: Some(Red), background: None, underline_color: None, });
+        sheet.add_style_extending("critical", "danger",
+            StyleProperties { transformation: [ Blink, ].to_vec(), color: None, background: Some(White), underline_color: None, }
+        ).unwrap();
+        let properties = sheet.resolved_properties("critical");
+        assert_eq!(properties.transformation.len(), 2);
+        assert!(properties.color.is_some());
+        assert!(properties.background.is_some());
+        // the parent must exist
+        assert!(sheet.add_style_extending("other", "missing",
+            StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, }
+        ).is_err());
+    }
+
+    #[test]
+    fn aliases() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.add_alias("error", "danger").unwrap();
+        sheet.add_alias("failure", "error").unwrap();
+        assert_eq!(sheet.alias_target("failure"), Some("danger"));
+        assert!(sheet.contains("error"));
+        assert_eq!(sheet.style_names(), vec!["danger"]);
+        // changes made through aliases affect the aliased style
+        sheet.update_style("error", StyleProperties::builder().color(Magenta).build()).unwrap();
+        sheet.set_prefix("failure", "ERROR: ").unwrap();
+        assert_eq!(sheet.format("danger", "x"), "\x1b[35mx\x1b[0m");
+        sheet.println("failure", "x");
+        assert_eq!(buffer.contents(), "\x1b[35mERROR: x\x1b[0m\n");
+        sheet.add_style_extending("critical", "error", StyleProperties::builder().bold().build()).unwrap();
+        assert_eq!(sheet.styles["critical"].parent.as_deref(), Some("danger"));
+        // remove_style() only removes the alias
+        sheet.remove_style("failure").unwrap();
+        assert!(!sheet.contains("failure"));
+        assert!(sheet.contains("danger"));
+        // aliases of removed styles are removed
+        sheet.remove_style("danger").unwrap();
+        assert!(!sheet.contains("error"));
+        // a new style replaces an alias with the same name
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        sheet.add_alias("note", "info").unwrap();
+        sheet.add_style("note", StyleProperties::builder().color(Blue).build());
+        assert_eq!(sheet.alias_target("note"), None);
+        assert!(matches!(sheet.add_alias("note", "info"), Err(StylesheetError::DuplicateStyle(_))));
+        assert!(matches!(sheet.add_alias("tip", "unknown"), Err(StylesheetError::UnknownStyle(_))));
+    }
+
+    #[test]
+    fn fallback() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        assert_eq!(sheet.format("infp", "x"), "x");
+        sheet.set_fallback(FallbackPolicy::Style("info".to_string())).unwrap();
+        assert_eq!(sheet.format("infp", "x"), "\x1b[32mx\x1b[0m");
+        assert!(sheet.set_fallback(FallbackPolicy::Style("unknown".to_string())).is_err());
+        assert_eq!(sheet.fallback(), &FallbackPolicy::Style("info".to_string()));
+        sheet.set_fallback(FallbackPolicy::Warn).unwrap();
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let handler_warnings = Arc::clone(&warnings);
+        sheet.set_warning_handler(move |warning| handler_warnings.lock().unwrap().push(warning.to_string()));
+        assert_eq!(sheet.format("infp", "x"), "x");
+        // only the first time
+        sheet.format("infp", "x");
+        assert_eq!(*warnings.lock().unwrap(), vec!["Style not found: \"infp\"".to_string()]);
+        sheet.set_fallback(FallbackPolicy::Error).unwrap();
+        assert!(matches!(sheet.try_format("infp", "x"), Err(StylesheetError::UnknownStyle(_))));
+        assert_eq!(sheet.try_format("info", "x").unwrap(), "\x1b[32mx\x1b[0m");
+    }
+
+    #[test]
+    fn fallback_error() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_fallback(FallbackPolicy::Error).unwrap();
+        // only the try_ methods fail
+        assert_eq!(sheet.format("infp", "x"), "x");
+        let err = sheet.try_println("infp", "x").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(matches!(
+            err.get_ref().and_then(|err| err.downcast_ref::<StylesheetError>()),
+            Some(StylesheetError::UnknownStyle(_))
+        ));
+        assert!(sheet.try_print("infp", "x").is_err());
+        sheet.println("infp", "x");
+        assert_eq!(buffer.contents(), "x\n");
+    }
+
+    #[test]
+    fn add_styles() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().color(Red).build()),
+            ("info", StyleProperties::builder().color(Green).build()),
+        ]);
+        assert_eq!(sheet.style_names(), vec!["danger", "info"]);
+        assert_eq!(sheet.styles["info"].sgr, "\x1b[32m");
+        sheet.freeze();
+        let result = sheet.try_add_styles(vec![("success".to_string(), StyleProperties::default())]);
+        assert!(matches!(result, Err(StylesheetError::Frozen)));
+        assert!(!sheet.contains("success"));
+    }
+
+    #[test]
+    fn clone() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.freeze();
+        let copy = sheet.clone();
+        assert!(copy.is_frozen());
+        assert_eq!(copy.view(), sheet.view());
+        // the writer is shared
+        copy.println("danger", "a");
+        assert_eq!(buffer.contents(), "\x1b[31ma\x1b[0m\n");
+        let mut copy = sheet.clone_unfrozen();
+        copy.add_style("info", StyleProperties::default());
+        assert!(!sheet.contains("info"));
+    }
+
+    #[test]
+    fn debug() {
+        let mut sheet = Stylesheet::default();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let debug = format!("{:?}", sheet);
+        assert!(debug.starts_with("Stylesheet { styles: {\"danger\": StyleDefinition"));
+        assert!(debug.contains("color_mode: Auto"));
+    }
+
+    #[test]
+    fn add_style_extending_cycle() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("a", StyleProperties { transformation: [].to_vec(), color: Some(Red), background: None, underline_color: None, });
+        sheet.add_style_extending("b", "a", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, })
+            .unwrap();
+        // redefining a as a child of b must fail and leave a untouched
+        match sheet.add_style_extending("a", "b", StyleProperties { transformation: [].to_vec(), color: None, background: None, underline_color: None, }) {
+            Err(StylesheetError::InheritanceCycle(names)) => assert_eq!(names, ["a", "b", "a"]),
+            _ => panic!("Expected an InheritanceCycle error"),
+        }
+        assert!(sheet.styles["a"].parent.is_none());
+        assert!(sheet.resolved_properties("b").color.is_some());
+    }
+
+    #[test]
+    fn remove_extended_style() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties { transformation: [].to_vec(), color: Some(Red), background: None, underline_color: None, });
+        sheet.add_style_extending("critical", "danger",
+            StyleProperties { transformation: [ Blink, ].to_vec(), color: None, background: None, underline_color: None, }
+        ).unwrap();
+        sheet.remove_style("danger").unwrap();
+        // critical must keep the properties it inherited
+        assert!(sheet.styles["critical"].parent.is_none());
+        assert!(sheet.resolved_properties("critical").color.is_some());
+    }
+
+    #[test]
+    fn from_toml_str_extends() {
+        let sheet = Stylesheet::from_toml_str("
+            [critical]
+            extends = \"danger\"
+            transformation = [\"blink\"]
+
+            [danger]
+            color = \"red\"
+        ").unwrap();
+        assert!(sheet.resolved_properties("critical").color.is_some());
+
+        let result = Stylesheet::from_toml_str("[a]\nextends = \"b\"\n[b]\nextends = \"a\"\n");
+        match result {
+            Err(StylesheetError::InheritanceCycle(_)) => (),
+            _ => panic!("Expected an InheritanceCycle error"),
+        }
+    }
+
+    #[test]
+    fn format() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().bold().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        // must be able to format a &str and a String
+        assert_eq!(sheet.format("danger", "down"), "\x1b[1;31mdown\x1b[0m");
+        assert_eq!(sheet.format("danger", String::from("down")), "\x1b[1;31mdown\x1b[0m");
+        // unknown styles fall back to default
+        assert_eq!(sheet.format("unknown", "down"), "down");
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.format("danger", "down"), "down");
+    }
+
+    #[test]
+    fn styled_text() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        assert_eq!(format!("<{}>", sheet.style("danger", "x")), "<x>");
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(format!("<{}>", sheet.style("danger", "x")), "<\x1b[31mx\x1b[0m>");
+        // must be the same as format()
+        for style_name in ["danger", "unknown", Stylesheet::RAINBOW_STYLE].iter() {
+            assert_eq!(sheet.style(style_name, "xy").to_string(), sheet.format(style_name, "xy"));
+        }
+    }
+
+    #[test]
+    fn padding() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        // escape codes must not be counted
+        assert_eq!(sheet.pad_left("danger", "ab", 5), "   \x1b[31mab\x1b[0m");
+        assert_eq!(sheet.pad_right("danger", "ab", 5), "\x1b[31mab\x1b[0m   ");
+        assert_eq!(sheet.center("danger", "ab", 5), " \x1b[31mab\x1b[0m  ");
+        // wide characters take two columns
+        assert_eq!(sheet.pad_left(Stylesheet::DEFAULT_STYLE, "日本", 5), " 日本");
+        // long texts are not truncated
+        assert_eq!(sheet.center(Stylesheet::DEFAULT_STYLE, "abcdef", 3), "abcdef");
+    }
+
+    #[test]
+    fn println_aligned() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.println_centered(Stylesheet::DEFAULT_STYLE, "ab");
+        sheet.println_right(Stylesheet::DEFAULT_STYLE, "ab");
+        // trailing spaces are not printed
+        assert_eq!(buffer.contents(), format!("{}ab\n{}ab\n", " ".repeat(39), " ".repeat(78)));
+    }
+
+    #[test]
+    fn format_wrapped() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        assert_eq!(
+            sheet.format_wrapped("info", "Vulcan ship detected in sector 7", 12, 0),
+            "Vulcan ship\ndetected in\nsector 7"
+        );
+        assert_eq!(
+            sheet.format_wrapped("info", "Vulcan ship detected\n\nin sector 7", 12, 2),
+            "Vulcan ship\n  detected\n\nin sector 7"
+        );
+        // indentation must not be styled
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(
+            sheet.format_wrapped("info", "aaa bbb", 5, 1),
+            "\x1b[32maaa\x1b[0m\n \x1b[32mbbb\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn println_wrapped() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        let message = "word ".repeat(20);
+        sheet.println_wrapped(Stylesheet::DEFAULT_STYLE, message.trim());
+        // writers are wrapped at 80 columns
+        assert_eq!(buffer.contents(), format!("{}\n{}\n", "word ".repeat(16).trim(), "word ".repeat(4).trim()));
+    }
+
+    #[test]
+    fn format_link() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_hyperlinks(true);
+        assert_eq!(
+            sheet.format_link("info", "chart", "https://example.com"),
+            "\x1b]8;;https://example.com\x1b\\\x1b[32mchart\x1b[0m\x1b]8;;\x1b\\"
+        );
+        // fallback when the terminal doesn't support links
+        sheet.set_hyperlinks(false);
+        assert_eq!(sheet.format_link("info", "chart", "https://example.com"), "\x1b[32mchart (https://example.com)\x1b[0m");
+        // no escape sequences when colors are disabled
+        sheet.set_hyperlinks(true);
+        sheet.set_plain(true);
+        assert_eq!(sheet.format_link("info", "chart", "https://example.com"), "chart (https://example.com)");
+    }
+
+    #[test]
+    fn banner() {
+        let mut sheet = Stylesheet::new();
+        let rule = "=".repeat(19);
+        assert_eq!(sheet.banner(Stylesheet::DEFAULT_STYLE, " Scan  log"), format!("{}\n  S C A N   L O G  \n{}", rule, rule));
+        sheet.add_style("info", StyleProperties::builder().color(Green).build());
+        sheet.set_color_mode(ColorMode::Always);
+        // every line is styled
+        assert_eq!(sheet.banner("info", "a"), "\x1b[32m=====\x1b[0m\n\x1b[32m  A  \x1b[0m\n\x1b[32m=====\x1b[0m");
+    }
+
+    #[test]
+    fn format_gradient() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.capabilities.color_level = ColorLevel::TrueColor;
+        assert_eq!(sheet.format_gradient((0, 0, 0), (255, 255, 255), "abc"), "abc");
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(
+            sheet.format_gradient((0, 0, 0), (200, 100, 50), "a c"),
+            "\x1b[38;2;0;0;0ma \x1b[38;2;200;100;50mc\x1b[0m"
+        );
+        // a single character has the from color
+        assert_eq!(sheet.format_gradient((1, 2, 3), (4, 5, 6), "a"), "\x1b[38;2;1;2;3ma\x1b[0m");
+        assert_eq!(sheet.format_gradient((1, 2, 3), (4, 5, 6), ""), "");
+    }
+
+    #[test]
+    fn rainbow() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(
+            sheet.format(Stylesheet::RAINBOW_STYLE, "ab cdefg"),
+            "\x1b[91ma\x1b[93mb \x1b[92mc\x1b[96md\x1b[94me\x1b[95mf\x1b[91mg\x1b[0m"
+        );
+        assert_eq!(sheet.format_markup("<rainbow>a</rainbow>").unwrap(), "\x1b[91ma\x1b[0m");
+        // a style with the same name wins
+        sheet.add_style(Stylesheet::RAINBOW_STYLE, StyleProperties::builder().color(Green).build());
+        assert_eq!(sheet.format(Stylesheet::RAINBOW_STYLE, "ab"), "\x1b[32mab\x1b[0m");
+        sheet.set_plain(true);
+        assert_eq!(sheet.format_rainbow("ab"), "ab");
+    }
+
+    #[test]
+    fn format_boxed() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_unicode(true);
+        assert_eq!(
+            sheet.format_boxed(Stylesheet::DEFAULT_STYLE, &["Alert", "Shields up"]),
+            "┌────────────┐\n│ Alert      │\n│ Shields up │\n└────────────┘"
+        );
+        sheet.set_unicode(false);
+        assert_eq!(sheet.format_boxed(Stylesheet::DEFAULT_STYLE, &["ab"]), "+----+\n| ab |\n+----+");
+        // escape sequences must not change the box width
+        assert_eq!(
+            sheet.format_boxed(Stylesheet::DEFAULT_STYLE, &["\x1b[31mab\x1b[0m", "abc"]),
+            "+-----+\n| \x1b[31mab\x1b[0m  |\n| abc |\n+-----+"
+        );
+    }
+
+    #[test]
+    fn format_tree() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_unicode(true);
+        let root = TreeNode::new("Sector 001")
+            .child(TreeNode::new("Sol\n3 planets")
+                .child(TreeNode::new("Earth"))
+                .child(TreeNode::new("Borg cube\nthreat: 95")))
+            .child(TreeNode::new("Wolf 359"));
+        assert_eq!(sheet.format_tree(&root), concat!(
+            "Sector 001\n",
+            "├── Sol\n",
+            "│   │   3 planets\n",
+            "│   ├── Earth\n",
+            "│   └── Borg cube\n",
+            "│           threat: 95\n",
+            "└── Wolf 359",
+        ));
+        assert_eq!(sheet.format_tree(&TreeNode::new("Sol")), "Sol");
+        // nodes with an empty label keep their line
+        let root = TreeNode::new("Sol").child(TreeNode::new("")).child(TreeNode::new("Earth"));
+        assert_eq!(sheet.format_tree(&root), "Sol\n├── \n└── Earth");
+        assert_eq!(sheet.format_tree(&TreeNode::new("")), "");
+
+        // node styles win over depth styles
+        sheet.set_unicode(false);
+        sheet.add_style(tree::BRANCH_STYLE, StyleProperties::builder().dim().build());
+        sheet.add_style(tree::depth_style(1), StyleProperties::builder().color(Cyan).build());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let root = TreeNode::new("Sol")
+            .child(TreeNode::new("Earth"))
+            .child(TreeNode::new("Borg cube").with_style("danger"));
+        assert_eq!(
+            sheet.format_tree(&root),
+            "Sol\n\x1b[2m|-- \x1b[0m\x1b[36mEarth\x1b[0m\n\x1b[2m`-- \x1b[0m\x1b[31mBorg cube\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn format_list() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.set_unicode(true);
+        let list = List::numbered()
+            .with_start(9)
+            .item("Hostile ships")
+            .with_item(list::ListItem::new("Anomalies\nin sector 001").with_sublist(
+                List::bullets().item("Whormhole").with_item(
+                    list::ListItem::new("Nebula").with_sublist(List::lettered().item("Mutara"))
+                )
+            ));
+        assert_eq!(sheet.format_list(&list), concat!(
+            " 9. Hostile ships\n",
+            "10. Anomalies\n",
+            "    in sector 001\n",
+            "    ◦ Whormhole\n",
+            "    ◦ Nebula\n",
+            "      a. Mutara",
+        ));
+        assert_eq!(sheet.format_list(&List::bullets()), "");
+
+        sheet.set_unicode(false);
+        sheet.add_style(list::MARKER_STYLE, StyleProperties::builder().dim().build());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let list = List::new(list::Marker::Custom("->".to_string()))
+            .styled_item("danger", "Borg cube")
+            .styled_item("missing", "Sol");
+        assert_eq!(
+            sheet.format_list(&list),
+            "\x1b[2m->\x1b[0m \x1b[31mBorg cube\x1b[0m\n\x1b[2m->\x1b[0m Sol"
+        );
+        // empty items keep their marker
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.format_list(&List::numbered().item("Sol").item("").item("Vulcan")), "1. Sol\n2.\n3. Vulcan");
+    }
+
+    #[test]
+    fn hr() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_unicode(false);
+        sheet.hr(Stylesheet::DEFAULT_STYLE);
+        sheet.hr_with(Stylesheet::DEFAULT_STYLE, '=');
+        assert_eq!(buffer.contents(), format!("{}\n{}\n", "-".repeat(80), "=".repeat(80)));
+        // wide characters must not exceed the width
+        assert_eq!(sheet.format_hr(Stylesheet::DEFAULT_STYLE, '日', 5), "日日");
+    }
+
+    #[test]
+    fn print_segments() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.add_style("success", StyleProperties::builder().color(Green).build());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.print_segments(&[("success", "[ OK ]"), ("unknown", " started")]);
+        sheet.print_segments(&[]);
+        assert_eq!(buffer.contents(), "\x1b[32m[ OK ]\x1b[0m started\n\n");
+    }
+
+    #[test]
+    fn format_template() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(
+            sheet.format_template("{{{:danger}}} in {}", &[&"Borg", &7]),
+            "{\x1b[31mBorg\x1b[0m} in 7"
+        );
+        // must work with macros too
+        assert_eq!(styled!(sheet, "{:danger} in {}", "Borg", 7), "\x1b[31mBorg\x1b[0m in 7");
+        assert_eq!(styled!(sheet, "no args"), "no args");
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough arguments")]
+    fn format_template_missing_argument() {
+        Stylesheet::new().format_template("{} {:danger}", &[&1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many arguments")]
+    fn format_template_extra_argument() {
+        Stylesheet::new().format_template("{}", &[&1, &2]);
+    }
+
+    #[test]
+    fn try_format_template() {
+        let sheet = Stylesheet::new();
+        assert_eq!(sheet.try_format_template("{:danger", &[&1]), Err(TemplateError::UnterminatedPlaceholder));
+        assert_eq!(sheet.try_format_template("{x}", &[&1]), Err(TemplateError::InvalidPlaceholder("x".to_string())));
+        assert_eq!(sheet.try_format_template("}", &[]), Err(TemplateError::UnmatchedBrace));
+        assert_eq!(sheet.try_format_template("{}", &[&1, &2]), Err(TemplateError::TooManyArguments));
+        assert_eq!(try_styled!(sheet, "{} {}", "Borg"), Err(TemplateError::NotEnoughArguments));
+        assert_eq!(try_styled!(sheet, "{{{}}}", "Borg").unwrap(), "{Borg}");
+    }
+
+    #[test]
+    fn sprintln() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        sprintln!(sheet, "Found {:danger} in sector {:info}", "Borg cube", 7);
+        assert_eq!(buffer.contents(), "Found Borg cube in sector 7\n");
+    }
+
+    #[test]
+    fn format_markup() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(
+            sheet.format_markup("at <danger>12%</danger>!").unwrap(),
+            "at \x1b[31m12%\x1b[0m!"
+        );
+        assert!(sheet.format_markup("at <danger>12%").is_err());
+    }
+
+    #[test]
+    fn highlighted() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let mut rules = HighlightRules::new();
+        rules.add_rule(r"(?i)error", "danger").unwrap();
+        sheet.set_highlight_rules(rules);
+        assert_eq!(sheet.highlight_rules().len(), 1);
+        sheet.println_highlighted("1 error");
+        assert_eq!(buffer.contents(), "1 \x1b[31merror\x1b[0m\n");
+        // a frozen stylesheet can change its rules
+        let mut frozen = sheet.into_frozen();
+        frozen.set_highlight_rules(HighlightRules::new());
+        assert_eq!(frozen.format_highlighted("1 error"), "1 error");
+    }
+
+    #[test]
+    fn highlight_lines() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        let mut rules = HighlightRules::new();
+        rules.add_rule(r"(?i)error", "danger").unwrap();
+        sheet.set_highlight_rules(rules);
+        let input = io::Cursor::new(b"ok\r\n\x1b[1mError\x1b[0m\n\xffend".to_vec());
+        assert_eq!(sheet.highlight_lines(input).unwrap(), 3);
+        assert_eq!(buffer.contents(), "ok\n\x1b[31mError\x1b[0m\n\u{fffd}end\n");
+    }
+
+    #[test]
+    fn format_nested_markup() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_capabilities(TerminalCapabilities::minimal());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.add_style("title", StyleProperties::builder().bold().underlined().color(White).build());
+        sheet.set_color_mode(ColorMode::Always);
+        // closing the inner style must restore the outer one, without a reset
+        assert_eq!(
+            sheet.format_markup("<title>Engines <danger>down</danger> now</title>!").unwrap(),
+            "\x1b[1;4;37mEngines \x1b[31mdown\x1b[37m now\x1b[0m!"
+        );
+        // transformations of the inner style must be turned off one by one
+        assert_eq!(
+            sheet.format_markup("<danger>a<title>b</title>c</danger>").unwrap(),
+            "\x1b[31ma\x1b[1;4;37mb\x1b[22;24;31mc\x1b[0m"
+        );
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.format_markup("<title>a<danger>b</danger></title>").unwrap(), "ab");
+    }
+
+    #[test]
+    fn println_markup() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.println_markup("at <danger>12%</danger>").unwrap();
+        // malformed markup must not print anything
+        assert!(sheet.println_markup("at </danger>").is_err());
+        assert_eq!(buffer.contents(), "at 12%\n");
+    }
+
+    #[test]
+    fn write() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_color_mode(ColorMode::Always);
+        let mut buffer: Vec<u8> = Vec::new();
+        sheet.write("danger", "a", &mut buffer).unwrap();
+        sheet.writeln(Stylesheet::DEFAULT_STYLE, "b", &mut buffer).unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "\x1b[31ma\x1b[0mb\n");
+    }
+
+    #[test]
+    fn with_writer() {
+        let buffer = SharedBuffer::default();
+        // the color variables of the environment must not matter
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        sheet.set_writer(buffer.clone());
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        // a writer is not a terminal
+        assert!(!sheet.colors_enabled());
+        sheet.print("danger", "a");
+        sheet.println("danger", "b");
+        assert_eq!(buffer.contents(), "ab\n");
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.println("danger", "c");
+        assert_eq!(buffer.contents(), "ab\n\x1b[31mc\x1b[0m\n");
+    }
+
+    #[test]
+    fn keep_ansi() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        sheet.add_style("danger", StyleProperties::builder().color(Red).build());
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        assert!(sheet.keeps_ansi());
+        sheet.println("danger", "a");
+        // only for this writer
+        sheet.set_writer(buffer.clone());
+        assert!(!sheet.keeps_ansi());
+        sheet.println("danger", "b");
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        sheet.println("danger", "c");
+        assert_eq!(buffer.contents(), "\x1b[31ma\x1b[0m\nb\nc\n");
+    }
+
+    /// A writer that behaves like a pipe closed by the reader.
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "Broken pipe"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn try_println() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.try_println("info", "a").unwrap();
+        sheet.try_print("info", "b").unwrap();
+        assert_eq!(buffer.contents(), "a\nb");
+        let sheet = Stylesheet::with_writer(ClosedPipe);
+        assert_eq!(sheet.try_println("info", "a").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(sheet.try_print("info", "a").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    #[should_panic(expected = "FATAL: Broken pipe")]
+    fn println_broken_pipe() {
+        Stylesheet::with_writer(ClosedPipe).println("info", "a");
+    }
+
+    #[test]
+    fn println() {
+        let sheet = Stylesheet::new();
+        // must be able to println a &str and a String without panicking
+        sheet.println(Stylesheet::DEFAULT_STYLE, "Just a test String reference");
+        sheet.println(Stylesheet::DEFAULT_STYLE, "A cool String struct");
+        // did not panick
+        assert!(true);
+    }
+
+    #[test]
+    fn print() {
+        let sheet = Stylesheet::new();
+        // must be able to print a &str and a String without panicking
+        sheet.print(Stylesheet::DEFAULT_STYLE, "A ");
+        sheet.print(Stylesheet::DEFAULT_STYLE, "B");
+        // did not panick
+        assert!(true);
+    }
+
+    #[test]
+    fn print_and_flush() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(io::BufWriter::new(buffer.clone()));
+        sheet.print("info", "a");
+        // print() doesn't append a newline, and doesn't flush by default
+        assert_eq!(buffer.contents(), "");
+        sheet.flush().unwrap();
+        assert_eq!(buffer.contents(), "a");
+        sheet.set_auto_flush(true);
+        sheet.print("info", "b");
+        assert_eq!(buffer.contents(), "ab");
+    }
+
+    #[test]
+    fn eprintln() {
+        let sheet = Stylesheet::new();
+        // must be able to print to stderr without panicking
+        sheet.eprint(Stylesheet::DEFAULT_STYLE, "A ");
+        sheet.eprintln(Stylesheet::DEFAULT_STYLE, String::from("test"));
+    }
+
+    #[test]
+    fn colors_enabled_stderr() {
+        let mut sheet = Stylesheet::new_honoring_no_color(false);
+        // stdout piped, stderr on a terminal
+        sheet.is_tty = false;
+        sheet.stderr_is_tty = true;
+        sheet.capabilities.ansi = true;
+        assert!(!sheet.colors_enabled());
+        assert!(sheet.colors_enabled_stderr());
+        // a console that would show escape sequences as text
+        sheet.capabilities.ansi = false;
+        assert!(!sheet.colors_enabled_stderr());
+        sheet.capabilities.ansi = true;
+        sheet.set_color_mode(ColorMode::Never);
+        assert!(!sheet.colors_enabled_stderr());
+    }
+
+    #[test]
+    fn from_toml_str() {
+        let sheet = Stylesheet::from_toml_str("
+            [danger]
+            transformation = [\"bold\", \"blink\"]
+            color = \"red\"
+            background = \"white\"
+
+            [info]
+            color = \"green\"
+        ").unwrap();
+        // must contain the styles from the document, plus DEFAULT_STYLE
+        assert!(sheet.contains("danger"));
+        assert!(sheet.contains("info"));
+        assert_eq!(sheet.len(), 3);
+    }
+
+    #[test]
+    fn from_toml_str_invalid_value() {
+        let result = Stylesheet::from_toml_str("
+            [danger]
+            color = \"red\"
+            transformation = [\"bold\", \"sparkling\"]
+        ");
+        // must report the line of the unknown transformation
+        match result {
+            Err(StylesheetError::InvalidValue { line, style, key, value, .. }) => {
+                assert_eq!(line, Some(4));
+                assert_eq!(style, "danger");
+                assert_eq!(key, "transformation");
+                assert_eq!(value, "sparkling");
+            },
+            _ => panic!("Expected an InvalidValue error"),
+        }
+    }
+
+    #[test]
+    fn from_toml_str_syntax_error() {
+        let result = Stylesheet::from_toml_str("[danger]\ncolor = red\n");
+        match result {
+            Err(StylesheetError::Syntax { line, .. }) => assert_eq!(line, Some(2)),
+            _ => panic!("Expected a Syntax error"),
+        }
+    }
+
+    #[test]
+    fn from_json() {
+        let sheet = Stylesheet::from_json(r#"{
+            "danger": { "transformation": ["bold", "blink"], "color": "red", "background": "white" },
+            "info": { "color": "green" }
+        }"#).unwrap();
+        assert!(sheet.contains("danger"));
+        assert!(sheet.contains("info"));
+        assert_eq!(sheet.len(), 3);
+    }
+
+    #[test]
+    fn from_json_invalid_value() {
+        let result = Stylesheet::from_json(r#"{ "danger": { "color": "infrared" } }"#);
+        match result {
+            Err(StylesheetError::InvalidValue { key, value, .. }) => {
+                assert_eq!(key, "color");
+                assert_eq!(value, "infrared");
+            },
+            _ => panic!("Expected an InvalidValue error"),
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties {
+            transformation: [ Bold, Blink, ].to_vec(), color: Some(Red), background: Some(White), underline_color: None,
+        });
+        sheet.add_style("info", StyleProperties {
+            transformation: [].to_vec(), color: Some(Green), background: None, underline_color: None,
+        });
+        // dumping a loaded dump must give the same document
+        let json = sheet.to_json();
+        let copy = Stylesheet::from_json(&json).unwrap();
+        assert_eq!(copy.len(), sheet.len());
+        assert_eq!(copy.to_json(), json);
+    }
+
+    #[test]
+    fn serde_properties() {
+        let properties = StyleProperties::builder().bold().color(Rgb(1, 2, 3)).build();
+        let json = serde_json::to_string(&properties).unwrap();
+        assert_eq!(json, r#"{"transformation":["bold"],"color":"rgb(1, 2, 3)"}"#);
+        let copy: StyleProperties = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy, properties);
+        assert!(serde_json::from_str::<StyleProperties>(r#"{"color":"ultraviolet"}"#).is_err());
+    }
+
+    #[test]
+    fn serde_stylesheet() {
+        let mut sheet = Stylesheet::new();
+        sheet.add_style("danger", StyleProperties::builder().bold().color(Red).build());
+        sheet.add_style_extending("critical", "danger", StyleProperties::builder().blink().build()).unwrap();
+        let yaml = serde_yaml::to_string(&sheet).unwrap();
+        let copy: Stylesheet = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(copy.view(), sheet.view());
+        assert_eq!(copy.styles["critical"].sgr, sheet.styles["critical"].sgr);
+        // inheritance is validated
+        let cycle = r#"{"a": {"extends": "b"}, "b": {"extends": "a"}}"#;
+        assert!(serde_json::from_str::<Stylesheet>(cycle).is_err());
+    }
+
+    #[test]
+    fn from_yaml_str() {
+        let sheet = Stylesheet::from_yaml_str("
+danger:
+  transformation: [bold, blink]
+  color: red
+  background: white
+info:
+  color: green
+").unwrap();
+        assert!(sheet.contains("danger"));
+        assert!(sheet.contains("info"));
+        assert_eq!(sheet.len(), 3);
+    }
+
+    #[test]
+    fn from_yaml_str_invalid_value() {
+        let result = Stylesheet::from_yaml_str("danger:\n  background: plaid\n");
+        // must tell the offending key and the allowed values
+        match result {
+            Err(err @ StylesheetError::InvalidValue { .. }) => {
+                let message = err.to_string();
+                assert!(message.contains("background"));
+                assert!(message.contains("plaid"));
+                assert!(message.contains(&StyleColor::NAMES.join(", ")));
+            },
+            _ => panic!("Expected an InvalidValue error"),
+        }
+    }
+
+    #[test]
+    fn from_yaml_str_unknown_key() {
+        let result = Stylesheet::from_yaml_str("danger:\n  colour: red\n");
+        match result {
+            Err(err @ StylesheetError::Syntax { .. }) => assert!(err.to_string().contains("colour")),
+            _ => panic!("Expected a Syntax error"),
+        }
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let mut sheet = Stylesheet::new();
+        let vars = vec![
+            ("CLI_EXP_STYLE_DANGER".to_string(), "bold, red,on_white".to_string()),
+            ("CLI_EXP_STYLE_".to_string(), "green".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        sheet.apply_overrides(vars).unwrap();
+        // only the variable with a style name must be used
+        assert!(sheet.contains("danger"));
+        assert_eq!(sheet.len(), 2);
+        let properties = &sheet.styles.get("danger").unwrap().properties;
+        assert_eq!(properties.transformation.len(), 1);
+        assert!(properties.color.is_some());
+        assert!(properties.background.is_some());
+    }
+
+    #[test]
+    fn apply_overrides_invalid_value() {
+        let mut sheet = Stylesheet::new();
+        let vars = vec![
+            ("CLI_EXP_STYLE_INFO".to_string(), "green".to_string()),
+            ("CLI_EXP_STYLE_DANGER".to_string(), "bold,on_fire".to_string()),
+        ];
+        // must fail without changing any style
+        match sheet.apply_overrides(vars) {
+            Err(StylesheetError::InvalidValue { key, value, .. }) => {
+                assert_eq!(key, "CLI_EXP_STYLE_DANGER");
+                assert_eq!(value, "on_fire");
+            },
+            _ => panic!("Expected an InvalidValue error"),
+        }
+        assert_eq!(sheet.len(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn pager_commands() {
+        let shell = |pager: &str| Some(vec!["sh".to_string(), "-c".to_string(), pager.to_string()]);
+        assert_eq!(pager_command(None), shell("less -R"));
+        assert_eq!(pager_command(Some("more -d".to_string())), shell("more -d"));
+        // quoted paths are left to the shell
+        assert_eq!(pager_command(Some("'/opt/my pager/less' -R".to_string())), shell("'/opt/my pager/less' -R"));
+        // no pager
+        assert_eq!(pager_command(Some(" ".to_string())), None);
+        assert_eq!(pager_command(Some("cat".to_string())), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn missing_pager() {
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        let command = pager_command(Some("nonexistent-pager-1701".to_string())).unwrap();
+        sheet.page(&command, "line 1\nline 2\n").unwrap();
+        assert_eq!(buffer.contents(), "line 1\nline 2\n");
+    }
+
+    #[test]
+    fn paged_to_writer() {
+        // writers are not terminals, so the content is printed directly
+        let buffer = SharedBuffer::default();
+        let sheet = Stylesheet::with_writer(buffer.clone());
+        sheet.print_paged("line 1\nline 2\n").unwrap();
+        assert_eq!(buffer.contents(), "line 1\nline 2\n");
+    }
+
+    #[test]
+    fn count_down() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        sheet.set_color_mode(ColorMode::Never);
+        assert_eq!(sheet.countdown_line("danger", "Retrying in", 75), "Retrying in 01:15");
+        sheet.count_down("danger", 0, "Retrying in").unwrap();
+        assert_eq!(buffer.contents(), "\rRetrying in 00:00\x1b[K\n");
     }
 }
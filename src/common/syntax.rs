@@ -0,0 +1,314 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use stylesheet::{Stylesheet, StyleColor, StyleProperties};
+
+extern crate regex;
+use self::regex::Regex;
+
+
+/// The style of comments, and of Rust attributes.
+pub const COMMENT_STYLE: &str = "syntax_comment";
+/// The style of strings and characters.
+pub const STRING_STYLE: &str = "syntax_string";
+/// The style of numbers.
+pub const NUMBER_STYLE: &str = "syntax_number";
+/// The style of keywords, booleans, null and Rust macros.
+pub const KEYWORD_STYLE: &str = "syntax_keyword";
+/// The style of TOML keys and tables, and JSON keys.
+pub const KEY_STYLE: &str = "syntax_key";
+/// The style of Rust types.
+pub const TYPE_STYLE: &str = "syntax_type";
+
+/// The styles of the named groups of the language patterns.
+const GROUP_STYLES: [(&str, &str); 9] = [
+    ("comment", COMMENT_STYLE),
+    ("attribute", COMMENT_STYLE),
+    ("string", STRING_STYLE),
+    ("number", NUMBER_STYLE),
+    ("keyword", KEYWORD_STYLE),
+    ("macro", KEYWORD_STYLE),
+    ("key", KEY_STYLE),
+    ("table", KEY_STYLE),
+    ("type", TYPE_STYLE),
+];
+
+// Every pattern is a list of alternatives: where several alternatives
+// match, the one that starts first wins, then the one listed first.
+// Only the named groups are styled, so an alternative can match a
+// context, like the "=" after a TOML key, without styling it.
+lazy_static! {
+    static ref TOML_PATTERN: Regex = Regex::new(concat!(
+        r"(?m:^[ \t]*(?P<table>\[\[?[^\]\n]*\]\]?))",
+        r#"|(?m:^[ \t]*(?P<key>[A-Za-z0-9_.\-]+|"[^"\n]*")[ \t]*=)"#,
+        r"|(?P<comment>#[^\n]*)",
+        r#"|(?P<string>"""[\s\S]*?"""|'''[\s\S]*?'''|"(?:[^"\\\n]|\\.)*"|'[^'\n]*')"#,
+        r"|(?P<keyword>\b(?:true|false|inf|nan)\b)",
+        r"|(?P<number>[+-]?\b(?:0x[0-9a-fA-F_]+|\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)\b)",
+    )).unwrap();
+
+    static ref JSON_PATTERN: Regex = Regex::new(concat!(
+        r#"(?P<key>"(?:[^"\\\n]|\\.)*")\s*:"#,
+        r#"|(?P<string>"(?:[^"\\\n]|\\.)*")"#,
+        r"|(?P<keyword>\b(?:true|false|null)\b)",
+        r"|(?P<number>-?\b\d+(?:\.\d+)?(?:[eE][+-]?\d+)?\b)",
+    )).unwrap();
+
+    static ref RUST_PATTERN: Regex = Regex::new(concat!(
+        r"(?P<comment>//[^\n]*|/\*[\s\S]*?\*/)",
+        r"|(?P<attribute>#!?\[[^\]\n]*\])",
+        r##"|(?P<string>b?r#"[\s\S]*?"#|b?r"[^"]*"|b?"(?:[^"\\]|\\[\s\S])*"|b?'(?:[^'\\\n]|\\[^'\n]+)')"##,
+        r"|(?P<keyword>\b(?:as|async|await|break|const|continue|crate|dyn|else|enum|extern|false|fn|for|if|impl",
+        r"|in|let|loop|match|mod|move|mut|pub|ref|return|self|Self|static|struct|super|trait|true|type|unsafe",
+        r"|use|where|while)\b)",
+        r"|(?P<macro>\b[a-z_][A-Za-z0-9_]*!)",
+        r"|(?P<type>\b(?:[A-Z][A-Za-z0-9_]*|[iu](?:8|16|32|64|128|size)|f32|f64|bool|char|str)\b)",
+        r"|(?P<number>\b(?:0[xob][0-9a-fA-F_]+|\d[\d_]*(?:\.\d[\d_]*)?(?:[eE][+-]?\d+)?)",
+        r"(?:[iu](?:8|16|32|64|128|size)|f32|f64)?\b)",
+    )).unwrap();
+}
+
+
+/// The languages that can be highlighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Toml,
+    Json,
+    Rust,
+}
+
+impl Language {
+    /// Names accepted by from_str(), for example in a "--language" option.
+    pub const NAMES: [&'static str; 3] = ["toml", "json", "rust"];
+
+    /// Return the language of a file, according to its extension,
+    /// or None if it's not supported.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    pub fn from_path<P>(path: P) -> Option<Language> where P: AsRef<Path> {
+        match path.as_ref().extension()?.to_str()? {
+            "toml" => Some(Language::Toml),
+            "json" => Some(Language::Json),
+            "rs" => Some(Language::Rust),
+            _ => None,
+        }
+    }
+
+    fn pattern(self) -> &'static Regex {
+        match self {
+            Language::Toml => &TOML_PATTERN,
+            Language::Json => &JSON_PATTERN,
+            Language::Rust => &RUST_PATTERN,
+        }
+    }
+}
+
+impl FromStr for Language {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "toml" => Ok(Language::Toml),
+            "json" => Ok(Language::Json),
+            "rust" => Ok(Language::Rust),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Language::Toml => "toml",
+            Language::Json => "json",
+            Language::Rust => "rust",
+        };
+        f.write_str(name)
+    }
+}
+
+
+/// Split code into (style name, text) segments, like
+/// HighlightRules::segments(). Texts that are not highlighted have
+/// no style name.
+///
+/// # Arguments
+///
+/// * `code` - The code to split.
+/// * `language` - The language of the code.
+///
+/// # Example
+///
+/// ```
+/// use common::syntax::{self, Language};
+/// assert_eq!(syntax::segments("min_threat = 50", Language::Toml), vec![
+///     (Some(syntax::KEY_STYLE), "min_threat"),
+///     (None, " = "),
+///     (Some(syntax::NUMBER_STYLE), "50"),
+/// ]);
+/// ```
+pub fn segments(code: &str, language: Language) -> Vec<(Option<&'static str>, &str)> {
+    let pattern = language.pattern();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for captures in pattern.captures_iter(code) {
+        for (group, style_name) in GROUP_STYLES.iter() {
+            let found = match captures.name(group) {
+                Some(found) if !found.as_str().is_empty() => found,
+                _ => continue,
+            };
+            if found.start() > start {
+                segments.push((None, &code[start..found.start()]));
+            }
+            segments.push((Some(*style_name), found.as_str()));
+            start = found.end();
+        }
+    }
+    if start < code.len() {
+        segments.push((None, &code[start..]));
+    }
+    segments
+}
+
+/// Return code with syntax highlighting. Styles that don't exist in
+/// sheet are not applied: add_syntax_styles() adds them all. Styles are
+/// applied to every line separately, so the output can be paged or
+/// filtered by line.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `code` - The code to highlight.
+/// * `language` - The language of the code.
+///
+/// # Example
+///
+/// ```
+/// use common::stylesheet::Stylesheet;
+/// use common::syntax::{self, Language};
+/// let mut sheet = Stylesheet::theme_dark();
+/// syntax::add_syntax_styles(&mut sheet);
+/// println!("{}", syntax::format_code(&sheet, "[alerts.danger]\nbell = true", Language::Toml));
+/// ```
+pub fn format_code(sheet: &Stylesheet, code: &str, language: Language) -> String {
+    segments(code, language).into_iter()
+        .map(|(style_name, text)| match style_name {
+            Some(style_name) if sheet.contains(style_name) => text.split('\n')
+                .map(|line| if line.is_empty() { String::new() } else { sheet.format(style_name, line) })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            _ => text.to_string(),
+        })
+        .collect()
+}
+
+/// Print code with syntax highlighting, followed by a newline.
+/// See format_code().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `code` - The code to print.
+/// * `language` - The language of the code.
+pub fn print_code(sheet: &Stylesheet, code: &str, language: Language) -> io::Result<()> {
+    sheet.try_print_raw(&format!("{}\n", format_code(sheet, code, language)))
+}
+
+/// Add the styles used for syntax highlighting, with colors that are
+/// readable on dark and light backgrounds. Styles with the same names
+/// are replaced, so add them before loading the user configuration.
+///
+/// # Arguments
+///
+/// * `sheet` - The stylesheet to modify.
+pub fn add_syntax_styles(sheet: &mut Stylesheet) {
+    sheet.add_styles(vec![
+        (COMMENT_STYLE, StyleProperties::builder().dim().italic().build()),
+        (STRING_STYLE, StyleProperties::builder().color(StyleColor::Green).build()),
+        (NUMBER_STYLE, StyleProperties::builder().color(StyleColor::Cyan).build()),
+        (KEYWORD_STYLE, StyleProperties::builder().color(StyleColor::Magenta).build()),
+        (KEY_STYLE, StyleProperties::builder().color(StyleColor::Blue).build()),
+        (TYPE_STYLE, StyleProperties::builder().color(StyleColor::Yellow).build()),
+    ]);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Return the highlighted parts of code, as (style name, text).
+    fn highlighted(code: &str, language: Language) -> Vec<(&'static str, &str)> {
+        segments(code, language).into_iter()
+            .filter_map(|(style_name, text)| style_name.map(|style_name| (style_name, text)))
+            .collect()
+    }
+
+    #[test]
+    fn highlight_toml() {
+        let code = "# scanner\n[alerts.danger]\nbell = true # ring\nname = \"Sol # 3\"\nthreats = [\n  10,\n]";
+        assert_eq!(highlighted(code, Language::Toml), vec![
+            (COMMENT_STYLE, "# scanner"),
+            (KEY_STYLE, "[alerts.danger]"),
+            (KEY_STYLE, "bell"),
+            (KEYWORD_STYLE, "true"),
+            (COMMENT_STYLE, "# ring"),
+            (KEY_STYLE, "name"),
+            (STRING_STYLE, "\"Sol # 3\""),
+            (KEY_STYLE, "threats"),
+            (NUMBER_STYLE, "10"),
+        ]);
+        // the text between the highlighted parts is kept
+        let text: String = segments(code, Language::Toml).iter().map(|(_, text)| *text).collect();
+        assert_eq!(text, code);
+    }
+
+    #[test]
+    fn highlight_json() {
+        assert_eq!(highlighted("{\"min_threat\": -5.5, \"name\": \"Sol\", \"ok\": null}", Language::Json), vec![
+            (KEY_STYLE, "\"min_threat\""),
+            (NUMBER_STYLE, "-5.5"),
+            (KEY_STYLE, "\"name\""),
+            (STRING_STYLE, "\"Sol\""),
+            (KEY_STYLE, "\"ok\""),
+            (KEYWORD_STYLE, "null"),
+        ]);
+    }
+
+    #[test]
+    fn highlight_rust() {
+        let code = "#[derive(Debug)]\nfn scan<'a>(x: &'a str) -> Option<u8> { println!(\"{}\", 'x'); 0x1F } // done";
+        assert_eq!(highlighted(code, Language::Rust), vec![
+            (COMMENT_STYLE, "#[derive(Debug)]"),
+            (KEYWORD_STYLE, "fn"),
+            (TYPE_STYLE, "str"),
+            (TYPE_STYLE, "Option"),
+            (TYPE_STYLE, "u8"),
+            (KEYWORD_STYLE, "println!"),
+            (STRING_STYLE, "\"{}\""),
+            (STRING_STYLE, "'x'"),
+            (NUMBER_STYLE, "0x1F"),
+            (COMMENT_STYLE, "// done"),
+        ]);
+    }
+
+    #[test]
+    fn format_lines() {
+        let mut sheet = Stylesheet::new();
+        sheet.set_color_mode(::stylesheet::ColorMode::Always);
+        sheet.add_style(STRING_STYLE, StyleProperties::builder().color(StyleColor::Green).build());
+        // every line is styled separately, missing styles are not applied
+        assert_eq!(
+            format_code(&sheet, "s = \"\"\"a\nb\"\"\" # c", Language::Toml),
+            "s = \x1b[32m\"\"\"a\x1b[0m\n\x1b[32mb\"\"\"\x1b[0m # c"
+        );
+        assert_eq!(Language::from_path("scanner.toml"), Some(Language::Toml));
+        assert_eq!(Language::from_path("main.rs"), Some(Language::Rust));
+        assert_eq!(Language::from_path("README"), None);
+        assert_eq!("json".parse(), Ok(Language::Json));
+    }
+}
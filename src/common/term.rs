@@ -0,0 +1,307 @@
+use std::io;
+
+use stylesheet::Stylesheet;
+
+
+/// Clear the whole line of the cursor. The cursor doesn't move.
+pub const CLEAR_LINE: &str = "\x1b[2K";
+/// Clear the line from the cursor to its end.
+pub const CLEAR_LINE_END: &str = "\x1b[K";
+/// Clear the screen and move the cursor to its top left corner.
+pub const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+/// Clear the screen from the cursor to its end.
+pub const CLEAR_SCREEN_END: &str = "\x1b[J";
+/// Hide the cursor.
+pub const HIDE_CURSOR: &str = "\x1b[?25l";
+/// Show the cursor.
+pub const SHOW_CURSOR: &str = "\x1b[?25h";
+/// Remember the position of the cursor.
+pub const SAVE_CURSOR: &str = "\x1b7";
+/// Move the cursor to the position remembered with SAVE_CURSOR.
+pub const RESTORE_CURSOR: &str = "\x1b8";
+/// Switch to the alternate screen, that has no scrollback.
+pub const ENTER_ALTERNATE_SCREEN: &str = "\x1b[?1049h";
+/// Switch back to the normal screen, as it was before
+/// ENTER_ALTERNATE_SCREEN.
+pub const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+/// Ring the terminal bell. Depending on its configuration, the terminal
+/// plays a sound, flashes or marks its window or tab.
+pub const BELL: &str = "\x07";
+
+
+/// Return the sequence that moves the cursor up by n lines.
+/// Like the other cursor_* functions, it returns an empty string
+/// if n is 0, because terminals move by 1 in that case.
+///
+/// # Example
+///
+/// ```
+/// use common::term;
+/// assert_eq!(term::cursor_up(2), "\x1b[2A");
+/// assert_eq!(term::cursor_up(0), "");
+/// ```
+pub fn cursor_up(n: usize) -> String {
+    cursor_move(n, 'A')
+}
+
+/// Return the sequence that moves the cursor down by n lines.
+pub fn cursor_down(n: usize) -> String {
+    cursor_move(n, 'B')
+}
+
+/// Return the sequence that moves the cursor right by n columns.
+pub fn cursor_right(n: usize) -> String {
+    cursor_move(n, 'C')
+}
+
+/// Return the sequence that moves the cursor left by n columns.
+pub fn cursor_left(n: usize) -> String {
+    cursor_move(n, 'D')
+}
+
+fn cursor_move(n: usize, direction: char) -> String {
+    if n == 0 { String::new() } else { format!("\x1b[{}{}", n, direction) }
+}
+
+/// Return the sequence that moves the cursor to a position of the
+/// screen. Rows and columns start from 0, at the top left corner.
+///
+/// # Example
+///
+/// ```
+/// use common::term;
+/// assert_eq!(term::cursor_to(0, 9), "\x1b[1;10H");
+/// ```
+pub fn cursor_to(row: usize, column: usize) -> String {
+    format!("\x1b[{};{}H", row + 1, column + 1)
+}
+
+/// Return the sequence that moves the cursor to a column of its line.
+/// Columns start from 0.
+pub fn cursor_to_column(column: usize) -> String {
+    format!("\x1b[{}G", column + 1)
+}
+
+/// Return the text that replaces the cursor line with line, like
+/// a status or a progress bar that is redrawn in place. The cursor is
+/// left at the end of line; an empty line clears the cursor line.
+///
+/// # Example
+///
+/// ```
+/// use common::term;
+/// assert_eq!(term::replace_line("Scanning"), "\rScanning\x1b[K");
+/// ```
+pub fn replace_line(line: &str) -> String {
+    format!("\r{}{}", line, CLEAR_LINE_END)
+}
+
+/// Return the text that replaces lines drawn before, ending at the
+/// cursor line, with new lines. The cursor is left at the end of the
+/// last line, and whatever was below it is cleared. Writing all the
+/// lines at once avoids flickering, so widgets that redraw several
+/// lines, like menus, use it.
+///
+/// # Arguments
+///
+/// * `previous` - The number of lines drawn before. 0 means that the
+///   lines are drawn for the first time, starting from the cursor line.
+/// * `lines` - The new lines.
+///
+/// # Example
+///
+/// ```
+/// use common::term;
+/// let lines = vec!["Sector 1: clear".to_string(), "Sector 2: clear".to_string()];
+/// assert_eq!(
+///     term::replace_lines(2, &lines),
+///     "\x1b[1A\rSector 1: clear\x1b[K\nSector 2: clear\x1b[K\x1b[J"
+/// );
+/// ```
+pub fn replace_lines(previous: usize, lines: &[String]) -> String {
+    let mut output = cursor_up(previous.saturating_sub(1));
+    output.push('\r');
+    output.push_str(&lines.join(&format!("{}\n", CLEAR_LINE_END)));
+    output.push_str(CLEAR_LINE_END);
+    output.push_str(CLEAR_SCREEN_END);
+    output
+}
+
+/// Print an escape sequence, like the ones of this module, if the
+/// output of sheet is a terminal. Otherwise nothing is printed, so that
+/// files and pipes don't receive sequences they can't interpret.
+/// The sequence takes effect when the output is flushed.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+/// * `sequence` - The escape sequence.
+///
+/// # Example
+///
+/// ```
+/// use common::stylesheet::Stylesheet;
+/// use common::term;
+/// let sheet = Stylesheet::theme_dark();
+/// term::print(&sheet, term::CLEAR_SCREEN).unwrap();
+/// term::print(&sheet, &term::cursor_down(2)).unwrap();
+/// sheet.flush().unwrap();
+/// ```
+pub fn print(sheet: &Stylesheet, sequence: &str) -> io::Result<()> {
+    if !sheet.is_interactive() {
+        return Ok(());
+    }
+    sheet.try_print_raw(sequence)
+}
+
+/// Hide the cursor until the returned guard is dropped, even if a panic
+/// unwinds the stack. Nothing happens if the output is not a terminal.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+///
+/// # Example
+///
+/// ```
+/// use common::stylesheet::Stylesheet;
+/// use common::term;
+/// let sheet = Stylesheet::theme_dark();
+/// {
+///     let _hidden = term::hide_cursor(&sheet).unwrap();
+///     // draw an animation
+/// }
+/// // the cursor is visible again
+/// ```
+pub fn hide_cursor(sheet: &Stylesheet) -> io::Result<HiddenCursor<'_>> {
+    print(sheet, HIDE_CURSOR)?;
+    sheet.flush()?;
+    Ok(HiddenCursor { sheet })
+}
+
+/// A guard that shows the cursor when it is dropped.
+/// See hide_cursor().
+#[derive(Debug)]
+pub struct HiddenCursor<'a> {
+    sheet: &'a Stylesheet,
+}
+
+impl<'a> Drop for HiddenCursor<'a> {
+    fn drop(&mut self) {
+        // nothing can be done if this fails
+        let _ = print(self.sheet, SHOW_CURSOR).and_then(|_| self.sheet.flush());
+    }
+}
+
+/// Switch to the alternate screen until the returned guard is dropped,
+/// even if a panic unwinds the stack. Full screen modes, like dashboards,
+/// should draw there: the normal screen and its scrollback are restored
+/// as they were when the guard is dropped, like less and vim do.
+/// Nothing happens if the output is not a terminal.
+/// Note that std::process::exit() doesn't drop the guard, and that
+/// messages printed by panics are lost with the alternate screen:
+/// print errors after the guard is dropped.
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet used to print.
+///
+/// # Example
+///
+/// ```
+/// use common::stylesheet::Stylesheet;
+/// use common::term;
+/// let sheet = Stylesheet::theme_dark();
+/// {
+///     let _screen = term::alternate_screen(&sheet).unwrap();
+///     term::print(&sheet, &term::cursor_to(0, 0)).unwrap();
+///     sheet.println("info", "Bridge status");
+/// }
+/// // the original screen is back
+/// ```
+pub fn alternate_screen(sheet: &Stylesheet) -> io::Result<AlternateScreen<'_>> {
+    print(sheet, ENTER_ALTERNATE_SCREEN)?;
+    print(sheet, CLEAR_SCREEN)?;
+    sheet.flush()?;
+    Ok(AlternateScreen { sheet })
+}
+
+/// A guard that goes back to the normal screen when it is dropped.
+/// See alternate_screen().
+#[derive(Debug)]
+pub struct AlternateScreen<'a> {
+    sheet: &'a Stylesheet,
+}
+
+impl<'a> AlternateScreen<'a> {
+    /// Go back to the normal screen now, returning write errors.
+    /// Dropping the guard does the same, but errors are ignored.
+    pub fn leave(self) -> io::Result<()> {
+        let sheet = self.sheet;
+        // don't switch twice
+        ::std::mem::forget(self);
+        print(sheet, LEAVE_ALTERNATE_SCREEN)?;
+        sheet.flush()
+    }
+}
+
+impl<'a> Drop for AlternateScreen<'a> {
+    fn drop(&mut self) {
+        // nothing can be done if this fails
+        let _ = print(self.sheet, LEAVE_ALTERNATE_SCREEN).and_then(|_| self.sheet.flush());
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support::SharedBuffer;
+
+    #[test]
+    fn sequences() {
+        assert_eq!(cursor_down(3), "\x1b[3B");
+        assert_eq!(cursor_right(1), "\x1b[1C");
+        assert_eq!(cursor_left(0), "");
+        assert_eq!(cursor_to_column(0), "\x1b[1G");
+        let lines = vec!["a".to_string(), "b".to_string()];
+        assert_eq!(replace_lines(0, &lines), "\ra\x1b[K\nb\x1b[K\x1b[J");
+        assert_eq!(replace_lines(3, &lines), "\x1b[2A\ra\x1b[K\nb\x1b[K\x1b[J");
+    }
+
+    #[test]
+    fn printed_only_to_terminals() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::with_writer(buffer.clone());
+        print(&sheet, CLEAR_SCREEN).unwrap();
+        drop(hide_cursor(&sheet).unwrap());
+        assert_eq!(buffer.contents(), "");
+
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        {
+            let _hidden = hide_cursor(&sheet).unwrap();
+            print(&sheet, &cursor_up(1)).unwrap();
+        }
+        assert_eq!(buffer.contents(), "\x1b[?25l\x1b[1A\x1b[?25h");
+    }
+
+    #[test]
+    fn alternate_screen_guard() {
+        let buffer = SharedBuffer::default();
+        let mut sheet = Stylesheet::new();
+        sheet.set_writer_keeping_ansi(buffer.clone());
+        drop(alternate_screen(&sheet).unwrap());
+        assert_eq!(buffer.contents(), "\x1b[?1049h\x1b[2J\x1b[H\x1b[?1049l");
+        buffer.clear();
+        alternate_screen(&sheet).unwrap().leave().unwrap();
+        assert_eq!(buffer.contents(), "\x1b[?1049h\x1b[2J\x1b[H\x1b[?1049l");
+        // the screen is restored when a panic unwinds the stack
+        buffer.clear();
+        let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| {
+            let _screen = alternate_screen(&sheet).unwrap();
+            panic!("warp core breach");
+        }));
+        assert!(result.is_err());
+        assert!(buffer.contents().ends_with("\x1b[?1049l"));
+    }
+}
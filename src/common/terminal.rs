@@ -0,0 +1,405 @@
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+extern crate console;
+#[cfg(unix)]
+extern crate termios;
+
+extern crate serde;
+use self::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+
+/// The size of a terminal, in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalSize {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+/// Return the size of the terminal connected to stdout,
+/// or None if stdout is not a terminal.
+///
+/// # Example
+///
+/// ```
+/// use common::terminal::terminal_size;
+/// match terminal_size() {
+///     Some(size) => println!("{} columns, {} rows", size.columns, size.rows),
+///     None => println!("Not a terminal"),
+/// }
+/// ```
+pub fn terminal_size() -> Option<TerminalSize> {
+    console::Term::stdout().size_checked().map(|(rows, columns)| {
+        TerminalSize { columns: columns as usize, rows: rows as usize }
+    })
+}
+
+/// Return the number of columns of the terminal connected to stdout,
+/// or default if stdout is not a terminal.
+pub fn terminal_width_or(default: usize) -> usize {
+    terminal_size().map_or(default, |size| size.columns)
+}
+
+
+/// How many colors a terminal can show, from the fewest to the most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorLevel {
+    /// The 16 basic colors, like the Windows console host.
+    Basic,
+    /// The xterm 256-color palette.
+    Ansi256,
+    /// 24-bit colors.
+    TrueColor,
+}
+
+impl ColorLevel {
+    /// Names accepted by from_str(), as written in stylesheet files.
+    pub const NAMES: [&'static str; 3] = ["basic", "ansi256", "truecolor"];
+}
+
+impl FromStr for ColorLevel {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "basic" => Ok(ColorLevel::Basic),
+            "ansi256" => Ok(ColorLevel::Ansi256),
+            "truecolor" => Ok(ColorLevel::TrueColor),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for ColorLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ColorLevel::Basic => "basic",
+            ColorLevel::Ansi256 => "ansi256",
+            ColorLevel::TrueColor => "truecolor",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Color levels are serialized by name, as in stylesheet files.
+impl Serialize for ColorLevel {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ColorLevel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&name), &"a color level"))
+    }
+}
+
+
+/// The features supported by a terminal, that change how texts
+/// should be printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminalCapabilities {
+    /// How many colors the terminal can show.
+    pub color_level: ColorLevel,
+    /// True if the terminal can show characters like box-drawing lines.
+    pub unicode: bool,
+    /// True if the terminal supports OSC 8 hyperlinks.
+    pub hyperlinks: bool,
+    /// True if the terminal interprets escape sequences. This is false
+    /// for dumb terminals, and for Windows consoles where virtual terminal
+    /// processing can't be enabled.
+    pub ansi: bool,
+}
+
+impl TerminalCapabilities {
+    /// Guess the capabilities of the terminal from the environment variables
+    /// set by terminal emulators and by the locale.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::terminal::{ColorLevel, TerminalCapabilities};
+    /// let capabilities = TerminalCapabilities::detect();
+    /// if capabilities.color_level == ColorLevel::TrueColor {
+    ///     println!("16 million colors");
+    /// }
+    /// ```
+    pub fn detect() -> TerminalCapabilities {
+        TerminalCapabilities {
+            color_level: detect_color_level(),
+            unicode: detect_unicode(),
+            hyperlinks: detect_hyperlinks(),
+            ansi: env::var("TERM").map_or(true, |term| term != "dumb") && enable_ansi_support(),
+        }
+    }
+
+    /// Return the capabilities of the most limited terminals: 16 colors,
+    /// ASCII characters only, no hyperlinks.
+    pub fn minimal() -> TerminalCapabilities {
+        TerminalCapabilities { color_level: ColorLevel::Basic, unicode: false, hyperlinks: false, ansi: true }
+    }
+}
+
+/// Make sure that the consoles connected to stdout and stderr interpret
+/// escape sequences, and return true on success.
+/// On Windows, this enables virtual terminal processing. It fails on
+/// versions older than Windows 10; in that case, texts should be printed
+/// without styles. Other systems always interpret escape sequences.
+///
+/// # Example
+///
+/// ```
+/// use common::terminal::enable_ansi_support;
+/// if !enable_ansi_support() {
+///     println!("Styles are not supported by this console");
+/// }
+/// ```
+pub fn enable_ansi_support() -> bool {
+    if !cfg!(windows) {
+        return true;
+    }
+    // on Windows, colors_supported() enables virtual terminal processing
+    [console::Term::stdout(), console::Term::stderr()].iter()
+        .all(|term| !term.is_term() || term.features().colors_supported())
+}
+
+/// Return the color level declared by the terminal.
+fn detect_color_level() -> ColorLevel {
+    color_level_from(
+        &env::var("TERM").unwrap_or_default(),
+        &env::var("COLORTERM").unwrap_or_default(),
+        cfg!(windows),
+        env::var_os("WT_SESSION").is_some(),
+    )
+}
+
+/// Return the color level of a terminal, given the values of TERM and
+/// COLORTERM, whether we run on Windows and whether we run in Windows
+/// Terminal.
+fn color_level_from(term: &str, colorterm: &str, windows: bool, windows_terminal: bool) -> ColorLevel {
+    if colorterm == "truecolor" || colorterm == "24bit" || windows_terminal {
+        ColorLevel::TrueColor
+    } else if term.contains("256color") {
+        ColorLevel::Ansi256
+    } else if windows && term.is_empty() {
+        // the console host of Windows 10 supports the 256-color palette,
+        // but older versions don't support escape sequences at all
+        ColorLevel::Ansi256
+    } else {
+        ColorLevel::Basic
+    }
+}
+
+/// Return true if the terminal is known to support OSC 8 hyperlinks.
+/// Other terminals could print the escape sequences as text.
+fn detect_hyperlinks() -> bool {
+    let term_program = env::var("TERM_PROGRAM").unwrap_or_default();
+    let term = env::var("TERM").unwrap_or_default();
+    let vte_version: u32 = env::var("VTE_VERSION").ok().and_then(|v| v.parse().ok()).unwrap_or(0);
+    ["iTerm.app", "WezTerm", "vscode", "Hyper"].contains(&term_program.as_str())
+        || term.contains("kitty")
+        || term.contains("alacritty")
+        || vte_version >= 5000
+        || env::var_os("KONSOLE_VERSION").is_some()
+        || env::var_os("WT_SESSION").is_some()
+}
+
+/// Return true if the locale uses UTF-8, so that we can print
+/// characters like box-drawing lines.
+fn detect_unicode() -> bool {
+    if cfg!(windows) {
+        return true;
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default()
+        .to_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+
+/// Whether the terminal has a light or dark background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundKind {
+    Light,
+    Dark,
+    /// The terminal didn't tell us. Most terminals have a dark background.
+    Unknown,
+}
+
+impl BackgroundKind {
+    /// Guess the background of the terminal. The COLORFGBG environment
+    /// variable, set by some terminals, is used if it's present.
+    /// Otherwise, if stdin and stdout are the terminal, it is asked for
+    /// its background color, with an OSC 11 query. Terminals that don't
+    /// answer make this function wait for 100 milliseconds.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::terminal::BackgroundKind;
+    /// if BackgroundKind::detect() == BackgroundKind::Light {
+    ///     println!("Avoiding yellow");
+    /// }
+    /// ```
+    pub fn detect() -> BackgroundKind {
+        let kind = env::var("COLORFGBG").map_or(BackgroundKind::Unknown, |value| background_from_colorfgbg(&value));
+        if kind != BackgroundKind::Unknown || env::var("TERM").is_ok_and(|term| term == "dumb") {
+            return kind;
+        }
+        query_background_color().map_or(BackgroundKind::Unknown, background_from_rgb)
+    }
+}
+
+/// Return the background kind declared by COLORFGBG, which contains
+/// the foreground and background colors as numbers of the 16 basic
+/// colors, like "15;0". Some terminals add a middle component.
+fn background_from_colorfgbg(value: &str) -> BackgroundKind {
+    match value.rsplit(';').next().and_then(|background| background.parse::<u8>().ok()) {
+        Some(0..=6) | Some(8) => BackgroundKind::Dark,
+        Some(7) | Some(9..=15) => BackgroundKind::Light,
+        _ => BackgroundKind::Unknown,
+    }
+}
+
+/// Return the background kind of a background color.
+fn background_from_rgb(rgb: (u8, u8, u8)) -> BackgroundKind {
+    // relative luminance, 0-255
+    let luminance = 0.2126 * f64::from(rgb.0) + 0.7152 * f64::from(rgb.1) + 0.0722 * f64::from(rgb.2);
+    if luminance > 127.5 {
+        BackgroundKind::Light
+    } else {
+        BackgroundKind::Dark
+    }
+}
+
+/// Ask the terminal its background color with an OSC 11 query, and
+/// return it. Return None if there is no terminal or it doesn't answer.
+/// The query is only sent if stdin and stdout are both the terminal:
+/// otherwise, like in "mapper_day > log", the program may exit before
+/// the answer arrives, and the shell would read it as typed text.
+#[cfg(unix)]
+fn query_background_color() -> Option<(u8, u8, u8)> {
+    use std::fs::OpenOptions;
+    use std::io::{self, IsTerminal, Read, Write};
+    use std::os::unix::io::AsRawFd;
+    use self::termios::{tcsetattr, Termios, ECHO, ICANON, TCSANOW, VMIN, VTIME};
+
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+    let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+    let fd = tty.as_raw_fd();
+    let original = Termios::from_fd(fd).ok()?;
+    // read the answer without waiting for a newline and without showing it;
+    // every read waits for 0.1 seconds at most
+    let mut raw = original;
+    raw.c_lflag &= !(ICANON | ECHO);
+    raw.c_cc[VMIN] = 0;
+    raw.c_cc[VTIME] = 1;
+    tcsetattr(fd, TCSANOW, &raw).ok()?;
+
+    let mut answer: Vec<u8> = Vec::new();
+    if tty.write_all(b"\x1b]11;?\x07").and_then(|_| tty.flush()).is_ok() {
+        let mut byte = [0; 1];
+        // the answer ends with BEL or ST
+        while answer.len() < 64 && !answer.ends_with(b"\x07") && !answer.ends_with(b"\x1b\\") {
+            match tty.read(&mut byte) {
+                Ok(1) => answer.push(byte[0]),
+                _ => break,
+            }
+        }
+    }
+    let _ = tcsetattr(fd, TCSANOW, &original);
+    parse_osc_color(&String::from_utf8_lossy(&answer))
+}
+
+#[cfg(not(unix))]
+fn query_background_color() -> Option<(u8, u8, u8)> {
+    None
+}
+
+/// Parse the answer to an OSC 10 or OSC 11 query, like
+/// "\x1b]11;rgb:ffff/ffff/dddd\x07". Every component has 1 to 4
+/// hexadecimal digits.
+fn parse_osc_color(answer: &str) -> Option<(u8, u8, u8)> {
+    let color = answer.split("rgb:").nth(1)?.trim_end_matches(['\x07', '\x1b', '\\']);
+    let components: Vec<u8> = color.split('/')
+        .map(|component| {
+            if component.is_empty() || component.len() > 4 {
+                return None;
+            }
+            let value = u32::from_str_radix(component, 16).ok()?;
+            let max = (1 << (4 * component.len())) - 1;
+            Some((value * 255 / max) as u8)
+        })
+        .collect::<Option<_>>()?;
+    match components[..] {
+        [r, g, b] => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_level_from_str() {
+        for name in ColorLevel::NAMES.iter() {
+            let level: ColorLevel = name.parse().unwrap();
+            assert_eq!(level.to_string(), *name);
+        }
+        assert!("sepia".parse::<ColorLevel>().is_err());
+    }
+
+    #[test]
+    fn color_level_from_env() {
+        assert_eq!(color_level_from("xterm-256color", "", false, false), ColorLevel::Ansi256);
+        assert_eq!(color_level_from("xterm", "truecolor", false, false), ColorLevel::TrueColor);
+        assert_eq!(color_level_from("xterm", "", false, false), ColorLevel::Basic);
+        // Windows consoles don't set TERM
+        assert_eq!(color_level_from("", "", true, false), ColorLevel::Ansi256);
+        assert_eq!(color_level_from("", "", true, true), ColorLevel::TrueColor);
+        // msys terminals on Windows
+        assert_eq!(color_level_from("xterm", "", true, false), ColorLevel::Basic);
+    }
+
+    #[test]
+    fn ansi_support() {
+        // only Windows consoles can fail
+        if !cfg!(windows) {
+            assert!(enable_ansi_support());
+        }
+    }
+
+    #[test]
+    fn color_levels_are_ordered() {
+        assert!(ColorLevel::Basic < ColorLevel::Ansi256);
+        assert!(ColorLevel::Ansi256 < ColorLevel::TrueColor);
+    }
+
+    #[test]
+    fn background_from_env() {
+        assert_eq!(background_from_colorfgbg("15;0"), BackgroundKind::Dark);
+        assert_eq!(background_from_colorfgbg("0;15"), BackgroundKind::Light);
+        assert_eq!(background_from_colorfgbg("0;default;7"), BackgroundKind::Light);
+        assert_eq!(background_from_colorfgbg("default;default"), BackgroundKind::Unknown);
+        assert_eq!(background_from_colorfgbg(""), BackgroundKind::Unknown);
+    }
+
+    #[test]
+    fn background_from_osc() {
+        assert_eq!(parse_osc_color("\x1b]11;rgb:ffff/ffff/dddd\x07"), Some((255, 255, 221)));
+        assert_eq!(parse_osc_color("\x1b]11;rgb:1e/1e/2e\x1b\\"), Some((30, 30, 46)));
+        assert_eq!(parse_osc_color("\x1b]11;rgb:f/0/8\x07"), Some((255, 0, 136)));
+        assert_eq!(parse_osc_color(""), None);
+        assert_eq!(parse_osc_color("\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(background_from_rgb((255, 255, 221)), BackgroundKind::Light);
+        assert_eq!(background_from_rgb((30, 30, 46)), BackgroundKind::Dark);
+    }
+}
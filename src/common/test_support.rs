@@ -0,0 +1,30 @@
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+
+/// A Write implementor whose content can be read after
+/// it has been moved into a Stylesheet.
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    /// Return what was written so far.
+    pub fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+
+    /// Forget what was written so far.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
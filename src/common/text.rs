@@ -0,0 +1,206 @@
+extern crate console;
+
+use ansi;
+
+
+/// Return text truncated so that it occupies at most max_cols columns,
+/// ending with "…" if something was removed. Widths are measured as
+/// the terminal shows characters, so wide characters count as two
+/// columns. Escape sequences are never cut, and the ones after the
+/// truncation point are kept, so styles are still reset properly.
+///
+/// # Arguments
+///
+/// * `text` - The text to truncate, possibly containing escape sequences.
+/// * `max_cols` - The maximum number of columns.
+///
+/// # Example
+///
+/// ```
+/// use common::text::truncate;
+/// assert_eq!(truncate("Romulan ship approaching", 10), "Romulan s…");
+/// ```
+pub fn truncate(text: &str, max_cols: usize) -> String {
+    const ELLIPSIS: &str = "…";
+    if visible_width(text) <= max_cols {
+        return text.to_string();
+    }
+    let tail = if max_cols == 0 { "" } else { ELLIPSIS };
+    let max_cols = max_cols - visible_width(tail);
+    let mut result = String::with_capacity(text.len());
+    let mut cols = 0;
+    let mut truncated = false;
+    let mut buffer = [0; 4];
+    for segment in ansi::segments(text) {
+        match segment {
+            // copied whole, so that sequences like hyperlinks are terminated
+            ansi::Segment::Escape(sequence) => result.push_str(sequence),
+            ansi::Segment::Text(_) if truncated => (),
+            ansi::Segment::Text(part) => {
+                for c in part.chars() {
+                    let width = console::measure_text_width(c.encode_utf8(&mut buffer));
+                    if cols + width > max_cols {
+                        truncated = true;
+                        result.push_str(tail);
+                        break;
+                    }
+                    cols += width;
+                    result.push(c);
+                }
+            },
+        }
+    }
+    result
+}
+
+
+/// Return the number of columns that text occupies on a terminal.
+/// Escape sequences, including hyperlinks, are not counted, and wide
+/// characters count as two columns. Use it to align texts that are
+/// already styled, for example in tables.
+///
+/// # Arguments
+///
+/// * `text` - The text to measure, possibly containing escape sequences.
+///
+/// # Example
+///
+/// ```
+/// use common::text::visible_width;
+/// assert_eq!(visible_width("\x1b[31mRomulan\x1b[0m"), 7);
+/// assert_eq!(visible_width("日本"), 4);
+/// ```
+pub fn visible_width(text: &str) -> usize {
+    console::measure_text_width(&ansi::strip(text))
+}
+
+
+/// Match pattern against text as a fuzzy search does: the characters of
+/// pattern must appear in text in the same order, but not necessarily
+/// next to each other. The comparison is case insensitive. Return None
+/// if text doesn't match, or a score and the byte offsets of the matched
+/// characters of text. Higher scores are better matches: consecutive
+/// characters and characters at the beginning of words score more,
+/// characters skipped between the matches score less.
+/// An empty pattern matches any text with a score of 0.
+///
+/// # Arguments
+///
+/// * `pattern` - The text typed by the user.
+/// * `text` - The text to search.
+///
+/// # Example
+///
+/// ```
+/// use common::text::fuzzy_match;
+/// let (_, offsets) = fuzzy_match("wc", "warp core breach").unwrap();
+/// assert_eq!(offsets, vec![0, 5]);
+/// assert!(fuzzy_match("cw", "warp core breach").is_none());
+/// ```
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    if pattern.is_empty() {
+        return Some((0, Vec::new()));
+    }
+    let matches = |i: usize, pattern_char: char| chars[i].1.to_lowercase().eq(pattern_char.to_lowercase());
+    let word_start = |i: usize| {
+        i == 0 || !chars[i - 1].1.is_alphanumeric() || (chars[i - 1].1.is_lowercase() && chars[i].1.is_uppercase())
+    };
+
+    // try every position of the first character, and keep the best match;
+    // the other characters are matched as soon as possible
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..chars.len()).filter(|&i| matches(i, pattern[0])) {
+        let mut positions = vec![start];
+        let mut i = start + 1;
+        for &pattern_char in &pattern[1..] {
+            while i < chars.len() && !matches(i, pattern_char) {
+                i += 1;
+            }
+            if i == chars.len() {
+                break;
+            }
+            positions.push(i);
+            i += 1;
+        }
+        if positions.len() < pattern.len() {
+            // later starts can't match more characters
+            break;
+        }
+        let mut score = 0;
+        for (n, &position) in positions.iter().enumerate() {
+            score += 1;
+            if word_start(position) {
+                score += 8;
+            }
+            if n > 0 {
+                let gap = (position - positions[n - 1] - 1) as i64;
+                score += if gap == 0 { 8 } else { -gap.min(5) };
+            }
+        }
+        if best.as_ref().is_none_or(|&(best_score, _)| score > best_score) {
+            best = Some((score, positions));
+        }
+    }
+    best.map(|(score, positions)| (score, positions.iter().map(|&i| chars[i].0).collect()))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_plain_text() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate("Romulan ship", 5), "Romu…");
+        assert_eq!(truncate("Romulan ship", 1), "…");
+        assert_eq!(truncate("Romulan ship", 0), "");
+    }
+
+    #[test]
+    fn truncate_wide_characters() {
+        // every character takes 2 columns
+        assert_eq!(truncate("日本語テキスト", 6), "日本…");
+        assert_eq!(truncate("日本語テキスト", 5), "日本…");
+    }
+
+    #[test]
+    fn truncate_escape_sequences() {
+        // escape sequences are not counted, and the final reset is kept
+        assert_eq!(truncate("\x1b[31mRomulan\x1b[0m", 7), "\x1b[31mRomulan\x1b[0m");
+        assert_eq!(truncate("\x1b[31mRomulan\x1b[0m", 4), "\x1b[31mRom…\x1b[0m");
+        // hyperlinks are not cut, and stay terminated
+        assert_eq!(
+            truncate("\x1b]8;;https://example.com\x1b\\Romulan\x1b]8;;\x1b\\", 6),
+            "\x1b]8;;https://example.com\x1b\\Romul…\x1b]8;;\x1b\\"
+        );
+    }
+
+    #[test]
+    fn visible_width_of_styled_text() {
+        assert_eq!(visible_width(""), 0);
+        assert_eq!(visible_width("Romulan"), 7);
+        assert_eq!(visible_width("\x1b[1;38;2;255;0;0mé日\x1b[0m"), 3);
+        // hyperlinks must not count
+        assert_eq!(visible_width("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"), 4);
+    }
+
+    #[test]
+    fn fuzzy_matches() {
+        assert_eq!(fuzzy_match("", "anything"), Some((0, vec![])));
+        assert_eq!(fuzzy_match("WARP", "warp core").unwrap().1, vec![0, 1, 2, 3]);
+        assert!(fuzzy_match("pw", "warp").is_none());
+        // the beginning of words is preferred to the first occurrence
+        assert_eq!(fuzzy_match("cb", "ocean core breach").unwrap().1, vec![6, 11]);
+        assert_eq!(fuzzy_match("cb", "warpCoreBreach").unwrap().1, vec![4, 8]);
+        // consecutive characters score more
+        let close = fuzzy_match("core", "warp core").unwrap().0;
+        let scattered = fuzzy_match("core", "a cat or eel").unwrap().0;
+        assert!(close > scattered);
+        // offsets are in bytes
+        assert_eq!(fuzzy_match("é", "café").unwrap().1, vec![3]);
+    }
+}
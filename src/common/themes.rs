@@ -0,0 +1,280 @@
+use std::fmt;
+use std::str::FromStr;
+
+use stylesheet::{ConflictPolicy, Stylesheet, StylesheetError, StyleProperties};
+use stylesheet::StyleColor::*;
+use terminal::BackgroundKind;
+
+extern crate serde;
+use self::serde::{de, Deserialize, Deserializer};
+
+
+/// The style names defined by every built-in theme.
+pub const STYLE_NAMES: [&str; 4] = ["danger", "warning", "info", "success"];
+
+
+/// The built-in themes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Dark,
+    Light,
+    Monochrome,
+    Solarized,
+    ColorblindSafe,
+    HighContrast,
+}
+
+impl Theme {
+    /// Names accepted by from_str(), for example in a "theme" command.
+    pub const NAMES: [&'static str; 6] = ["dark", "light", "monochrome", "solarized", "colorblind", "high_contrast"];
+
+    /// Return a new stylesheet containing this theme.
+    pub fn stylesheet(self) -> Stylesheet {
+        match self {
+            Theme::Dark => Stylesheet::theme_dark(),
+            Theme::Light => Stylesheet::theme_light(),
+            Theme::Monochrome => Stylesheet::theme_monochrome(),
+            Theme::Solarized => Stylesheet::theme_solarized(),
+            Theme::ColorblindSafe => Stylesheet::theme_colorblind(),
+            Theme::HighContrast => Stylesheet::theme_high_contrast(),
+        }
+    }
+
+    /// Return the theme that is readable on a background: Light for
+    /// light backgrounds, Dark otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `background` - The background of the terminal.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use common::terminal::BackgroundKind;
+    /// use common::themes::Theme;
+    /// let sheet = Theme::for_background(BackgroundKind::detect()).stylesheet();
+    /// sheet.println("warning", "Readable on any background");
+    /// ```
+    pub fn for_background(background: BackgroundKind) -> Theme {
+        match background {
+            BackgroundKind::Light => Theme::Light,
+            BackgroundKind::Dark | BackgroundKind::Unknown => Theme::Dark,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "dark" => Ok(Theme::Dark),
+            "light" => Ok(Theme::Light),
+            "monochrome" => Ok(Theme::Monochrome),
+            "solarized" => Ok(Theme::Solarized),
+            "colorblind" => Ok(Theme::ColorblindSafe),
+            "high_contrast" => Ok(Theme::HighContrast),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+            Theme::Monochrome => "monochrome",
+            Theme::Solarized => "solarized",
+            Theme::ColorblindSafe => "colorblind",
+            Theme::HighContrast => "high_contrast",
+        };
+        f.write_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Theme {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(|_| de::Error::invalid_value(de::Unexpected::Str(&name), &"a theme name"))
+    }
+}
+
+
+impl Stylesheet {
+    /// Return a stylesheet with the styles listed in STYLE_NAMES,
+    /// meant for terminals with a dark background.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// let sheet = Stylesheet::theme_dark();
+    /// sheet.println("success", "All systems nominal");
+    /// ```
+    pub fn theme_dark() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().bold().color(BrightRed).build()),
+            ("warning", StyleProperties::builder().color(BrightYellow).build()),
+            ("info", StyleProperties::builder().color(BrightCyan).build()),
+            ("success", StyleProperties::builder().color(BrightGreen).build()),
+        ]);
+        sheet
+    }
+
+    /// Return a stylesheet with the styles listed in STYLE_NAMES,
+    /// meant for terminals with a light background.
+    /// Yellow is hard to read on white, so warnings are magenta.
+    pub fn theme_light() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().bold().color(Red).build()),
+            ("warning", StyleProperties::builder().color(Magenta).build()),
+            ("info", StyleProperties::builder().color(Blue).build()),
+            ("success", StyleProperties::builder().color(Green).build()),
+        ]);
+        sheet
+    }
+
+    /// Return a stylesheet with the styles listed in STYLE_NAMES,
+    /// that only uses transformations. It works with any background
+    /// and with terminals that don't support colors.
+    pub fn theme_monochrome() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().bold().reverse().build()),
+            ("warning", StyleProperties::builder().bold().underlined().build()),
+            ("info", StyleProperties::default()),
+            ("success", StyleProperties::builder().bold().build()),
+        ]);
+        sheet
+    }
+
+    /// Return a stylesheet with the styles listed in STYLE_NAMES,
+    /// using the accent colors of the Solarized palette.
+    /// Terminals without 256 colors show the closest basic colors.
+    pub fn theme_solarized() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().bold().color(Fixed(160)).build()),
+            ("warning", StyleProperties::builder().color(Fixed(136)).build()),
+            ("info", StyleProperties::builder().color(Fixed(33)).build()),
+            ("success", StyleProperties::builder().color(Fixed(64)).build()),
+        ]);
+        sheet
+    }
+
+    /// Return a stylesheet with the styles listed in STYLE_NAMES,
+    /// using colors of the Okabe-Ito palette, that people with any
+    /// kind of color blindness can tell apart. Red and green are
+    /// not used; danger is also bold.
+    pub fn theme_colorblind() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            // vermillion, orange, sky blue, blue
+            ("danger", StyleProperties::builder().bold().color(Rgb(213, 94, 0)).build()),
+            ("warning", StyleProperties::builder().color(Rgb(230, 159, 0)).build()),
+            ("info", StyleProperties::builder().color(Rgb(86, 180, 233)).build()),
+            ("success", StyleProperties::builder().color(Rgb(0, 114, 178)).build()),
+        ]);
+        sheet
+    }
+
+    /// Return a stylesheet with the styles listed in STYLE_NAMES,
+    /// for users with low vision: every style is bold, with a bright
+    /// background and the text color that contrasts with it the most.
+    pub fn theme_high_contrast() -> Stylesheet {
+        let mut sheet = Stylesheet::new();
+        sheet.add_styles(vec![
+            ("danger", StyleProperties::builder().bold().color(BrightWhite).background(Red).build()),
+            ("warning", StyleProperties::builder().bold().color(Black).background(BrightYellow).build()),
+            ("info", StyleProperties::builder().bold().color(BrightWhite).background(Blue).build()),
+            ("success", StyleProperties::builder().bold().color(Black).background(BrightGreen).build()),
+        ]);
+        sheet
+    }
+
+    /// Replace the styles listed in STYLE_NAMES with the ones of a
+    /// built-in theme. Other styles are not modified, and styles that
+    /// extend the replaced styles will follow the new theme.
+    /// Settings like the ColorMode are not modified.
+    ///
+    /// # Arguments
+    ///
+    /// * `theme` - The theme to switch to.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use common::stylesheet::*;
+    /// use common::themes::Theme;
+    /// let mut sheet = Stylesheet::theme_dark();
+    /// sheet.load_theme(Theme::Light).unwrap();
+    /// sheet.println("warning", "Now readable on a white background");
+    /// ```
+    pub fn load_theme(&mut self, theme: Theme) -> Result<(), StylesheetError> {
+        self.merge(&theme.stylesheet(), ConflictPolicy::Override)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylesheet::ColorMode;
+
+    #[test]
+    fn themes_define_all_styles() {
+        let mut themes = [
+            Stylesheet::theme_dark(),
+            Stylesheet::theme_light(),
+            Stylesheet::theme_solarized(),
+            Stylesheet::theme_colorblind(),
+            Stylesheet::theme_high_contrast(),
+        ];
+        for theme in themes.iter_mut() {
+            theme.set_color_mode(ColorMode::Always);
+            for style_name in STYLE_NAMES.iter() {
+                // must not fall back to the default style
+                assert_ne!(theme.format(style_name, "x"), "x");
+            }
+        }
+    }
+
+    #[test]
+    fn load_theme() {
+        let mut sheet = Stylesheet::theme_dark();
+        sheet.set_color_mode(ColorMode::Always);
+        sheet.add_style_extending("critical", "danger", StyleProperties::builder().blink().build()).unwrap();
+        sheet.add_style("custom", StyleProperties::builder().italic().build());
+        sheet.load_theme(Theme::Light).unwrap();
+        assert_eq!(sheet.format("danger", "x"), "\x1b[1;31mx\x1b[0m");
+        // extending styles follow the theme, other styles are unchanged
+        assert_eq!(sheet.format("critical", "x"), "\x1b[1;5;31mx\x1b[0m");
+        assert_eq!(sheet.format("custom", "x"), "\x1b[3mx\x1b[0m");
+        assert_eq!(sheet.color_mode(), ColorMode::Always);
+    }
+
+    #[test]
+    fn theme_from_str() {
+        for name in Theme::NAMES.iter() {
+            let theme: Theme = name.parse().unwrap();
+            assert_eq!(theme.to_string(), *name);
+        }
+        assert!("neon".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn theme_dark() {
+        let mut sheet = Stylesheet::theme_dark();
+        sheet.set_color_mode(ColorMode::Always);
+        assert_eq!(sheet.format("danger", "x"), "\x1b[1;91mx\x1b[0m");
+    }
+
+    #[test]
+    fn theme_for_background() {
+        assert_eq!(Theme::for_background(BackgroundKind::Light), Theme::Light);
+        assert_eq!(Theme::for_background(BackgroundKind::Unknown), Theme::Dark);
+    }
+}
@@ -0,0 +1,125 @@
+use stylesheet::Stylesheet;
+
+
+/// The style of the branches that connect the nodes of a tree.
+pub const BRANCH_STYLE: &str = "tree_branch";
+
+/// Return the name of the style used for the nodes at a depth that have
+/// no style of their own: "tree_depth_0" for the root, "tree_depth_1"
+/// for its children, and so on.
+pub fn depth_style(depth: usize) -> String {
+    format!("tree_depth_{}", depth)
+}
+
+
+/// A node of a tree printed by Stylesheet::print_tree(), with a label
+/// and any number of children. A node can have its own style; otherwise
+/// the style of its depth is used, if the Stylesheet contains it.
+/// See depth_style().
+///
+/// # Example
+///
+/// ```
+/// use common::stylesheet::Stylesheet;
+/// use common::tree::TreeNode;
+/// let sheet = Stylesheet::theme_dark();
+/// let root = TreeNode::new("Sector 001")
+///     .child(TreeNode::new("Sol")
+///         .child(TreeNode::new("USS Enterprise").with_style("info"))
+///         .child(TreeNode::new("Borg cube").with_style("danger")))
+///     .child(TreeNode::new("Wolf 359"));
+/// sheet.print_tree(&root);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeNode {
+    pub label: String,
+    /// The style of the label, or None to use the style of the depth.
+    pub style: Option<String>,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// Return a node without children.
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - The text of the node. It can span several lines.
+    pub fn new<S>(label: S) -> TreeNode where S: Into<String> {
+        TreeNode { label: label.into(), style: None, children: Vec::new() }
+    }
+
+    /// Return the node with a style for its label.
+    pub fn with_style(mut self, style_name: &str) -> TreeNode {
+        self.style = Some(style_name.to_string());
+        self
+    }
+
+    /// Return the node with a child added after the others.
+    pub fn child(mut self, child: TreeNode) -> TreeNode {
+        self.children.push(child);
+        self
+    }
+
+    /// Add a child after the others.
+    pub fn push(&mut self, child: TreeNode) {
+        self.children.push(child);
+    }
+}
+
+
+/// Return a tree, one node per line. See Stylesheet::format_tree().
+///
+/// # Arguments
+///
+/// * `sheet` - The Stylesheet containing the styles.
+/// * `root` - The root of the tree, printed on the first line.
+pub fn format_tree(sheet: &Stylesheet, root: &TreeNode) -> String {
+    let mut lines = Vec::new();
+    tree_lines(sheet, root, 0, "", "", &mut lines);
+    lines.join("\n")
+}
+
+/// Add the lines of node and its descendants to lines.
+/// first_prefix precedes the first line of the label, and prefix
+/// precedes the other lines, included the lines of the children.
+fn tree_lines(sheet: &Stylesheet, node: &TreeNode, depth: usize, first_prefix: &str, prefix: &str, lines: &mut Vec<String>) {
+    let (branch, last_branch, vertical, space) = if sheet.unicode_enabled() {
+        ("├── ", "└── ", "│   ", "    ")
+    } else {
+        ("|-- ", "`-- ", "|   ", "    ")
+    };
+    let depth_style = depth_style(depth);
+    let style_name = node.style.as_ref().unwrap_or(&depth_style);
+
+    // a node with an empty label still has its line, with its branch
+    let mut label_lines: Vec<&str> = node.label.lines().collect();
+    if label_lines.is_empty() {
+        label_lines.push("");
+    }
+    for (i, line) in label_lines.into_iter().enumerate() {
+        // under the label, the branch continues if there are children
+        let line_prefix = match (i, node.children.is_empty()) {
+            (0, _) => first_prefix.to_string(),
+            (_, true) => format!("{}{}", prefix, space),
+            (_, false) => format!("{}{}", prefix, vertical),
+        };
+        lines.push(format!("{}{}", paint(sheet, BRANCH_STYLE, &line_prefix), paint(sheet, style_name, line)));
+    }
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let child_first_prefix = format!("{}{}", prefix, if last { last_branch } else { branch });
+        let child_prefix = format!("{}{}", prefix, if last { space } else { vertical });
+        tree_lines(sheet, child, depth + 1, &child_first_prefix, &child_prefix, lines);
+    }
+}
+
+/// Return text with a style, or as it is if the style doesn't exist:
+/// the styles of trees are optional, so they don't trigger the
+/// FallbackPolicy.
+fn paint(sheet: &Stylesheet, style_name: &str, text: &str) -> String {
+    if sheet.contains(style_name) && !text.is_empty() {
+        sheet.format(style_name, text)
+    } else {
+        text.to_string()
+    }
+}